@@ -59,7 +59,7 @@ use clap::{App, Arg, ArgMatches};
 use maidsafe_utilities::{log, SeededRng};
 use parsec::{
     mock::{PeerId, Transaction},
-    Block, ConsensusMode, Parsec, Request,
+    Block, Parsec, ParsecConfig, Request,
 };
 use rand::Rng;
 use std::{
@@ -120,7 +120,7 @@ impl Peer {
                 our_id,
                 genesis_group,
                 vec![],
-                ConsensusMode::Supermajority,
+                ParsecConfig::default(),
                 secure_rng,
             ),
             observations: vec![],
@@ -140,7 +140,7 @@ impl Peer {
                 our_id,
                 genesis_group,
                 section,
-                ConsensusMode::Supermajority,
+                ParsecConfig::default(),
                 secure_rng,
             ),
             observations: vec![],
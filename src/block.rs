@@ -7,24 +7,39 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
+    config::QuorumPolicy,
     error::Error,
+    gossip::EventHash,
+    hash::Hash,
     id::{Proof, PublicId},
     network_event::NetworkEvent,
-    observation::Observation,
+    observation::{ConsensusMode, Observation, ObservationHash},
+    serialise,
     vote::Vote,
     DkgResult, DkgResultWrapper,
 };
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::{
     collections::{vec_deque, BTreeMap, BTreeSet, VecDeque},
+    fmt::{self, Debug, Formatter},
     ops::{Deref, DerefMut},
 };
+use threshold_crypto::{
+    serde_impl::SerdeSecret, PublicKeySet, SecretKeyShare, Signature, SignatureShare,
+};
 
 /// A struct representing a collection of votes by peers for an `Observation`.
+///
+/// The serialised form includes the optional index, signature and chain link of the block, which
+/// makes it incompatible with the one of version 0.5.0 in both directions.
 #[serde(bound = "")]
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Debug)]
 pub struct Block<T: NetworkEvent, P: PublicId> {
     payload: Observation<T, P>,
     proofs: BTreeSet<Proof<P>>,
+    index: Option<u64>,
+    signature: Option<Signature>,
+    chain_link: Option<ChainLink>,
 }
 
 impl<T: NetworkEvent, P: PublicId> Block<T, P> {
@@ -36,6 +51,9 @@ impl<T: NetworkEvent, P: PublicId> Block<T, P> {
                 dkg_result: DkgResultWrapper(dkg_result),
             },
             proofs: BTreeSet::new(),
+            index: None,
+            signature: None,
+            chain_link: None,
         }
     }
 
@@ -59,7 +77,13 @@ impl<T: NetworkEvent, P: PublicId> Block<T, P> {
             .collect();
         let proofs = proofs?;
 
-        Ok(Self { payload, proofs })
+        Ok(Self {
+            payload,
+            proofs,
+            index: None,
+            signature: None,
+            chain_link: None,
+        })
     }
 
     /// Returns the payload of this block.
@@ -72,6 +96,119 @@ impl<T: NetworkEvent, P: PublicId> Block<T, P> {
         &self.proofs
     }
 
+    /// Verifies that this block was legitimately consensused by the given voters, i.e. that every
+    /// proof is a valid signature of the payload by one of the voters, and that the signers are
+    /// more than two thirds of the voters, or are the single creator of the observation if it was
+    /// consensused in `ConsensusMode::Single`.
+    ///
+    /// `consensus_mode` is the one the `Parsec` instance was created with; internal observations
+    /// always require a supermajority, as when they are consensused. `DkgResult` blocks carry no
+    /// proofs and so can't be verified this way. For sections configured with another
+    /// [`QuorumPolicy`](struct.QuorumPolicy.html), use
+    /// [`verify_with_policy`](#method.verify_with_policy) instead.
+    pub fn verify(&self, voters: &BTreeSet<P>, consensus_mode: ConsensusMode) -> Result<(), Error> {
+        self.verify_with_policy(voters, consensus_mode, QuorumPolicy::default())
+    }
+
+    /// Same as [`verify`](#method.verify), but the signers have to be a quorum of the voters as
+    /// per `quorum_policy`, the one the `Parsec` instance was configured with.
+    pub fn verify_with_policy(
+        &self,
+        voters: &BTreeSet<P>,
+        consensus_mode: ConsensusMode,
+        quorum_policy: QuorumPolicy,
+    ) -> Result<(), Error> {
+        let voters = voters.iter().map(|voter| (voter.clone(), 1)).collect();
+        self.verify_weighted(&voters, consensus_mode, quorum_policy)
+    }
+
+    /// Same as [`verify_with_policy`](#method.verify_with_policy), but for voters whose weights
+    /// were changed by `Observation::ChangeWeight`: the quorum is reached when the total weight of
+    /// the signers is a quorum of the total weight of the voters. Returns `Error::InvalidWeight`
+    /// if the total weight overflows.
+    pub fn verify_weighted(
+        &self,
+        voters: &BTreeMap<P, usize>,
+        consensus_mode: ConsensusMode,
+        quorum_policy: QuorumPolicy,
+    ) -> Result<(), Error> {
+        if self.proofs.is_empty() {
+            return Err(Error::MissingVotes);
+        }
+
+        let data = serialise(&self.payload);
+        let mut signers_weight: usize = 0;
+        for proof in &self.proofs {
+            let weight = voters.get(proof.public_id()).ok_or(Error::UnknownPeer)?;
+            if !proof.is_valid(&data) {
+                return Err(Error::SignatureFailure);
+            }
+            signers_weight = signers_weight
+                .checked_add(*weight)
+                .ok_or(Error::InvalidWeight)?;
+        }
+
+        let has_consensus = match consensus_mode.of(&self.payload) {
+            ConsensusMode::Single => self.proofs.len() == 1,
+            ConsensusMode::Supermajority => {
+                let voters_weight = voters
+                    .values()
+                    .try_fold(0usize, |sum, weight| sum.checked_add(*weight))
+                    .ok_or(Error::InvalidWeight)?;
+                quorum_policy.is_quorum(signers_weight, voters_weight)
+            }
+        };
+        if has_consensus {
+            Ok(())
+        } else {
+            Err(Error::InsufficientVotes)
+        }
+    }
+
+    /// Returns the threshold signature of this block by the section, if it has one. See
+    /// [`ParsecConfig::with_block_signatures`](
+    /// struct.ParsecConfig.html#method.with_block_signatures).
+    pub fn signature(&self) -> Option<&Signature> {
+        self.signature.as_ref()
+    }
+
+    /// Returns whether this block carries a threshold signature valid for the given public key
+    /// set, i.e. signed by the section which generated the keys. The public key set is the one of
+    /// a `DkgResult` output in an earlier block.
+    pub fn verify_with(&self, public_key_set: &PublicKeySet) -> bool {
+        match (self.index, self.signature.as_ref()) {
+            (Some(index), Some(signature)) => public_key_set.public_key().verify(
+                signature,
+                signed_content(index, self.previous_hash().as_ref(), &self.payload_hash()),
+            ),
+            _ => false,
+        }
+    }
+
+    /// Returns the number of blocks the section consensused before this one, if block signatures
+    /// are on. The threshold signature covers it, as well as the hash of the previous block if the
+    /// blocks are chained, so a signed block can't be replayed at another position of the
+    /// section's history.
+    pub fn index(&self) -> Option<u64> {
+        self.index
+    }
+
+    /// Returns the position of this block in the section's history, if the blocks are chained.
+    /// See [`ParsecConfig::with_chained_blocks`](
+    /// struct.ParsecConfig.html#method.with_chained_blocks).
+    pub fn chain_link(&self) -> Option<&ChainLink> {
+        self.chain_link.as_ref()
+    }
+
+    /// Returns the hash of this block, which the next block of the chain links to. It covers the
+    /// payload and the chain link, but neither the proofs nor the signature, which aren't the
+    /// same for every peer.
+    pub fn hash(&self) -> BlockHash {
+        BlockHash(Hash::from(
+            serialise(&(self.payload_hash(), &self.chain_link)).as_slice(),
+        ))
+    }
+
     /// Is this block signed by the given peer?
     pub fn is_signed_by(&self, peer_id: &P) -> bool {
         self.proofs.iter().any(|proof| proof.public_id() == peer_id)
@@ -88,24 +225,230 @@ impl<T: NetworkEvent, P: PublicId> Block<T, P> {
         let proof = vote.create_proof(peer_id)?;
         Ok(self.proofs.insert(proof))
     }
+
+    pub(crate) fn payload_hash(&self) -> ObservationHash {
+        ObservationHash::from(&self.payload)
+    }
+
+    // Hash of the block before this one, if the blocks are chained and this isn't the first one.
+    pub(crate) fn previous_hash(&self) -> Option<BlockHash> {
+        self.chain_link.and_then(|link| link.previous)
+    }
+
+    pub(crate) fn set_index(&mut self, index: u64) {
+        self.index = Some(index);
+    }
+
+    pub(crate) fn set_signature(&mut self, signature: Signature) {
+        self.signature = Some(signature);
+    }
+
+    pub(crate) fn set_chain_link(&mut self, chain_link: ChainLink) {
+        self.chain_link = Some(chain_link);
+    }
+
+    fn secret_key_share(&self) -> Option<&SecretKeyShare> {
+        match self.payload {
+            Observation::DkgResult { ref dkg_result, .. } => dkg_result.0.secret_key_share.as_ref(),
+            _ => None,
+        }
+    }
+
+    fn set_secret_key_share(&mut self, secret_key_share: SecretKeyShare) {
+        if let Observation::DkgResult {
+            ref mut dkg_result, ..
+        } = self.payload
+        {
+            dkg_result.0.secret_key_share = Some(secret_key_share);
+        }
+    }
+}
+
+/// Hash of a `Block`. See [`Block::hash`](struct.Block.html#method.hash).
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct BlockHash(Hash);
+
+impl Debug for BlockHash {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        self.0.fmt(formatter)
+    }
+}
+
+/// Position of a `Block` in the history of the section: its sequence number, counting from the
+/// genesis block, and the hash of the block before it.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Debug)]
+pub struct ChainLink {
+    /// Number of blocks output before this one.
+    pub index: u64,
+    /// Hash of the previous block, or `None` for the genesis block.
+    pub previous: Option<BlockHash>,
+}
+
+impl ChainLink {
+    // Returns the link of the block following the one with the given link and hash.
+    pub(crate) fn next(previous: Option<(&ChainLink, BlockHash)>) -> Self {
+        match previous {
+            Some((link, hash)) => ChainLink {
+                index: link.index + 1,
+                previous: Some(hash),
+            },
+            None => ChainLink {
+                index: 0,
+                previous: None,
+            },
+        }
+    }
+}
+
+/// Checks that `blocks` form an unbroken segment of the section's history, i.e. that each block
+/// links to the one before it. The first block may be anywhere in the history; it's up to the
+/// caller to check it against the last block it knows of.
+pub fn verify_chain<'a, T, P, I>(blocks: I) -> Result<(), Error>
+where
+    T: NetworkEvent + 'a,
+    P: PublicId + 'a,
+    I: IntoIterator<Item = &'a Block<T, P>>,
+{
+    let mut previous = None;
+    for block in blocks {
+        let link = block.chain_link().ok_or(Error::BrokenChain)?;
+        if previous.is_some() && *link != ChainLink::next(previous) {
+            return Err(Error::BrokenChain);
+        }
+        previous = Some((link, block.hash()));
+    }
+    Ok(())
+}
+
+/// Share of the threshold signature of a block, by one of the peers holding a share of the
+/// section's secret key.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Debug)]
+pub struct BlockSignatureShare {
+    pub(crate) index: u64,
+    pub(crate) previous: Option<BlockHash>,
+    pub(crate) payload_hash: ObservationHash,
+    pub(crate) share: SignatureShare,
+}
+
+// Tag of the content of block signatures, so that they can't pass for signatures of anything else
+// made with the same keys, such as common coin shares.
+const SIGNATURE_DOMAIN: &str = "parsec-block-signature";
+
+// The content signed by the threshold signature of the block with the given index, previous block
+// hash and payload hash.
+pub(crate) fn signed_content(
+    index: u64,
+    previous: Option<&BlockHash>,
+    payload_hash: &ObservationHash,
+) -> Vec<u8> {
+    serialise(&(SIGNATURE_DOMAIN, index, previous, payload_hash))
+}
+
+/// Blocks consensused within the same meta-election, in the consensused order. They should be
+/// applied together.
+#[serde(bound = "")]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct ConsensusBatch<T: NetworkEvent, P: PublicId> {
+    blocks: Vec<Block<T, P>>,
+    election_index: usize,
+    deciding_event: EventHash,
+}
+
+impl<T: NetworkEvent, P: PublicId> ConsensusBatch<T, P> {
+    /// Returns the blocks of this batch.
+    pub fn blocks(&self) -> &[Block<T, P>] {
+        &self.blocks
+    }
+
+    /// Converts this batch into its blocks.
+    pub fn into_blocks(self) -> Vec<Block<T, P>> {
+        self.blocks
+    }
+
+    /// Returns the index of the meta-election which decided this batch, i.e. the number of
+    /// observations consensused in the earlier meta-elections. It is the same on every peer, and
+    /// increases from one meta-election to the next.
+    ///
+    /// A batch holding the `DkgResult` of a DKG completed by the consensus of a meta-election has
+    /// the same index as the batch of that meta-election.
+    pub fn election_index(&self) -> usize {
+        self.election_index
+    }
+
+    /// Returns the hash of the event on which the meta-election reached consensus.
+    pub fn deciding_event(&self) -> &EventHash {
+        &self.deciding_event
+    }
+}
+
+impl<T: NetworkEvent, P: PublicId> From<BlockGroup<T, P>> for ConsensusBatch<T, P> {
+    fn from(block_group: BlockGroup<T, P>) -> Self {
+        Self {
+            blocks: block_group.blocks.into_iter().collect(),
+            election_index: block_group.election_index,
+            deciding_event: block_group.deciding_event,
+        }
+    }
+}
+
+/// Receiver of the stable blocks, which `Parsec` hands them to as soon as they are consensused,
+/// as an alternative to polling for them. See
+/// [Parsec::set_block_sink](struct.Parsec.html#method.set_block_sink).
+pub trait BlockSink<T: NetworkEvent, P: PublicId> {
+    /// Called with each stable block, in the consensused order.
+    fn handle_block(&mut self, block: Block<T, P>);
+
+    /// Called with the blocks decided within the same meta-election, i.e. with each batch
+    /// [Parsec::batch_poll](struct.Parsec.html#method.batch_poll) would return. By default, passes
+    /// them to `handle_block` one by one.
+    fn handle_batch(&mut self, batch: ConsensusBatch<T, P>) {
+        for block in batch.into_blocks() {
+            self.handle_block(block);
+        }
+    }
 }
 
 /// Group of blocks that were all created within the same meta-election.
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
-pub(crate) struct BlockGroup<T: NetworkEvent, P: PublicId>(pub VecDeque<Block<T, P>>);
+pub(crate) struct BlockGroup<T: NetworkEvent, P: PublicId> {
+    pub blocks: VecDeque<Block<T, P>>,
+    // Length of the consensus history before the meta-election which decided the blocks.
+    pub election_index: usize,
+    // Event on which the meta-election which decided the blocks reached consensus.
+    pub deciding_event: EventHash,
+}
 
-impl<T: NetworkEvent, P: PublicId> BlockGroup<T, P> {
-    pub fn is_empty(&self) -> bool {
-        self.0.is_empty()
+// Unlike the `Serialize` impl of `Block`, this keeps the secret key shares of `DkgResult` payloads.
+// It is only used to persist our own state (see `Parsec::save`), never to send blocks to peers.
+impl<T: NetworkEvent, P: PublicId> Serialize for BlockGroup<T, P> {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let blocks: Vec<_> = self
+            .blocks
+            .iter()
+            .map(|block| (block, block.secret_key_share().map(SerdeSecret)))
+            .collect();
+        (blocks, self.election_index, &self.deciding_event).serialize(s)
     }
 }
 
-impl<T: NetworkEvent, P: PublicId> IntoIterator for BlockGroup<T, P> {
-    type Item = Block<T, P>;
-    type IntoIter = vec_deque::IntoIter<Block<T, P>>;
-
-    fn into_iter(self) -> Self::IntoIter {
-        self.0.into_iter()
+impl<'a, T: NetworkEvent, P: PublicId> Deserialize<'a> for BlockGroup<T, P> {
+    fn deserialize<D: Deserializer<'a>>(deserializer: D) -> Result<Self, D::Error> {
+        type SavedBlocks<T, P> = Vec<(Block<T, P>, Option<SerdeSecret<SecretKeyShare>>)>;
+        let (blocks, election_index, deciding_event): (SavedBlocks<T, P>, usize, EventHash) =
+            Deserialize::deserialize(deserializer)?;
+        Ok(BlockGroup {
+            blocks: blocks
+                .into_iter()
+                .map(|(mut block, secret_key_share)| {
+                    if let Some(secret_key_share) = secret_key_share {
+                        block.set_secret_key_share(secret_key_share.into_inner());
+                    }
+                    block
+                })
+                .collect(),
+            election_index,
+            deciding_event,
+        })
     }
 }
 
@@ -114,19 +457,19 @@ impl<'a, T: NetworkEvent, P: PublicId> IntoIterator for &'a BlockGroup<T, P> {
     type IntoIter = vec_deque::Iter<'a, Block<T, P>>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.0.iter()
+        self.blocks.iter()
     }
 }
 
 impl<T: NetworkEvent, P: PublicId> Deref for BlockGroup<T, P> {
     type Target = VecDeque<Block<T, P>>;
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.blocks
     }
 }
 
 impl<T: NetworkEvent, P: PublicId> DerefMut for BlockGroup<T, P> {
     fn deref_mut(&mut self) -> &mut VecDeque<Block<T, P>> {
-        &mut self.0
+        &mut self.blocks
     }
 }
@@ -0,0 +1,518 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    error::Error,
+    observation::{ConsensusMode, MaliceKind},
+};
+use log::LogLevelFilter;
+use serde::{de::Error as _, Deserialize, Deserializer};
+use std::{
+    cmp,
+    collections::{BTreeMap, BTreeSet},
+    num::NonZeroUsize,
+};
+
+/// Configuration of a `Parsec` instance, passed to
+/// [`Parsec::from_genesis`](struct.Parsec.html#method.from_genesis) or
+/// [`Parsec::from_existing`](struct.Parsec.html#method.from_existing).
+///
+/// The default configuration uses `ConsensusMode::Supermajority` with a quorum of more than two
+/// thirds of the voters, detects every kind of malice, doesn't limit the size of gossip messages,
+/// uses `DkgPolicy::ToleratingFaults` and logs at every level. The `with_*` methods change these
+/// one at a time:
+///
+/// ```
+/// # use parsec::{ConsensusMode, MaliceKind, ParsecConfig};
+/// # use std::num::NonZeroUsize;
+/// let config = ParsecConfig::default()
+///     .with_consensus_mode(ConsensusMode::Single)
+///     .with_detected_malice(MaliceKind::DuplicateVote, false)
+///     .with_gossip_event_limit(NonZeroUsize::new(1000));
+/// assert_eq!(config.consensus_mode(), ConsensusMode::Single);
+/// assert!(!config.detects_malice(MaliceKind::DuplicateVote));
+/// assert!(config.detects_malice(MaliceKind::Fork));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParsecConfig {
+    consensus_mode: ConsensusMode,
+    quorum_policy: QuorumPolicy,
+    malice_detection: bool,
+    // Kinds of malice not to detect even if malice detection is on.
+    ignored_malice: BTreeSet<MaliceKind>,
+    spam_policy: SpamPolicy,
+    // Numbers of consensused accusations of each kind after which we vote to remove the offender.
+    removal_thresholds: BTreeMap<MaliceKind, NonZeroUsize>,
+    // Number of our sync events after which a peer not answering our request is unresponsive.
+    unresponsive_threshold: Option<NonZeroUsize>,
+    // Number of votes for invalid payloads a peer can gossip us before it is accused.
+    invalid_payload_threshold: Option<NonZeroUsize>,
+    gossip_event_limit: Option<NonZeroUsize>,
+    dkg_policy: DkgPolicy,
+    block_signatures: bool,
+    block_signature_timeout: Option<NonZeroUsize>,
+    chained_blocks: bool,
+    #[serde(with = "log_level_filter")]
+    log_level: LogLevelFilter,
+}
+
+impl Default for ParsecConfig {
+    fn default() -> Self {
+        Self {
+            consensus_mode: ConsensusMode::Supermajority,
+            quorum_policy: QuorumPolicy::default(),
+            malice_detection: true,
+            ignored_malice: BTreeSet::new(),
+            spam_policy: SpamPolicy::default(),
+            removal_thresholds: BTreeMap::new(),
+            unresponsive_threshold: None,
+            invalid_payload_threshold: None,
+            gossip_event_limit: None,
+            dkg_policy: DkgPolicy::ToleratingFaults,
+            block_signatures: false,
+            block_signature_timeout: NonZeroUsize::new(50),
+            chained_blocks: false,
+            log_level: LogLevelFilter::Trace,
+        }
+    }
+}
+
+impl ParsecConfig {
+    /// Sets how many votes are needed for an observation to become a candidate for consensus.
+    /// For more details, see [ConsensusMode](enum.ConsensusMode.html).
+    pub fn with_consensus_mode(self, consensus_mode: ConsensusMode) -> Self {
+        Self {
+            consensus_mode,
+            ..self
+        }
+    }
+
+    /// Sets the share of the voters needed to agree on anything: an observation to become a
+    /// candidate for consensus under `ConsensusMode::Supermajority`, an event to strongly see
+    /// another and each step of the binary meta-votes. It also bounds the threshold of
+    /// `DkgPolicy::ToleratingFaults`. All the peers must use the same policy.
+    ///
+    /// The policy has to tolerate a faulty voter of the section the `Parsec` instance is created
+    /// for, unless the section has fewer than four voters.
+    pub fn with_quorum_policy(self, quorum_policy: QuorumPolicy) -> Self {
+        Self {
+            quorum_policy,
+            ..self
+        }
+    }
+
+    /// Sets whether the events received from other peers are checked for malice, for the
+    /// offenders to be accused. If off, no kind of malice is detected, regardless of
+    /// [with_detected_malice](#method.with_detected_malice). Gossip received before we can handle
+    /// it is then accepted too, rather than rejected with `Error::PrematureGossip`.
+    ///
+    /// Malice detection is on by default. It used to be compiled in only with the
+    /// `malice-detection` cargo feature, which now has no effect: turn it off here to keep the
+    /// behaviour of builds without that feature.
+    pub fn with_malice_detection(self, malice_detection: bool) -> Self {
+        Self {
+            malice_detection,
+            ..self
+        }
+    }
+
+    /// Sets whether the given kind of malice is detected, provided malice detection is on. All
+    /// kinds are detected by default.
+    ///
+    /// Accusations of a kind we don't detect ourselves are never deemed invalid, as we can't
+    /// tell whether they are.
+    pub fn with_detected_malice(mut self, kind: MaliceKind, detected: bool) -> Self {
+        if detected {
+            let _ = self.ignored_malice.remove(&kind);
+        } else {
+            let _ = self.ignored_malice.insert(kind);
+        }
+        self
+    }
+
+    /// Sets the limits beyond which a peer is accused of spamming. Spam is only detected if
+    /// `MaliceKind::Unprovable` is.
+    pub fn with_spam_policy(self, spam_policy: SpamPolicy) -> Self {
+        Self {
+            spam_policy,
+            ..self
+        }
+    }
+
+    /// Sets after how many accusations of the given kind of malice against a peer reach consensus
+    /// we automatically vote for `Observation::Remove` of that peer, or never to if `None`, which
+    /// is the default. The `related_info` of the removal is the serialised
+    /// `Observation::Accusation` which reached the threshold.
+    ///
+    /// This only adds a vote of ours: the offender stops being a voter as soon as a single
+    /// accusation against it reaches consensus, whatever the threshold. The accusations reaching
+    /// consensus after that still count. Only provable malice counts, so a threshold for
+    /// `MaliceKind::Unprovable` has no effect.
+    pub fn with_removal_threshold(
+        mut self,
+        kind: MaliceKind,
+        threshold: Option<NonZeroUsize>,
+    ) -> Self {
+        if let Some(threshold) = threshold {
+            let _ = self.removal_thresholds.insert(kind, threshold);
+        } else {
+            let _ = self.removal_thresholds.remove(&kind);
+        }
+        self
+    }
+
+    /// Sets after how many of our own sync events a peer which still hasn't answered one of our
+    /// gossip requests is deemed unresponsive, or never to if `None`, which is the default.
+    /// Unresponsive peers are listed by [`Parsec::unresponsive_peers`](
+    /// struct.Parsec.html#method.unresponsive_peers), and accused with
+    /// `Malice::Unprovable(UnprovableMalice::Unresponsive)` if `MaliceKind::Unprovable` is
+    /// detected. Any answer from the peer, even to part of a split sync, clears its count, and each
+    /// gossip request it sends us halves it.
+    pub fn with_unresponsive_threshold(self, unresponsive_threshold: Option<NonZeroUsize>) -> Self {
+        Self {
+            unresponsive_threshold,
+            ..self
+        }
+    }
+
+    /// Sets how many events carrying votes for payloads our [`PayloadValidator`](
+    /// trait.PayloadValidator.html) deems invalid a peer can create before it is accused
+    /// with `Malice::Unprovable(UnprovableMalice::InvalidPayload)`, or that it never is if `None`,
+    /// which is the default. Only applies if `MaliceKind::Unprovable` is detected.
+    pub fn with_invalid_payload_threshold(
+        self,
+        invalid_payload_threshold: Option<NonZeroUsize>,
+    ) -> Self {
+        Self {
+            invalid_payload_threshold,
+            ..self
+        }
+    }
+
+    /// Sets the maximum number of events in a single gossip message, or removes the limit if
+    /// `None`. See [`Parsec::set_gossip_event_limit`](
+    /// struct.Parsec.html#method.set_gossip_event_limit) for details.
+    pub fn with_gossip_event_limit(self, gossip_event_limit: Option<NonZeroUsize>) -> Self {
+        Self {
+            gossip_event_limit,
+            ..self
+        }
+    }
+
+    /// Sets how the threshold of the distributed key generations is chosen.
+    ///
+    /// A DKG among exactly the current voters also provides the keys of the common coin. Without
+    /// such keys, the coin is predictable, and liveness isn't guaranteed against an adversary
+    /// controlling the delivery of the gossip messages. See [Parsec](struct.Parsec.html).
+    pub fn with_dkg_policy(self, dkg_policy: DkgPolicy) -> Self {
+        Self { dkg_policy, ..self }
+    }
+
+    /// Sets whether the consensused blocks are signed with a threshold signature of the section,
+    /// which is off by default. If on, the voters holding shares of the secret key generated by
+    /// the latest DKG among exactly the current voters sign each block with their share, and
+    /// blocks are only output once enough shares are gossiped to combine the signature. The
+    /// signature covers the block's payload and its index in the section's history. Blocks are
+    /// still output unsigned, with no [`Block::signature`](struct.Block.html#method.signature), if
+    /// there are no such keys, or if the signature can't be combined in time. See
+    /// [`with_block_signature_timeout`](#method.with_block_signature_timeout).
+    ///
+    /// The signature can be checked with [`Block::verify_with`](
+    /// struct.Block.html#method.verify_with). All the peers should use the same setting: peers
+    /// with it off don't sign the blocks, which delays and may prevent the signatures.
+    pub fn with_block_signatures(self, block_signatures: bool) -> Self {
+        Self {
+            block_signatures,
+            ..self
+        }
+    }
+
+    /// Sets after how many of our sync events blocks still waiting for their signature are output
+    /// unsigned, which is fifty by default. Each call to `create_gossip`, `handle_request` or
+    /// `handle_response` which creates a sync event counts, from the time the blocks reach
+    /// consensus. They're also output unsigned straight away while there are no keys to sign them
+    /// with. If `None`, blocks are never output unsigned while block signatures are on: they're
+    /// held back until signed, which requires a DKG among exactly the current voters after every
+    /// change of the voters. Only applies if block signatures are on.
+    pub fn with_block_signature_timeout(
+        self,
+        block_signature_timeout: Option<NonZeroUsize>,
+    ) -> Self {
+        Self {
+            block_signature_timeout,
+            ..self
+        }
+    }
+
+    /// Sets whether the output blocks are chained, which is off by default. If on, each block
+    /// carries its sequence number and the hash of the block output before it, so a peer which
+    /// missed some blocks can check the ones it's given are a continuation of those it has.
+    ///
+    /// See [`Block::chain_link`](struct.Block.html#method.chain_link) and
+    /// [`verify_chain`](fn.verify_chain.html). All the peers should use the same setting.
+    pub fn with_chained_blocks(self, chained_blocks: bool) -> Self {
+        Self {
+            chained_blocks,
+            ..self
+        }
+    }
+
+    /// Sets the most verbose level `Parsec` logs its messages at. The messages are still subject
+    /// to the filtering by the installed logger.
+    pub fn with_log_level(self, log_level: LogLevelFilter) -> Self {
+        Self { log_level, ..self }
+    }
+
+    /// Returns how many votes are needed for an observation to become a candidate for consensus.
+    pub fn consensus_mode(&self) -> ConsensusMode {
+        self.consensus_mode
+    }
+
+    /// Returns the share of the voters needed to agree on anything.
+    pub fn quorum_policy(&self) -> QuorumPolicy {
+        self.quorum_policy
+    }
+
+    /// Returns whether the events received from other peers are checked for malice.
+    pub fn malice_detection(&self) -> bool {
+        self.malice_detection
+    }
+
+    /// Returns whether the given kind of malice is detected.
+    pub fn detects_malice(&self, kind: MaliceKind) -> bool {
+        self.malice_detection && !self.ignored_malice.contains(&kind)
+    }
+
+    /// Returns the limits beyond which a peer is accused of spamming.
+    pub fn spam_policy(&self) -> SpamPolicy {
+        self.spam_policy
+    }
+
+    /// Returns after how many consensused accusations of the given kind of malice we vote to
+    /// remove the offender, if we do.
+    pub fn removal_threshold(&self, kind: MaliceKind) -> Option<NonZeroUsize> {
+        self.removal_thresholds.get(&kind).cloned()
+    }
+
+    /// Returns after how many of our sync events a peer not answering our request is deemed
+    /// unresponsive, if ever.
+    pub fn unresponsive_threshold(&self) -> Option<NonZeroUsize> {
+        self.unresponsive_threshold
+    }
+
+    /// Returns how many votes for invalid payloads a peer can create before it is accused,
+    /// if it ever is.
+    pub fn invalid_payload_threshold(&self) -> Option<NonZeroUsize> {
+        self.invalid_payload_threshold
+    }
+
+    /// Returns the maximum number of events in a single gossip message, if limited.
+    pub fn gossip_event_limit(&self) -> Option<NonZeroUsize> {
+        self.gossip_event_limit
+    }
+
+    /// Returns how the threshold of the distributed key generations is chosen.
+    pub fn dkg_policy(&self) -> DkgPolicy {
+        self.dkg_policy
+    }
+
+    /// Returns whether the consensused blocks are signed with a threshold signature.
+    pub fn block_signatures(&self) -> bool {
+        self.block_signatures
+    }
+
+    /// Returns after how many of our sync events blocks waiting for their signature are output
+    /// unsigned, if they ever are.
+    pub fn block_signature_timeout(&self) -> Option<NonZeroUsize> {
+        self.block_signature_timeout
+    }
+
+    /// Returns whether the output blocks are chained.
+    pub fn chained_blocks(&self) -> bool {
+        self.chained_blocks
+    }
+
+    /// Returns the most verbose level `Parsec` logs its messages at.
+    pub fn log_level(&self) -> LogLevelFilter {
+        self.log_level
+    }
+
+    pub(crate) fn set_gossip_event_limit(&mut self, gossip_event_limit: Option<NonZeroUsize>) {
+        self.gossip_event_limit = gossip_event_limit;
+    }
+}
+
+/// Limits beyond which a peer is accused of spamming, with
+/// `Malice::Unprovable(UnprovableMalice::Spam)`. Past its allowance, a peer's count of observation
+/// events, or of gossip messages sent to us, is compared with the average count of the other
+/// voters, so that a busy section doesn't make its members look like spammers. Internal
+/// observations such as DKG messages, coin shares and block signature shares aren't counted, and
+/// the counts restart every `window` sync events of ours, so they measure a rate rather than a
+/// total.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpamPolicy {
+    /// Largest size in bytes of the serialised observation carried by a single event.
+    pub max_payload_size: usize,
+    /// Number of observation events a peer can create regardless of what the other peers do.
+    pub observation_allowance: usize,
+    /// Number of gossip messages a peer can send us regardless of what the other peers do.
+    pub gossip_allowance: usize,
+    /// How many times the other voters' average count a peer's count can be past its allowance.
+    pub section_ratio: usize,
+    /// Number of our own sync events after which all the counts restart from zero.
+    pub window: usize,
+}
+
+impl Default for SpamPolicy {
+    fn default() -> Self {
+        Self {
+            max_payload_size: 1024 * 1024,
+            observation_allowance: 1000,
+            gossip_allowance: 500,
+            section_ratio: 10,
+            window: 1000,
+        }
+    }
+}
+
+impl SpamPolicy {
+    // Returns whether `count` exceeds the limit given the other peers' `average` count.
+    pub(crate) fn is_exceeded(&self, count: usize, allowance: usize, average: usize) -> bool {
+        count > allowance && count > average.saturating_mul(self.section_ratio)
+    }
+}
+
+/// The share of the voters needed to agree on anything, i.e. more than a given fraction of them.
+/// The default is more than two thirds, which tolerates the most faulty voters. A larger fraction
+/// tolerates fewer faulty voters, but more voters have to be unresponsive for the section to stall.
+///
+/// ```
+/// # use parsec::QuorumPolicy;
+/// let three_quarters = QuorumPolicy::new(3, 4).unwrap();
+/// assert!(!three_quarters.is_quorum(6, 8));
+/// assert!(three_quarters.is_quorum(7, 8));
+/// assert_eq!(three_quarters.tolerated_faults(8), 1);
+/// assert!(QuorumPolicy::new(1, 2).is_err());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct QuorumPolicy {
+    numerator: usize,
+    denominator: usize,
+}
+
+impl QuorumPolicy {
+    /// Creates a policy needing more than `numerator / denominator` of the voters.
+    ///
+    /// Returns `Error::InvalidQuorumPolicy` unless the fraction is at least two thirds, so that
+    /// any two quorums share an honest voter, and less than one, so that a large enough section
+    /// tolerates a faulty voter.
+    pub fn new(numerator: usize, denominator: usize) -> Result<Self, Error> {
+        if 3 * numerator < 2 * denominator || numerator >= denominator {
+            return Err(Error::InvalidQuorumPolicy);
+        }
+        Ok(Self {
+            numerator,
+            denominator,
+        })
+    }
+
+    /// Returns whether `count` voters out of `total` are a quorum.
+    pub fn is_quorum(self, count: usize, total: usize) -> bool {
+        // The products can't overflow in 128 bits.
+        count as u128 * self.denominator as u128 > total as u128 * self.numerator as u128
+    }
+
+    /// Returns the largest number of faulty voters out of `total` the policy tolerates, i.e. how
+    /// many voters can be missing from `total` for the rest to still be a quorum.
+    pub fn tolerated_faults(self, total: usize) -> usize {
+        // The quotient is at most `total`, as the fraction is less than one.
+        let largest_non_quorum =
+            (total as u128 * self.numerator as u128 / self.denominator as u128) as usize;
+        total.saturating_sub(largest_non_quorum + 1)
+    }
+
+    // Returns whether `count` voters out of `total` include at least one honest voter.
+    pub(crate) fn is_more_than_tolerated_faults(self, count: usize, total: usize) -> bool {
+        count > self.tolerated_faults(total)
+    }
+
+    // Returns whether the policy tolerates a faulty voter out of a section of `total` voters,
+    // unless the section is too small for the default policy to tolerate one either.
+    pub(crate) fn is_valid_for(self, total: usize) -> bool {
+        self.tolerated_faults(total) > 0 || Self::default().tolerated_faults(total) == 0
+    }
+}
+
+#[derive(Deserialize)]
+struct UncheckedQuorumPolicy {
+    numerator: usize,
+    denominator: usize,
+}
+
+// Deserialised through `new`, so that a saved configuration can't hold an invalid policy.
+impl<'de> Deserialize<'de> for QuorumPolicy {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let unchecked = UncheckedQuorumPolicy::deserialize(deserializer)?;
+        Self::new(unchecked.numerator, unchecked.denominator).map_err(D::Error::custom)
+    }
+}
+
+impl Default for QuorumPolicy {
+    fn default() -> Self {
+        Self {
+            numerator: 2,
+            denominator: 3,
+        }
+    }
+}
+
+/// How the threshold of a distributed key generation is chosen. Any `threshold + 1` participants
+/// can produce a signature with the generated key, while `threshold` or fewer can't. The weights
+/// given by `Observation::ChangeWeight` don't count here, as each participant holds one share of
+/// the key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DkgPolicy {
+    /// The threshold is the largest number of faulty participants the section tolerates under its
+    /// [`QuorumPolicy`](struct.QuorumPolicy.html), e.g. less than a third of the participants by
+    /// default.
+    ToleratingFaults,
+    /// The threshold is the given number, capped at the number of participants minus one.
+    Fixed(usize),
+}
+
+impl DkgPolicy {
+    pub(crate) fn threshold(self, participants_count: usize, quorum_policy: QuorumPolicy) -> usize {
+        match self {
+            DkgPolicy::ToleratingFaults => quorum_policy.tolerated_faults(participants_count),
+            DkgPolicy::Fixed(threshold) => {
+                cmp::min(threshold, participants_count.saturating_sub(1))
+            }
+        }
+    }
+}
+
+// `LogLevelFilter` doesn't implement the serde traits, so it is serialised by its name.
+mod log_level_filter {
+    use log::LogLevelFilter;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        level: &LogLevelFilter,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(level)
+    }
+
+    pub fn deserialize<'a, D: Deserializer<'a>>(
+        deserializer: D,
+    ) -> Result<LogLevelFilter, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse()
+            .map_err(|_| D::Error::custom(format!("invalid log level: {}", name)))
+    }
+}
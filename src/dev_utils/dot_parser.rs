@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-#[cfg(all(test, feature = "malice-detection", feature = "mock"))]
+#[cfg(all(test, feature = "mock"))]
 use crate::error::Error;
 #[cfg(any(all(test, feature = "mock"), feature = "testing"))]
 use crate::gossip::EventContextRef;
@@ -19,8 +19,8 @@ use crate::{
     },
     mock::{PeerId, Transaction},
     observation::{
-        ConsensusMode, Malice, MaliceInput, Observation, ObservationHash, ObservationInfo,
-        ObservationKey, ObservationStore,
+        ConsensusMode, KeyRotationProof, Malice, MaliceInput, Observation, ObservationHash,
+        ObservationInfo, ObservationKey, ObservationStore,
     },
     peer_list::{PeerIndex, PeerIndexMap, PeerIndexSet, PeerList, PeerState},
 };
@@ -540,9 +540,14 @@ fn parse_observation() -> Parser<u8, Observation<Transaction, PeerId>> {
     parse_genesis()
         | parse_add()
         | parse_remove()
+        | parse_reconfigure()
+        | parse_rotate_key()
+        | parse_change_weight()
         | parse_opaque()
         | parse_start_dkg()
         | parse_dkg_msg()
+        | parse_coin_share()
+        | parse_block_signature_share()
 }
 
 fn parse_accusation() -> Parser<u8, (PeerId, MaliceInput)> {
@@ -593,6 +598,32 @@ fn parse_remove() -> Parser<u8, Observation<Transaction, PeerId>> {
         })
 }
 
+fn parse_reconfigure() -> Parser<u8, Observation<Transaction, PeerId>> {
+    (seq(b"Reconfigure(") * parse_peers() - sym(b',') - spaces() + parse_peers() - sym(b')')).map(
+        |(add, remove)| Observation::Reconfigure {
+            add,
+            remove,
+            related_info: vec![],
+        },
+    )
+}
+
+// The mock IDs are their own secret IDs, so the proof can be recreated.
+fn parse_rotate_key() -> Parser<u8, Observation<Transaction, PeerId>> {
+    (seq(b"RotateKey(") * parse_peer_id() - sym(b',') - spaces() + parse_peer_id() - sym(b')')).map(
+        |(old, new)| Observation::RotateKey {
+            proof: KeyRotationProof::new(&old, &new),
+            old,
+            new,
+        },
+    )
+}
+
+fn parse_change_weight() -> Parser<u8, Observation<Transaction, PeerId>> {
+    (seq(b"ChangeWeight(") * parse_peer_id() - sym(b',') - spaces() + parse_usize() - sym(b')'))
+        .map(|(peer_id, weight)| Observation::ChangeWeight { peer_id, weight })
+}
+
 fn parse_add_or_remove() -> Parser<u8, (PeerId, Vec<u8>)> {
     parse_add_or_remove_with_related_info() | parse_add_or_remove_without_related_info()
 }
@@ -640,6 +671,38 @@ fn parse_dkg_msg() -> Parser<u8, Observation<Transaction, PeerId>> {
     .map(Observation::DkgMessage)
 }
 
+fn parse_coin_share() -> Parser<u8, Observation<Transaction, PeerId>> {
+    let parser_u8 = is_a(digit)
+        .repeat(1..)
+        .convert(String::from_utf8)
+        .convert(|s| u8::from_str(&s));
+    let parser_vec =
+        (seq(b"[") * list(parser_u8, seq(b", ")) - seq(b"]")).map(|v| v.into_iter().collect_vec());
+
+    (seq(b"CoinShare(") * none_of(b")").repeat(1..) * seq(b"), SerialisedCoinShare(") * parser_vec
+        - seq(b")"))
+    .map(|v| unwrap!(deserialise(&v)))
+    .map(Observation::CoinShare)
+}
+
+fn parse_block_signature_share() -> Parser<u8, Observation<Transaction, PeerId>> {
+    let parser_u8 = is_a(digit)
+        .repeat(1..)
+        .convert(String::from_utf8)
+        .convert(|s| u8::from_str(&s));
+    let parser_vec =
+        (seq(b"[") * list(parser_u8, seq(b", ")) - seq(b"]")).map(|v| v.into_iter().collect_vec());
+
+    (seq(b"BlockSignatureShare(")
+        * none_of(b")").repeat(1..)
+        * seq(b"), Serialised")
+        * seq(b"BlockSignatureShare(")
+        * parser_vec
+        - seq(b")"))
+    .map(|v| unwrap!(deserialise(&v)))
+    .map(Observation::BlockSignatureShare)
+}
+
 fn parse_transaction() -> Parser<u8, String> {
     is_a(alphanum).repeat(1..).convert(String::from_utf8)
 }
@@ -805,11 +868,11 @@ impl ParsedContents {
         Some(event)
     }
 
-    #[cfg(all(test, feature = "malice-detection", feature = "mock"))]
+    #[cfg(all(test, feature = "mock"))]
     /// Insert event into the `ParsedContents`. Note this does not perform any validations
     /// whatsoever, so this is useful for simulating all kinds of invalid or malicious situations.
     pub fn add_event(&mut self, event: Event<PeerId>) -> EventIndex {
-        let indexed_event = self.graph.insert(event);
+        let indexed_event = unwrap!(self.graph.insert(event));
         self.peer_list.add_event(indexed_event);
 
         let start_index = indexed_event.event_index().topological_index() + 1;
@@ -829,7 +892,7 @@ impl ParsedContents {
         }
     }
 
-    #[cfg(all(test, feature = "malice-detection", feature = "mock"))]
+    #[cfg(all(test, feature = "mock"))]
     pub fn new_event_from_observation(
         &mut self,
         self_parent: EventIndex,
@@ -1060,6 +1123,7 @@ fn convert_to_meta_election(
     MetaElection {
         meta_events,
         voters: convert_peer_id_set(meta_election.voters, peer_list),
+        weights: PeerIndexMap::default(),
         interesting_events,
         unconsensused_events,
         consensus_history: meta_election.consensus_history,
@@ -1156,7 +1220,7 @@ fn create_events(
             &mut parsed_contents.observations,
         );
 
-        let indexed_event_ref = parsed_contents.graph.insert(next_event);
+        let indexed_event_ref = unwrap!(parsed_contents.graph.insert(next_event));
         peer_list.add_event(indexed_event_ref);
         let event_index = indexed_event_ref.event_index();
         peer_list.record_gossiped_event_by(indexed_event_ref.creator(), event_index);
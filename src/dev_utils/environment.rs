@@ -7,6 +7,7 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
+    config::ParsecConfig,
     dev_utils::{
         network::{ConsensusError, Network},
         new_common_rng, RngChoice, RngDebug, Schedule,
@@ -29,9 +30,17 @@ impl Environment {
     /// Initialise the test environment. The random number generator will be seeded with `seed`
     /// or randomly if this is `SeededRandom`.
     pub fn with_consensus_mode(seed: RngChoice, consensus_mode: ConsensusMode) -> Self {
+        Self::with_config(
+            seed,
+            ParsecConfig::default().with_consensus_mode(consensus_mode),
+        )
+    }
+
+    /// Initialise the test environment with peers using the given configuration.
+    pub fn with_config(seed: RngChoice, config: ParsecConfig) -> Self {
         let rng = new_common_rng(seed);
         let rng2 = new_common_rng(seed);
-        let network = Network::new(consensus_mode);
+        let network = Network::with_config(config);
 
         Self { network, rng, rng2 }
     }
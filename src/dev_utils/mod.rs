@@ -24,10 +24,10 @@ mod pseudo_random;
 mod record;
 mod schedule;
 
-#[cfg(test)]
-pub(crate) use self::dot_parser::parse_test_dot_file;
 #[cfg(all(test, feature = "mock"))]
 pub(crate) use self::dot_parser::ParsedContents;
+#[cfg(test)]
+pub(crate) use self::dot_parser::{parse_dot_file, parse_test_dot_file};
 #[cfg(any(all(test, feature = "mock"), feature = "testing"))]
 pub use self::record::Record;
 pub use self::{
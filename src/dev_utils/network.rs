@@ -14,12 +14,11 @@ use super::{
 };
 use crate::{
     block::Block,
+    config::ParsecConfig,
     error::Error,
     gossip::{Request, Response},
     mock::{PeerId, Transaction},
-    observation::{
-        is_more_than_two_thirds, ConsensusMode, Malice, Observation as ParsecObservation,
-    },
+    observation::{ConsensusMode, Malice, Observation as ParsecObservation},
 };
 use itertools::Itertools;
 use rand::Rng;
@@ -43,7 +42,8 @@ pub struct Network {
     pub peers: BTreeMap<PeerId, Peer>,
     genesis: BTreeSet<PeerId>,
     msg_queue: BTreeMap<PeerId, Vec<QueueEntry>>,
-    consensus_mode: ConsensusMode,
+    config: ParsecConfig,
+    peer_configs: BTreeMap<PeerId, ParsecConfig>,
 }
 
 #[derive(Debug)]
@@ -110,16 +110,35 @@ pub enum ConsensusError {
 impl Network {
     /// Create an empty test network.
     pub fn new(consensus_mode: ConsensusMode) -> Self {
+        Self::with_config(ParsecConfig::default().with_consensus_mode(consensus_mode))
+    }
+
+    /// Create an empty test network, whose peers will use the given configuration.
+    pub fn with_config(config: ParsecConfig) -> Self {
         Network {
             peers: BTreeMap::new(),
             genesis: BTreeSet::new(),
             msg_queue: BTreeMap::new(),
-            consensus_mode,
+            config,
+            peer_configs: BTreeMap::new(),
         }
     }
 
+    /// Make the given peer use its own configuration instead of the network's one, once it is
+    /// created.
+    pub fn set_peer_config(&mut self, peer_id: PeerId, config: ParsecConfig) {
+        let _ = self.peer_configs.insert(peer_id, config);
+    }
+
+    fn peer_config(&self, peer_id: &PeerId) -> ParsecConfig {
+        self.peer_configs
+            .get(peer_id)
+            .unwrap_or(&self.config)
+            .clone()
+    }
+
     pub fn consensus_mode(&self) -> ConsensusMode {
-        self.consensus_mode
+        self.config.consensus_mode()
     }
 
     fn active_peers(&self) -> impl Iterator<Item = &Peer> {
@@ -334,7 +353,7 @@ impl Network {
         block: &'a Block<Transaction, PeerId>,
     ) -> (&'a Observation, Option<&'a PeerId>) {
         let peer_id = if block.payload().is_opaque() {
-            if self.consensus_mode == ConsensusMode::Single {
+            if self.consensus_mode() == ConsensusMode::Single {
                 Some(&unwrap!(block.proofs().iter().next()).public_id)
             } else {
                 None
@@ -416,15 +435,16 @@ impl Network {
         }
 
         let consensus_mode = if block.payload().is_opaque() {
-            self.consensus_mode
+            self.consensus_mode()
         } else {
             ConsensusMode::Supermajority
         };
         let correct_signatories = match consensus_mode {
             ConsensusMode::Single => !signatories.is_empty(),
-            ConsensusMode::Supermajority => {
-                is_more_than_two_thirds(signatories.len(), section.len())
-            }
+            ConsensusMode::Supermajority => self
+                .config
+                .quorum_policy()
+                .is_quorum(signatories.len(), section.len()),
         };
         if !correct_signatories {
             return Err(ConsensusError::TooFewSignatures {
@@ -441,7 +461,7 @@ impl Network {
         let mut valid_voters = BTreeSet::new();
 
         for block_group in block_groups {
-            for block in block_group {
+            for block in block_group.blocks() {
                 if let ParsecObservation::Genesis { ref group, .. } = *block.payload() {
                     valid_voters = group.clone();
                 }
@@ -454,7 +474,7 @@ impl Network {
                 self.check_block_signatories(block, &valid_voters)?;
             }
 
-            for block in block_group {
+            for block in block_group.blocks() {
                 match *block.payload() {
                     ParsecObservation::Genesis { .. } => (),
                     ParsecObservation::Add { ref peer_id, .. } => {
@@ -463,6 +483,14 @@ impl Network {
                     ParsecObservation::Remove { ref peer_id, .. } => {
                         let _ = valid_voters.remove(peer_id);
                     }
+                    ParsecObservation::Reconfigure {
+                        ref add,
+                        ref remove,
+                        ..
+                    } => {
+                        valid_voters = &valid_voters - remove;
+                        valid_voters.extend(add.iter().cloned());
+                    }
                     _ => {}
                 }
             }
@@ -560,7 +588,7 @@ impl Network {
                         Peer::from_genesis(
                             id.clone(),
                             &genesis_ids,
-                            self.consensus_mode,
+                            self.peer_config(id),
                             new_rng(rng2),
                         )
                     })
@@ -571,11 +599,11 @@ impl Network {
                         Peer::malicious_from_genesis(
                             id.clone(),
                             &genesis_ids,
-                            self.consensus_mode,
+                            self.peer_config(id),
                             new_rng(rng2),
                         )
                     })
-                    .collect_vec();;
+                    .collect_vec();
 
                 self.peers = good_peers
                     .into_iter()
@@ -609,7 +637,7 @@ impl Network {
                         peer_id.clone(),
                         &self.genesis,
                         &current_peers,
-                        self.consensus_mode,
+                        self.peer_config(&peer_id),
                         new_rng(rng2),
                     ),
                 );
@@ -637,7 +665,7 @@ impl Network {
                         self.check_unexpected_accusations(&peer_id)?;
                     }
                 }
-                Peer::update_network_views(&mut self.peers);
+                Peer::update_network_views(&mut self.peers, self.config.quorum_policy());
                 let running_peers_ids = self.running_peers_ids();
                 for peer_id in &running_peers_ids {
                     if rng.gen::<f64>() < options.prob_gossip {
@@ -687,7 +715,9 @@ impl Network {
                 let joined_count = self.num_with_network_view(NetworkView::Joined);
                 let leaving_count = self.num_with_network_view(NetworkView::Leaving);
                 let current_count = joined_count + leaving_count;
-                is_more_than_two_thirds(joined_count - 1, current_count)
+                self.config
+                    .quorum_policy()
+                    .is_quorum(joined_count - 1, current_count)
             }
             Some(NetworkView::Leaving) | Some(NetworkView::Left) => true,
         }
@@ -695,7 +725,7 @@ impl Network {
 
     fn allow_addition_of_peer(&self) -> bool {
         // For sections of size 3 or more, we only allow new node to join if the currently joined
-        // ones would still form a supermajority event after all the joining one including the new
+        // ones would still form a quorum even after all the joining one including the new
         // node become joined. This is to prevent the situation where too many nodes join so the
         // votes to add more nodes no longer have supermajority.
         //
@@ -705,6 +735,9 @@ impl Network {
         let joining_count = self.num_with_network_view(NetworkView::Joining);
 
         (joined_count < 3 && joining_count == 0)
-            || is_more_than_two_thirds(joined_count, joined_count + joining_count + 1)
+            || self
+                .config
+                .quorum_policy()
+                .is_quorum(joined_count, joined_count + joining_count + 1)
     }
 }
@@ -8,13 +8,12 @@
 
 use super::Observation;
 use crate::{
-    block::{Block, BlockGroup},
+    block::{Block, ConsensusBatch},
+    config::{ParsecConfig, QuorumPolicy},
     error::Result,
     gossip::{Cause, Event, EventIndex, Request, Response},
     mock::{PeerId, Transaction},
-    observation::{
-        is_more_than_two_thirds, ConsensusMode, Malice, Observation as ParsecObservation,
-    },
+    observation::{Malice, Observation as ParsecObservation},
     parsec::{Parsec, TestParsec},
     peer_list::PeerIndex,
 };
@@ -229,7 +228,7 @@ impl DerefMut for WrappedParsec {
 pub struct Peer {
     parsec: WrappedParsec,
     /// The blocks returned by `parsec.poll()`, held in the order in which they were returned.
-    grouped_blocks: Vec<BlockGroup<Transaction, PeerId>>,
+    grouped_blocks: Vec<ConsensusBatch<Transaction, PeerId>>,
     status: PeerStatus,
     network_view: NetworkView,
     votes_to_make: Vec<Observation>,
@@ -243,14 +242,14 @@ impl Peer {
     pub fn from_genesis(
         id: PeerId,
         genesis_group: &BTreeSet<PeerId>,
-        consensus_mode: ConsensusMode,
+        config: ParsecConfig,
         secure_rng: Box<dyn Rng>,
     ) -> Self {
         Self::new(WrappedParsec::Good(Parsec::from_genesis(
             id,
             genesis_group,
             vec![],
-            consensus_mode,
+            config,
             secure_rng,
         )))
     }
@@ -258,11 +257,11 @@ impl Peer {
     pub fn malicious_from_genesis(
         id: PeerId,
         genesis_group: &BTreeSet<PeerId>,
-        consensus_mode: ConsensusMode,
+        config: ParsecConfig,
         secure_rng: Box<dyn Rng>,
     ) -> Self {
         Self::new(WrappedParsec::Malicious(MaliciousComponents {
-            test_parsec: TestParsec::from_genesis(id, genesis_group, consensus_mode, secure_rng),
+            test_parsec: TestParsec::from_genesis(id, genesis_group, config, secure_rng),
             forked_event: None,
         }))
     }
@@ -271,14 +270,14 @@ impl Peer {
         id: PeerId,
         genesis_group: &BTreeSet<PeerId>,
         current_group: &BTreeSet<PeerId>,
-        consensus_mode: ConsensusMode,
+        config: ParsecConfig,
         secure_rng: Box<dyn Rng>,
     ) -> Self {
         Self::new(WrappedParsec::Good(Parsec::from_existing(
             id,
             genesis_group,
             current_group,
-            consensus_mode,
+            config,
             secure_rng,
         )))
     }
@@ -287,7 +286,7 @@ impl Peer {
         id: PeerId,
         genesis_group: &BTreeSet<PeerId>,
         current_group: &BTreeSet<PeerId>,
-        consensus_mode: ConsensusMode,
+        config: ParsecConfig,
         secure_rng: Box<dyn Rng>,
     ) -> Self {
         Self::new(WrappedParsec::Malicious(MaliciousComponents {
@@ -295,7 +294,7 @@ impl Peer {
                 id,
                 genesis_group,
                 current_group,
-                consensus_mode,
+                config,
                 secure_rng,
             ),
             forked_event: None,
@@ -355,10 +354,13 @@ impl Peer {
 
     fn make_active_if_added(&mut self, block: &Block<Transaction, PeerId>) {
         if self.status == PeerStatus::Pending {
-            if let ParsecObservation::Add { ref peer_id, .. } = *block.payload() {
-                if self.id() == peer_id {
-                    self.status = PeerStatus::Active;
-                }
+            let is_added = match *block.payload() {
+                ParsecObservation::Add { ref peer_id, .. } => self.id() == peer_id,
+                ParsecObservation::Reconfigure { ref add, .. } => add.contains(self.id()),
+                _ => false,
+            };
+            if is_added {
+                self.status = PeerStatus::Active;
             }
         }
     }
@@ -367,7 +369,7 @@ impl Peer {
     /// block.
     pub fn poll_all(&mut self) {
         while let Some(block_group) = self.parsec.batch_poll() {
-            for block in &block_group {
+            for block in block_group.blocks() {
                 self.make_active_if_added(block);
                 match block.payload() {
                     ParsecObservation::Add { peer_id, .. } => {
@@ -376,6 +378,14 @@ impl Peer {
                     ParsecObservation::Remove { peer_id, .. } => {
                         assert!(self.removed_peers_ids.insert(peer_id.clone()))
                     }
+                    ParsecObservation::Reconfigure { add, remove, .. } => {
+                        for peer_id in add {
+                            assert!(self.added_peers_ids.insert(peer_id.clone()))
+                        }
+                        for peer_id in remove {
+                            assert!(self.removed_peers_ids.insert(peer_id.clone()))
+                        }
+                    }
                     _ => (),
                 }
             }
@@ -388,12 +398,14 @@ impl Peer {
         self.parsec.our_pub_id()
     }
 
-    pub(crate) fn grouped_blocks(&self) -> &[BlockGroup<Transaction, PeerId>] {
+    pub(crate) fn grouped_blocks(&self) -> &[ConsensusBatch<Transaction, PeerId>] {
         &self.grouped_blocks
     }
 
     pub fn blocks(&self) -> impl Iterator<Item = &Block<Transaction, PeerId>> {
-        self.grouped_blocks.iter().flatten()
+        self.grouped_blocks
+            .iter()
+            .flat_map(|block_group| block_group.blocks())
     }
 
     pub fn status(&self) -> PeerStatus {
@@ -453,9 +465,12 @@ impl Peer {
         self.network_view = NetworkView::Leaving;
     }
 
-    /// Check if a supermajority of `Joined` peers have polled blocks changing the network view of
+    /// Check if a quorum of `Joined` peers have polled blocks changing the network view of
     /// `Joining` or `Leaving` peers.  Transition these to `Joined` or `Left` respectively.
-    pub fn update_network_views(all_peers: &mut BTreeMap<PeerId, Peer>) {
+    pub fn update_network_views(
+        all_peers: &mut BTreeMap<PeerId, Peer>,
+        quorum_policy: QuorumPolicy,
+    ) {
         let mut added_counts = BTreeMap::new();
         let mut removed_counts = BTreeMap::new();
         let mut running_peers_count = 0;
@@ -477,14 +492,14 @@ impl Peer {
         for (added_peer_id, count) in &added_counts {
             let peer = unwrap!(all_peers.get_mut(added_peer_id));
             if peer.network_view == NetworkView::Joining
-                && is_more_than_two_thirds(*count, running_peers_count)
+                && quorum_policy.is_quorum(*count, running_peers_count)
             {
                 peer.network_view = NetworkView::Joined;
             }
         }
 
         for (removed_peer_id, count) in &removed_counts {
-            if is_more_than_two_thirds(*count, running_peers_count) {
+            if quorum_policy.is_quorum(*count, running_peers_count) {
                 unwrap!(all_peers.get_mut(removed_peer_id)).network_view = NetworkView::Left;
             }
         }
@@ -495,6 +510,12 @@ impl Peer {
         self.blocks().map(Block::payload).collect()
     }
 
+    /// Returns an iterator over all observations voted for by this peer that haven't been
+    /// retrieved by `poll_all()` yet.
+    pub fn unpolled_observations(&self) -> impl Iterator<Item = &Observation> {
+        self.parsec.our_unpolled_observations()
+    }
+
     /// Returns an iterator over all accusations raised by this peer that haven't been retrieved by
     /// `poll_all()` yet.
     pub fn unpolled_accusations(
@@ -11,6 +11,7 @@ use super::{
     ReplayRng,
 };
 use crate::{
+    config::ParsecConfig,
     gossip::{Cause, Event, IndexedEventRef, PackedEvent, Request, Response},
     hash::Hash,
     mock::{PeerId, Transaction},
@@ -33,6 +34,8 @@ pub struct Record {
     consensus_history: Vec<ObservationKey>,
     // Consensus mode to play
     consensus_mode: ConsensusMode,
+    // Whether to detect malice while playing
+    malice_detection: bool,
     // True if when parsing the graph we had to add a final `Requesting` sync event and schedule a
     // Request to be sent to us in order to learn of any remaining events.
     added_final_requesting_event: bool,
@@ -44,12 +47,22 @@ impl Record {
         Ok(Self::from(contents))
     }
 
+    /// Sets whether malice is detected when playing the record. It is by default.
+    pub fn with_malice_detection(self, malice_detection: bool) -> Self {
+        Self {
+            malice_detection,
+            ..self
+        }
+    }
+
     pub fn play(self) -> Parsec<Transaction, PeerId> {
         let mut parsec = Parsec::from_genesis(
             self.our_id,
             &self.genesis_group,
             vec![],
-            self.consensus_mode,
+            ParsecConfig::default()
+                .with_consensus_mode(self.consensus_mode)
+                .with_malice_detection(self.malice_detection),
             Box::new(ReplayRng::new(self.secure_rng_values.clone())),
         );
 
@@ -121,8 +134,10 @@ impl From<ParsedContents> for Record {
                                 panic!("Unexpected accusation {:?}", *event);
                             }
                         }
-                        Observation::DkgMessage(_) => {
-                            // Skip DkgMessage that we generate.
+                        Observation::DkgMessage(_)
+                        | Observation::CoinShare(_)
+                        | Observation::BlockSignatureShare(_) => {
+                            // Skip DkgMessage, CoinShare and BlockSignatureShare that we generate.
                             continue;
                         }
                         _ => (),
@@ -193,6 +208,7 @@ impl From<ParsedContents> for Record {
             actions,
             consensus_history: contents.meta_election.consensus_history,
             consensus_mode: contents.consensus_mode,
+            malice_detection: true,
             added_final_requesting_event,
         }
     }
@@ -333,7 +349,10 @@ mod tests {
             get_graph_snapshot(&expected, ignore_last_events)
         };
 
-        let replay = unwrap!(Record::parse(path));
+        // Note: don't detect malice, because there could be a mismatch between parsed and replayed
+        // graphs when the dot file contains malice (e.g.: fork). This is because parsing does not
+        // create accusation events but replaying does.
+        let replay = unwrap!(Record::parse(path)).with_malice_detection(false);
         let ignore_last_events = if replay.added_final_requesting_event {
             // Ignore the `Requesting` event we created when parsing the graph, and the associated
             // `Request` we'll create when receiving the message.
@@ -367,10 +386,6 @@ mod tests {
         }
     }
 
-    // Note: skip this test when malice-detection is enabled, because there could be a mismatch
-    // between parsed and replayed graphs when the dot file contains malice (e.g.: fork). This is
-    // because parsing does not create accusation events but replaying does.
-    #[cfg(not(feature = "malice-detection"))]
     #[test]
     fn smoke_parsec() {
         use std::fs;
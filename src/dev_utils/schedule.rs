@@ -479,13 +479,9 @@ impl ObservationSchedule {
     }
 
     fn count_expected_accusations(&self) -> usize {
-        if cfg!(feature = "malice-detection") {
-            // One accusation per malicious peer as currently the malicious peers commit only one
-            // malice each.
-            self.genesis.ids_of_malicious_peers.len()
-        } else {
-            0
-        }
+        // One accusation per malicious peer as currently the malicious peers commit only one
+        // malice each.
+        self.genesis.ids_of_malicious_peers.len()
     }
 }
 
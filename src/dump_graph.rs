@@ -1020,6 +1020,19 @@ mod detail {
                 Observation::Remove { peer_id, .. } => {
                     format!("Remove({:?})", sanitise_peer_id(peer_id))
                 }
+                Observation::Reconfigure { add, remove, .. } => format!(
+                    "Reconfigure({:?}, {:?})",
+                    add.iter().map(sanitise_peer_id).collect::<BTreeSet<_>>(),
+                    remove.iter().map(sanitise_peer_id).collect::<BTreeSet<_>>()
+                ),
+                Observation::RotateKey { old, new, .. } => format!(
+                    "RotateKey({:?}, {:?})",
+                    sanitise_peer_id(old),
+                    sanitise_peer_id(new)
+                ),
+                Observation::ChangeWeight { peer_id, weight } => {
+                    format!("ChangeWeight({:?}, {})", sanitise_peer_id(peer_id), weight)
+                }
                 Observation::Accusation { offender, malice } => format!(
                     "Accusation {{ {:?}, {} }}",
                     sanitise_peer_id(offender),
@@ -1031,6 +1044,15 @@ mod detail {
                     dkg_result,
                 } => format!("DkgResult({:?}, {:?})", participants, dkg_result),
                 Observation::DkgMessage(msg) => format!("DkgMessage({:?})", msg),
+                Observation::CoinShare(coin_share) => format!(
+                    "CoinShare({}, {:?}, {})",
+                    coin_share.coin_id.election,
+                    sanitise_peer_id(&coin_share.coin_id.peer_id),
+                    coin_share.coin_id.round
+                ),
+                Observation::BlockSignatureShare(share) => {
+                    format!("BlockSignatureShare({:?})", share.payload_hash)
+                }
                 Observation::OpaquePayload(payload) => {
                     let max_length = 16;
                     let mut payload_str = sanitise_string(format!("{:?}", payload));
@@ -1053,6 +1075,12 @@ mod detail {
                 Observation::DkgMessage(msg) => {
                     format!("SerialisedDkgMessage({:?})", serialise(msg))
                 }
+                Observation::CoinShare(coin_share) => {
+                    format!("SerialisedCoinShare({:?})", serialise(coin_share))
+                }
+                Observation::BlockSignatureShare(share) => {
+                    format!("SerialisedBlockSignatureShare({:?})", serialise(share))
+                }
                 _ => String::new(),
             };
 
@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::peer_list::PeerState;
+use crate::{key_gen::dkg_status::DkgFailure, observation::MAX_WEIGHT, peer_list::PeerState};
 use std::{
     fmt::{self, Display, Formatter},
     result,
@@ -21,6 +21,10 @@ pub enum Error {
     UnknownPayload,
     /// Attempt to create a block with no votes.
     MissingVotes,
+    /// The votes of a block aren't enough for it to be consensused.
+    InsufficientVotes,
+    /// The blocks don't form an unbroken chain.
+    BrokenChain,
     /// Failed to verify signature.
     SignatureFailure,
     /// Peer is not known to our node.
@@ -53,8 +57,35 @@ pub enum Error {
     InvalidMessage,
     /// The request or response has already been handled by us.
     DuplicateMessage,
-    /// Faild DKG process
-    FailedDkg,
+    /// The peer talks a version of the gossip protocol we don't support, or doesn't support ours.
+    IncompatibleVersion,
+    /// The DKG session failed or was cancelled.
+    FailedDkg(DkgFailure),
+    /// No DKG session with the given ID has been started.
+    UnknownDkg,
+    /// A DKG session is running, so the section can't be summarised for a joining peer.
+    DkgInProgress,
+    /// The quorum is less than two thirds or all of the voters.
+    InvalidQuorumPolicy,
+    /// A voter was given a weight of zero or of more than `MAX_WEIGHT`.
+    InvalidWeight,
+    /// A reconfiguration changes no peer, adds a current voter or removes a peer which isn't one.
+    InvalidReconfiguration,
+    /// A key rotation isn't signed by both keys, or its new key is already in use.
+    InvalidKeyRotation,
+    /// The state a joining peer is caught up with doesn't match the section's summary.
+    MismatchedSummary,
+    /// The opaque payload was deemed invalid by the registered `PayloadValidator`.
+    InvalidPayload,
+    /// The state could not be serialised to be saved.
+    SerialisationFailure,
+    /// The saved state is malformed, was written by an incompatible version or belongs to a
+    /// different peer.
+    InvalidSavedState,
+    /// The storage of the gossip graph could not be set up, or failed to store an event.
+    StorageFailure,
+    /// The event was pruned from the gossip graph, so it can't be inserted again.
+    PrunedEvent,
     /// Logic error.
     Logic,
 }
@@ -71,6 +102,10 @@ impl Display for Error {
                 "The payload hash doesn't correspond to any payload known to our node."
             ),
             Error::MissingVotes => write!(f, "Block cannot be created with no votes"),
+            Error::InsufficientVotes => {
+                write!(f, "The block doesn't have enough votes to be consensused.")
+            }
+            Error::BrokenChain => write!(f, "The blocks don't form an unbroken chain."),
             Error::SignatureFailure => write!(
                 f,
                 "The message or signature might be corrupted, or the signer is wrong."
@@ -100,7 +135,45 @@ impl Display for Error {
             ),
             Error::InvalidMessage => write!(f, "This non-empty message is invalid."),
             Error::DuplicateMessage => write!(f, "This message has already been handled."),
-            Error::FailedDkg => write!(f, "The requested DKG could not proceed."),
+            Error::IncompatibleVersion => write!(
+                f,
+                "The peer's gossip protocol version is incompatible with ours."
+            ),
+            Error::FailedDkg(ref reason) => {
+                write!(f, "The requested DKG could not proceed: {}", reason)
+            }
+            Error::UnknownDkg => write!(f, "The requested DKG has not been started."),
+            Error::DkgInProgress => write!(
+                f,
+                "The section can't be summarised while a DKG session is running."
+            ),
+            Error::InvalidQuorumPolicy => write!(
+                f,
+                "The quorum must be at least two thirds and less than all of the voters."
+            ),
+            Error::InvalidWeight => write!(
+                f,
+                "The weight of a voter must be from one to {}.",
+                MAX_WEIGHT
+            ),
+            Error::InvalidReconfiguration => write!(
+                f,
+                "A reconfiguration must change at least one peer, can't add a current voter and \
+                 can only remove current voters."
+            ),
+            Error::InvalidKeyRotation => write!(
+                f,
+                "The key rotation must be signed by both keys, and the new key must not be in use."
+            ),
+            Error::MismatchedSummary => write!(
+                f,
+                "The state to catch up with doesn't match the section's summary."
+            ),
+            Error::InvalidPayload => write!(f, "The payload was refused by the payload validator."),
+            Error::SerialisationFailure => write!(f, "The state could not be serialised."),
+            Error::InvalidSavedState => write!(f, "The saved state could not be restored."),
+            Error::StorageFailure => write!(f, "The gossip graph storage failed."),
+            Error::PrunedEvent => write!(f, "The event was pruned from the gossip graph."),
             Error::Logic => write!(
                 f,
                 "This is a logic error and represents a flaw in the code."
@@ -0,0 +1,297 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    gossip::PackedEvent,
+    id::PublicId,
+    network_event::NetworkEvent,
+    observation::{Malice, Observation},
+};
+use std::collections::BTreeSet;
+
+/// Self-contained proof of malice: the signed events showing the misbehaviour, which can be
+/// checked by [`verify_evidence`](fn.verify_evidence.html) knowing nothing but the offender's
+/// `PublicId`. Obtained from [`Parsec::malice_evidence`](
+/// struct.Parsec.html#method.malice_evidence).
+#[serde(bound = "")]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct Evidence<T: NetworkEvent, P: PublicId> {
+    malice: Malice<T, P>,
+    events: Vec<PackedEvent<T, P>>,
+}
+
+impl<T: NetworkEvent, P: PublicId> Evidence<T, P> {
+    pub(crate) fn new(malice: Malice<T, P>, events: Vec<PackedEvent<T, P>>) -> Self {
+        Self { malice, events }
+    }
+
+    /// Returns the malice this is the evidence of.
+    pub fn malice(&self) -> &Malice<T, P> {
+        &self.malice
+    }
+
+    /// Returns the events proving the malice.
+    pub fn events(&self) -> &[PackedEvent<T, P>] {
+        &self.events
+    }
+
+    // Returns the creator of the first event, which is the offender for every kind of malice we
+    // produce evidence of.
+    pub(crate) fn offender(&self) -> Option<&P> {
+        self.events.first().map(PackedEvent::creator)
+    }
+}
+
+/// Returns whether `evidence` proves that `offender` committed the malice the evidence is of.
+///
+/// Every event in the evidence has to be signed by its creator, and together they have to show
+/// the misbehaviour without relying on any other events. Evidence of `Malice::UnexpectedGenesis`
+/// is only accepted if the genesis vote doesn't follow the offender's initial event: that the
+/// offender isn't a genesis member can't be told without knowing the genesis group, so use
+/// [`verify_evidence_with_genesis`](fn.verify_evidence_with_genesis.html) for that.
+pub fn verify_evidence<T: NetworkEvent, P: PublicId>(
+    evidence: &Evidence<T, P>,
+    offender: &P,
+) -> bool {
+    verify(evidence, offender, None)
+}
+
+/// Returns whether `evidence` proves that `offender` committed the malice the evidence is of, as
+/// [`verify_evidence`](fn.verify_evidence.html) does, but also accepting evidence of a genesis
+/// vote by an offender who isn't in `genesis_group`, the genesis group known to the verifier.
+pub fn verify_evidence_with_genesis<T: NetworkEvent, P: PublicId>(
+    evidence: &Evidence<T, P>,
+    offender: &P,
+    genesis_group: &BTreeSet<P>,
+) -> bool {
+    verify(evidence, offender, Some(genesis_group))
+}
+
+fn verify<T: NetworkEvent, P: PublicId>(
+    evidence: &Evidence<T, P>,
+    offender: &P,
+    genesis_group: Option<&BTreeSet<P>>,
+) -> bool {
+    let hashes = if let Some(hashes) = evidence
+        .events
+        .iter()
+        .map(PackedEvent::verify_signature)
+        .collect::<Option<Vec<_>>>()
+    {
+        hashes
+    } else {
+        return false;
+    };
+    let events = evidence.events.iter().zip(hashes).collect::<Vec<_>>();
+    let by_offender = |event: &PackedEvent<T, P>| event.creator() == offender;
+
+    match (&evidence.malice, &events[..]) {
+        // Two different events by the offender with the same self-parent.
+        (Malice::Fork(self_parent), [(first, first_hash), (second, second_hash)]) => {
+            by_offender(first)
+                && by_offender(second)
+                && first_hash != second_hash
+                && first.self_parent() == Some(self_parent)
+                && second.self_parent() == Some(self_parent)
+        }
+        // Two different events by the offender voting for the same observation.
+        (
+            Malice::DuplicateVote(first_accused, second_accused),
+            [(first, first_hash), (second, second_hash)],
+        ) => {
+            by_offender(first)
+                && by_offender(second)
+                && first_hash != second_hash
+                && first_hash == first_accused
+                && second_hash == second_accused
+                && first.payload().is_some()
+                && first.payload() == second.payload()
+        }
+        // A genesis vote by the offender, who isn't in the genesis group known to the verifier or
+        // whose vote doesn't follow its initial event. The group carried by the vote itself is
+        // chosen by the offender, so it proves nothing.
+        (
+            Malice::UnexpectedGenesis(accused),
+            [(event, event_hash), (self_parent, self_parent_hash)],
+        ) => {
+            match event.payload() {
+                Some(Observation::Genesis { .. }) => (),
+                _ => return false,
+            }
+            let is_genesis_member = genesis_group.map_or(true, |group| group.contains(offender));
+
+            by_offender(event)
+                && by_offender(self_parent)
+                && event_hash == accused
+                && event.self_parent() == Some(self_parent_hash)
+                && (!is_genesis_member || !self_parent.is_initial())
+        }
+        // An event by the offender whose other-parent is its own too.
+        (
+            Malice::OtherParentBySameCreator(accused),
+            [(event, _), (other_parent, other_parent_hash)],
+        ) => {
+            by_offender(event)
+                && *event == &**accused
+                && event.other_parent() == Some(other_parent_hash)
+                && by_offender(other_parent)
+        }
+        // An event by the offender whose self-parent isn't its own.
+        (
+            Malice::SelfParentByDifferentCreator(accused),
+            [(event, _), (self_parent, self_parent_hash)],
+        ) => {
+            by_offender(event)
+                && *event == &**accused
+                && event.self_parent() == Some(self_parent_hash)
+                && !by_offender(self_parent)
+        }
+        // A request by the offender whose other-parent isn't a `Requesting` event addressed to it.
+        (Malice::InvalidRequest(accused), [(event, _), (other_parent, other_parent_hash)]) => {
+            by_offender(event)
+                && *event == &**accused
+                && event.is_request()
+                && event.other_parent() == Some(other_parent_hash)
+                && other_parent.requesting_recipient() != Some(offender)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::{
+        gossip::EventHash,
+        mock::{PeerId, Transaction},
+    };
+
+    fn duplicate_vote_evidence(
+        creator: &PeerId,
+        first_payload: &str,
+        second_payload: &str,
+    ) -> Evidence<Transaction, PeerId> {
+        let initial = PackedEvent::new_initial(creator.clone());
+        let first = PackedEvent::new_observation(
+            creator.clone(),
+            initial.compute_hash(),
+            Observation::OpaquePayload(Transaction::new(first_payload)),
+        );
+        let second = PackedEvent::new_observation(
+            creator.clone(),
+            first.compute_hash(),
+            Observation::OpaquePayload(Transaction::new(second_payload)),
+        );
+        let malice = Malice::DuplicateVote(first.compute_hash(), second.compute_hash());
+        Evidence::new(malice, vec![first, second])
+    }
+
+    #[test]
+    fn duplicate_vote() {
+        let alice = PeerId::new("Alice");
+        let bob = PeerId::new("Bob");
+
+        let evidence = duplicate_vote_evidence(&alice, "ABCD", "ABCD");
+        assert!(verify_evidence(&evidence, &alice));
+        assert!(!verify_evidence(&evidence, &bob));
+
+        let evidence = duplicate_vote_evidence(&alice, "ABCD", "EFGH");
+        assert!(!verify_evidence(&evidence, &alice));
+    }
+
+    #[test]
+    fn fork() {
+        let alice = PeerId::new("Alice");
+        let bob = PeerId::new("Bob");
+        let initial = PackedEvent::new_initial(alice.clone());
+        let first = PackedEvent::new_requesting(alice.clone(), bob.clone(), initial.compute_hash());
+        let second = PackedEvent::new_observation(
+            alice.clone(),
+            initial.compute_hash(),
+            Observation::OpaquePayload(Transaction::new("ABCD")),
+        );
+
+        let evidence = Evidence::new(
+            Malice::Fork(initial.compute_hash()),
+            vec![first.clone(), second],
+        );
+        assert!(verify_evidence(&evidence, &alice));
+
+        // The same event twice isn't a fork.
+        let evidence = Evidence::new(
+            Malice::Fork(initial.compute_hash()),
+            vec![first.clone(), first],
+        );
+        assert!(!verify_evidence(&evidence, &alice));
+    }
+
+    #[test]
+    fn unexpected_genesis() {
+        let alice = PeerId::new("Alice");
+        let bob = PeerId::new("Bob");
+        let genesis_group = btree_set![alice.clone()];
+        let genesis_vote = |creator: &PeerId, self_parent: &PackedEvent<Transaction, PeerId>| {
+            PackedEvent::new_observation(
+                creator.clone(),
+                self_parent.compute_hash(),
+                Observation::Genesis {
+                    group: btree_set![alice.clone(), bob.clone()],
+                    related_info: vec![],
+                },
+            )
+        };
+
+        // Bob's genesis vote follows his initial event, but he isn't a genesis member, however
+        // much his vote claims he is.
+        let initial = PackedEvent::new_initial(bob.clone());
+        let vote = genesis_vote(&bob, &initial);
+        let evidence = Evidence::new(
+            Malice::UnexpectedGenesis(vote.compute_hash()),
+            vec![vote, initial],
+        );
+        assert!(!verify_evidence(&evidence, &bob));
+        assert!(verify_evidence_with_genesis(
+            &evidence,
+            &bob,
+            &genesis_group
+        ));
+
+        // Alice is a genesis member, but her genesis vote doesn't follow her initial event.
+        let initial = PackedEvent::new_initial(alice.clone());
+        let request =
+            PackedEvent::new_requesting(alice.clone(), bob.clone(), initial.compute_hash());
+        let vote = genesis_vote(&alice, &request);
+        let evidence = Evidence::new(
+            Malice::UnexpectedGenesis(vote.compute_hash()),
+            vec![vote, request],
+        );
+        assert!(verify_evidence(&evidence, &alice));
+
+        // The vote's self-parent has to be the offender's own event.
+        let vote = genesis_vote(&alice, &PackedEvent::new_initial(bob.clone()));
+        let evidence = Evidence::new(
+            Malice::UnexpectedGenesis(vote.compute_hash()),
+            vec![vote, PackedEvent::new_initial(bob.clone())],
+        );
+        assert!(!verify_evidence_with_genesis(
+            &evidence,
+            &alice,
+            &genesis_group
+        ));
+    }
+
+    #[test]
+    fn missing_events() {
+        let alice = PeerId::new("Alice");
+        let evidence = Evidence::<Transaction, PeerId>::new(
+            Malice::Fork(EventHash::ZERO),
+            vec![PackedEvent::new_initial(alice.clone())],
+        );
+        assert!(!verify_evidence(&evidence, &alice));
+    }
+}
@@ -7,18 +7,38 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
-    block::Block,
-    dev_utils::{new_common_rng, new_rng, parse_test_dot_file, Record, RngChoice, TestIterator},
+    block::{Block, BlockSink, ConsensusBatch},
+    config::{DkgPolicy, ParsecConfig, QuorumPolicy, SpamPolicy},
+    dev_utils::{
+        new_common_rng, new_rng, parse_dot_file, parse_test_dot_file, Record, RngChoice,
+        TestIterator,
+    },
     error::Error,
-    gossip::{Event, Graph, GraphSnapshot},
+    gossip::{
+        Capabilities, Event, Graph, GraphSnapshot, GraphStorage, Response, MIN_PROTOCOL_VERSION,
+        PROTOCOL_VERSION, SPLIT_SYNC_VERSION,
+    },
     id::{Proof, PublicId},
+    key_gen::dkg_status::DkgFailure,
     meta_voting::MetaElectionSnapshot,
     mock::{self, PeerId, Transaction},
-    observation::{ConsensusMode, Observation},
+    observation::{ConsensusMode, KeyRotationProof, MaliceKind, Observation, MAX_WEIGHT},
     parsec::TestParsec,
     peer_list::{PeerListSnapshot, PeerState},
+    section_summary::SignedSectionSummary,
+    vote::Vote,
+};
+use log::LogLevelFilter;
+use maidsafe_utilities::serialisation;
+use rand::Rng;
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    env, fs, iter,
+    num::NonZeroUsize,
+    process,
+    rc::Rc,
 };
-use std::collections::BTreeSet;
 
 // Use Fixed seed for functional tests: No randomization.
 static SEED: RngChoice = RngChoice::SeededXor([1, 2, 3, 4]);
@@ -63,7 +83,7 @@ fn from_existing() {
         our_id.clone(),
         &peers,
         &peers,
-        ConsensusMode::Supermajority,
+        ParsecConfig::default(),
         new_rng(&mut common_rng),
     );
 
@@ -88,7 +108,7 @@ fn from_existing_requires_non_empty_genesis_group() {
         our_id,
         &BTreeSet::new(),
         &peers,
-        ConsensusMode::Supermajority,
+        ParsecConfig::default(),
         new_rng(&mut common_rng),
     );
 }
@@ -108,7 +128,7 @@ fn from_existing_requires_that_genesis_group_does_not_contain_us() {
         our_id,
         &genesis_group,
         &section,
-        ConsensusMode::Supermajority,
+        ParsecConfig::default(),
         new_rng(&mut common_rng),
     );
 }
@@ -127,7 +147,7 @@ fn from_existing_requires_non_empty_section() {
         our_id,
         &genesis_group,
         &BTreeSet::new(),
-        ConsensusMode::Supermajority,
+        ParsecConfig::default(),
         new_rng(&mut common_rng),
     );
 }
@@ -147,7 +167,7 @@ fn from_existing_requires_that_section_does_not_contain_us() {
         our_id,
         &genesis_group,
         &section,
-        ConsensusMode::Supermajority,
+        ParsecConfig::default(),
         new_rng(&mut common_rng),
     );
 }
@@ -162,7 +182,7 @@ fn from_genesis() {
     let parsec = TestParsec::<Transaction, _>::from_genesis(
         our_id.clone(),
         &peers,
-        ConsensusMode::Supermajority,
+        ParsecConfig::default(),
         new_rng(&mut common_rng),
     );
     // the peer_list should contain the entire genesis group
@@ -188,6 +208,66 @@ fn from_genesis() {
     }
 }
 
+#[test]
+fn from_genesis_with_config() {
+    let mut common_rng = new_common_rng(SEED);
+    let peers = mock::create_ids(4);
+    let our_id = unwrap!(peers.first()).clone();
+    let their_id = unwrap!(peers.last()).clone();
+    let peers = peers.into_iter().collect();
+
+    let config = ParsecConfig::default()
+        .with_malice_detection(false)
+        .with_gossip_event_limit(NonZeroUsize::new(1))
+        .with_dkg_policy(DkgPolicy::Fixed(1));
+    let mut parsec = TestParsec::<Transaction, _>::from_genesis(
+        our_id,
+        &peers,
+        config.clone(),
+        new_rng(&mut common_rng),
+    );
+    assert_eq!(*parsec.config(), config);
+    assert!(!parsec.config().malice_detection());
+
+    // The event limit only applies once we agreed with the recipient on a gossip protocol version
+    // which can split syncs, so not to the very first gossip.
+    let request = unwrap!(parsec.create_gossip(&their_id));
+    assert!(!request.has_more());
+    assert!(request.packed_events.len() > 1);
+
+    // A fixed DKG threshold is capped at the number of participants minus one.
+    let quorum_policy = QuorumPolicy::default();
+    assert_eq!(DkgPolicy::Fixed(1).threshold(4, quorum_policy), 1);
+    assert_eq!(DkgPolicy::Fixed(10).threshold(4, quorum_policy), 3);
+    assert_eq!(DkgPolicy::ToleratingFaults.threshold(4, quorum_policy), 1);
+
+    // A stricter quorum tolerates fewer faulty participants.
+    assert_eq!(DkgPolicy::ToleratingFaults.threshold(8, quorum_policy), 2);
+    let three_quarters = unwrap!(QuorumPolicy::new(3, 4));
+    assert_eq!(DkgPolicy::ToleratingFaults.threshold(8, three_quarters), 1);
+    assert_eq!(QuorumPolicy::new(2, 3), Ok(quorum_policy));
+    assert_eq!(QuorumPolicy::new(3, 5), Err(Error::InvalidQuorumPolicy));
+    assert_eq!(QuorumPolicy::new(1, 1), Err(Error::InvalidQuorumPolicy));
+
+    // A policy has to tolerate a faulty voter of sections in which the default policy does.
+    assert!(three_quarters.is_valid_for(3));
+    assert!(!three_quarters.is_valid_for(4));
+    assert!(three_quarters.is_valid_for(5));
+
+    // Large weights don't overflow the quorum arithmetic.
+    assert!(quorum_policy.is_quorum(usize::MAX, usize::MAX));
+    assert!(!quorum_policy.is_quorum(usize::MAX / 3 * 2, usize::MAX));
+
+    // Deserialising a policy checks it too.
+    let invalid = unwrap!(serialisation::serialise(&(1usize, 1usize)));
+    assert!(serialisation::deserialise::<QuorumPolicy>(&invalid).is_err());
+    let valid = unwrap!(serialisation::serialise(&three_quarters));
+    assert_eq!(
+        unwrap!(serialisation::deserialise::<QuorumPolicy>(&valid)),
+        three_quarters
+    );
+}
+
 // TODO: remove this `cfg` once the `maidsafe_utilities` crate with PR 130 is published.
 #[cfg(feature = "testing")]
 #[test]
@@ -201,7 +281,7 @@ fn from_genesis_requires_the_genesis_group_contains_us() {
     let _ = TestParsec::<Transaction, _>::from_genesis(
         our_id.clone(),
         &peers,
-        ConsensusMode::Supermajority,
+        ParsecConfig::default(),
         new_rng(&mut common_rng),
     );
 }
@@ -266,7 +346,7 @@ fn add_peer() {
         fred_id.clone(),
         &genesis_group,
         &genesis_group,
-        ConsensusMode::Supermajority,
+        ParsecConfig::default(),
         new_rng(&mut common_rng),
     );
 
@@ -280,6 +360,706 @@ fn add_peer() {
     assert!(response.packed_events.len() < fred.graph().len());
 }
 
+#[test]
+fn from_section_summary() {
+    let mut common_rng = new_common_rng(SEED);
+    let fred_id = PeerId::new("Fred");
+    let genesis_group = btree_set![
+        PeerId::new("Alice"),
+        PeerId::new("Bob"),
+        PeerId::new("Carol"),
+        PeerId::new("Dave")
+    ];
+    let config = ParsecConfig::default().with_chained_blocks(true);
+    let mut peers: Vec<TestPeer> = genesis_group
+        .iter()
+        .map(|peer_id| {
+            TestParsec::from_genesis(
+                peer_id.clone(),
+                &genesis_group,
+                config.clone(),
+                new_rng(&mut common_rng),
+            )
+        })
+        .collect();
+    for peer in &mut peers {
+        unwrap!(peer.vote_for(Observation::Add {
+            peer_id: fred_id.clone(),
+            related_info: vec![],
+        }));
+    }
+
+    // Gossip until the section added Fred and all the voters agree on the summary.
+    let ids: Vec<_> = genesis_group.iter().cloned().collect();
+    let mut signed_summaries = vec![];
+    for _ in 0..100 {
+        for src in 0..ids.len() {
+            for dst in (0..ids.len()).filter(|dst| *dst != src) {
+                let request = unwrap!(peers[src].create_gossip(&ids[dst]));
+                let response = unwrap!(peers[dst].handle_request(&ids[src], request));
+                unwrap!(peers[src].handle_response(&ids[dst], response));
+            }
+        }
+
+        signed_summaries = peers
+            .iter()
+            .map(|peer| unwrap!(peer.section_summary()))
+            .collect();
+        let summary = signed_summaries[0].summary();
+        if summary.voters().contains(&fred_id)
+            && signed_summaries
+                .iter()
+                .all(|signed| signed.summary() == summary)
+        {
+            break;
+        }
+    }
+    let summary = signed_summaries[0].summary().clone();
+    assert!(summary.voters().contains(&fred_id));
+    assert!(signed_summaries.iter().all(SignedSectionSummary::is_valid));
+    assert!(!SignedSectionSummary::new(&PeerId::new("Mallory"), summary.clone()).is_valid());
+
+    // The summary carries the tip of the section's chain of blocks.
+    let blocks: Vec<_> = iter::from_fn(|| peers[0].poll()).collect();
+    assert_eq!(summary.next_block_index(), blocks.len() as u64);
+    assert_eq!(summary.chain_tip(), blocks.last().map(Block::hash).as_ref());
+
+    // Not enough of the voters known to Fred signed the summary.
+    let result = TestParsec::<Transaction, _>::from_section_summary(
+        fred_id.clone(),
+        &genesis_group,
+        &genesis_group,
+        &signed_summaries[..2],
+        config.clone(),
+        new_rng(&mut common_rng),
+    );
+    assert_eq!(result.err(), Some(Error::InsufficientVotes));
+
+    // Signatures of peers Fred doesn't know to be voters don't count.
+    let section = btree_set![
+        PeerId::new("Alice"),
+        PeerId::new("Eric"),
+        PeerId::new("Gina"),
+        PeerId::new("Hank")
+    ];
+    let result = TestParsec::<Transaction, _>::from_section_summary(
+        fred_id.clone(),
+        &genesis_group,
+        &section,
+        &signed_summaries,
+        config.clone(),
+        new_rng(&mut common_rng),
+    );
+    assert_eq!(result.err(), Some(Error::InsufficientVotes));
+
+    let new_fred = |common_rng: &mut _| {
+        unwrap!(TestParsec::from_section_summary(
+            fred_id.clone(),
+            &genesis_group,
+            &genesis_group,
+            &signed_summaries,
+            config.clone(),
+            new_rng(common_rng),
+        ))
+    };
+    let mut fred = new_fred(&mut common_rng);
+    let mut late_fred = new_fred(&mut common_rng);
+    assert!(fred.is_catching_up());
+
+    // Fred is caught up with the section from its current meta-election, without the events
+    // preceding it.
+    let catch_up = unwrap!(peers[0].create_catch_up(&fred_id));
+    let response = unwrap!(fred.handle_catch_up(&ids[0], catch_up));
+    unwrap!(peers[0].handle_response(&fred_id, response));
+    assert!(!fred.is_catching_up());
+    assert!(fred.graph().iter().count() < peers[0].graph().iter().count());
+
+    // Fred then reaches consensus along with the section, and outputs the same blocks.
+    let opaque = Observation::OpaquePayload(Transaction::new("caught up"));
+    for peer in &mut peers {
+        unwrap!(peer.vote_for(opaque.clone()));
+    }
+    peers.push(fred);
+    let ids: Vec<_> = ids.into_iter().chain(iter::once(fred_id.clone())).collect();
+    let mut fred_blocks = vec![];
+    for _ in 0..100 {
+        for src in 0..ids.len() {
+            for dst in (0..ids.len()).filter(|dst| *dst != src) {
+                let request = unwrap!(peers[src].create_gossip(&ids[dst]));
+                let response = unwrap!(peers[dst].handle_request(&ids[src], request));
+                unwrap!(peers[src].handle_response(&ids[dst], response));
+            }
+        }
+
+        fred_blocks.extend(iter::from_fn(|| peers[4].poll()));
+        if fred_blocks.iter().any(|block| *block.payload() == opaque) {
+            break;
+        }
+    }
+    let blocks: Vec<_> = iter::from_fn(|| peers[0].poll())
+        .take(fred_blocks.len())
+        .collect();
+    assert!(fred_blocks.iter().any(|block| *block.payload() == opaque));
+    assert_eq!(fred_blocks, blocks);
+    let link = unwrap!(fred_blocks[0].chain_link());
+    assert_eq!(link.index, summary.next_block_index());
+    assert_eq!(link.previous.as_ref(), summary.chain_tip());
+
+    // A catch-up created at a later meta-election than the summary is rejected.
+    let catch_up = unwrap!(peers[0].create_catch_up(&fred_id));
+    assert_eq!(
+        late_fred.handle_catch_up(&ids[0], catch_up).err(),
+        Some(Error::MismatchedSummary)
+    );
+    assert!(late_fred.is_catching_up());
+}
+
+#[test]
+fn dkg_status_and_cancellation() {
+    let mut common_rng = new_common_rng(SEED);
+    let genesis_group = btree_set![
+        PeerId::new("Alice"),
+        PeerId::new("Bob"),
+        PeerId::new("Carol"),
+        PeerId::new("Dave")
+    ];
+    let mut peers: Vec<TestPeer> = genesis_group
+        .iter()
+        .map(|peer_id| {
+            TestParsec::from_genesis(
+                peer_id.clone(),
+                &genesis_group,
+                ParsecConfig::default(),
+                new_rng(&mut common_rng),
+            )
+        })
+        .collect();
+    for peer in &mut peers {
+        unwrap!(peer.vote_for(Observation::StartDkg(genesis_group.clone())));
+    }
+    assert_eq!(peers[0].check_dkg(0), Err(Error::UnknownDkg));
+
+    let ids: Vec<_> = genesis_group.iter().cloned().collect();
+    let gossip = |peers: &mut Vec<TestPeer>| {
+        for src in 0..ids.len() {
+            for dst in (0..ids.len()).filter(|dst| *dst != src) {
+                let request = unwrap!(peers[src].create_gossip(&ids[dst]));
+                let response = unwrap!(peers[dst].handle_request(&ids[src], request));
+                unwrap!(peers[src].handle_response(&ids[dst], response));
+            }
+        }
+    };
+
+    // Gossip until Alice started the session.
+    for _ in 0..100 {
+        if peers[0].check_dkg(0).is_ok() {
+            break;
+        }
+        gossip(&mut peers);
+    }
+    let status = unwrap!(peers[0].dkg_status().remove(&0));
+    assert_eq!(*status.participants(), genesis_group);
+    assert!(!status.is_ready());
+
+    unwrap!(peers[0].cancel_dkg(0));
+    assert!(peers[0].dkg_status().is_empty());
+    let cancelled = Err(Error::FailedDkg(DkgFailure::Cancelled));
+    assert_eq!(peers[0].check_dkg(0), cancelled);
+    assert_eq!(peers[0].cancel_dkg(0), cancelled);
+    assert_eq!(peers[0].cancel_dkg(1), Err(Error::UnknownDkg));
+
+    // The other peers still complete the session without Alice.
+    for _ in 0..100 {
+        if peers[1].dkg_status().is_empty() {
+            break;
+        }
+        gossip(&mut peers);
+    }
+    assert!(peers[1].dkg_status().is_empty());
+    assert_eq!(peers[1].check_dkg(0), Ok(()));
+    assert_eq!(peers[0].check_dkg(0), cancelled);
+}
+
+#[test]
+fn threshold_coin() {
+    let mut common_rng = new_common_rng(SEED);
+    let genesis_group = btree_set![
+        PeerId::new("Alice"),
+        PeerId::new("Bob"),
+        PeerId::new("Carol"),
+        PeerId::new("Dave")
+    ];
+    let mut peers: Vec<TestPeer> = genesis_group
+        .iter()
+        .map(|peer_id| {
+            TestParsec::from_genesis(
+                peer_id.clone(),
+                &genesis_group,
+                ParsecConfig::default(),
+                new_rng(&mut common_rng),
+            )
+        })
+        .collect();
+    let ids: Vec<_> = genesis_group.iter().cloned().collect();
+    // Gossips until every peer polled `count` blocks with a payload matching `is_expected`.
+    let mut gossip_until_blocks =
+        |peers: &mut Vec<TestPeer>,
+         count: usize,
+         is_expected: &dyn Fn(&Observation<_, _>) -> bool| {
+            let mut polled = vec![0; peers.len()];
+            for _ in 0..1000 {
+                for (peer, polled) in peers.iter_mut().zip(polled.iter_mut()) {
+                    while *polled < count {
+                        match peer.poll() {
+                            Some(block) if is_expected(block.payload()) => *polled += 1,
+                            Some(_) => (),
+                            None => break,
+                        }
+                    }
+                }
+                if polled.iter().all(|polled| *polled == count) {
+                    return;
+                }
+                // Gossip between random pairs, so the peers don't all see the same events.
+                for _ in 0..ids.len() {
+                    let src = common_rng.gen_range(0, ids.len());
+                    let dst = (src + common_rng.gen_range(1, ids.len())) % ids.len();
+                    let request = unwrap!(peers[src].create_gossip(&ids[dst]));
+                    let response = unwrap!(peers[dst].handle_request(&ids[src], request));
+                    unwrap!(peers[src].handle_response(&ids[dst], response));
+                }
+            }
+            panic!("Not every peer polled the expected blocks.");
+        };
+    let count_coin_shares = |peer: &TestPeer| {
+        peer.graph()
+            .iter()
+            .filter(|event| {
+                peer.event_payload(event.inner())
+                    .map_or(false, Observation::is_coin_share)
+            })
+            .count()
+    };
+
+    // Until the voters run a DKG among themselves, the coin alternates with the round.
+    for peer in &peers {
+        assert_eq!(peer.toss_coin(&ids[0], 1), Some(true));
+        assert_eq!(peer.toss_coin(&ids[0], 2), Some(false));
+    }
+
+    // The common coin switches to threshold keys once the voters ran a DKG among themselves. No
+    // share of these tosses has been gossiped yet, so they can't be done.
+    for peer in &mut peers {
+        unwrap!(peer.vote_for(Observation::StartDkg(genesis_group.clone())));
+    }
+    gossip_until_blocks(&mut peers, 1, &Observation::is_dkg_result);
+    for peer in &peers {
+        assert_eq!(count_coin_shares(peer), 0);
+        assert_eq!(peer.toss_coin(&ids[0], 1), None);
+        assert_eq!(peer.toss_coin(&ids[0], 2), None);
+    }
+
+    // Each peer votes for the payloads in a different order, so the peers' interesting events
+    // differ. Keep voting until some meta-votes needed the coin to decide, and check that the
+    // peers still reach consensus on the payloads voted for while it was being tossed.
+    for round in 0..50 {
+        let payloads: Vec<_> = ids
+            .iter()
+            .map(|id| Observation::OpaquePayload(Transaction::new(format!("{:?}{}", id, round))))
+            .collect();
+        for (offset, peer) in peers.iter_mut().enumerate() {
+            for payload in payloads.iter().cycle().skip(offset).take(payloads.len()) {
+                unwrap!(peer.vote_for(payload.clone()));
+            }
+        }
+        gossip_until_blocks(&mut peers, payloads.len(), &|polled| {
+            payloads.contains(polled)
+        });
+        if peers.iter().all(|peer| count_coin_shares(peer) > 0) {
+            return;
+        }
+    }
+    panic!("The threshold coin was never tossed.");
+}
+
+#[test]
+fn block_signature_timeout() {
+    let genesis_group = btree_set![
+        PeerId::new("Alice"),
+        PeerId::new("Bob"),
+        PeerId::new("Carol"),
+        PeerId::new("Dave")
+    ];
+    let ids: Vec<_> = genesis_group.iter().cloned().collect();
+    let payload = Observation::OpaquePayload(Transaction::new("ABCD"));
+
+    // Votes for `payload`, gossips the same way for the given configuration and returns the
+    // payloads of the blocks each peer polled.
+    let polled_payloads = |config: ParsecConfig| {
+        let mut common_rng = new_common_rng(SEED);
+        let mut peers: Vec<TestPeer> = ids
+            .iter()
+            .map(|peer_id| {
+                TestParsec::from_genesis(
+                    peer_id.clone(),
+                    &genesis_group,
+                    config.clone(),
+                    new_rng(&mut common_rng),
+                )
+            })
+            .collect();
+        for peer in &mut peers {
+            unwrap!(peer.vote_for(payload.clone()));
+        }
+        for _ in 0..20 {
+            for src in 0..ids.len() {
+                let dst = (src + 1) % ids.len();
+                let request = unwrap!(peers[src].create_gossip(&ids[dst]));
+                let response = unwrap!(peers[dst].handle_request(&ids[src], request));
+                unwrap!(peers[src].handle_response(&ids[dst], response));
+            }
+        }
+        peers
+            .iter_mut()
+            .map(|peer| {
+                iter::from_fn(|| peer.poll())
+                    .map(|block| block.payload().clone())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // Without any DKG, there are no keys to sign the blocks with. By default, they're output
+    // unsigned.
+    let config = ParsecConfig::default().with_block_signatures(true);
+    for payloads in polled_payloads(config.clone()) {
+        assert!(payloads.contains(&payload));
+    }
+
+    // Without a timeout, they're held back instead.
+    for payloads in polled_payloads(config.with_block_signature_timeout(None)) {
+        assert!(payloads.is_empty());
+    }
+}
+
+#[test]
+fn change_weight() {
+    let mut common_rng = new_common_rng(SEED);
+    let genesis_group = btree_set![
+        PeerId::new("Alice"),
+        PeerId::new("Bob"),
+        PeerId::new("Carol"),
+        PeerId::new("Dave")
+    ];
+    let mut peers: Vec<TestPeer> = genesis_group
+        .iter()
+        .map(|peer_id| {
+            TestParsec::from_genesis(
+                peer_id.clone(),
+                &genesis_group,
+                ParsecConfig::default(),
+                new_rng(&mut common_rng),
+            )
+        })
+        .collect();
+    let ids: Vec<_> = genesis_group.iter().cloned().collect();
+    let gossip = |peers: &mut Vec<TestPeer>| {
+        for src in 0..ids.len() {
+            for dst in (0..ids.len()).filter(|dst| *dst != src) {
+                let request = unwrap!(peers[src].create_gossip(&ids[dst]));
+                let response = unwrap!(peers[dst].handle_request(&ids[src], request));
+                unwrap!(peers[src].handle_response(&ids[dst], response));
+            }
+        }
+    };
+    // Gossips until every peer polled the block with the given payload, and returns these blocks.
+    let gossip_until_block = |peers: &mut Vec<TestPeer>, payload: &Observation<_, _>| {
+        let mut blocks: Vec<Option<Block<Transaction, PeerId>>> = vec![None; peers.len()];
+        for _ in 0..100 {
+            for (peer, block) in peers.iter_mut().zip(blocks.iter_mut()) {
+                while block.is_none() {
+                    match peer.poll() {
+                        Some(polled) => {
+                            if polled.payload() == payload {
+                                *block = Some(polled);
+                            }
+                        }
+                        None => break,
+                    }
+                }
+            }
+            if blocks.iter().all(Option::is_some) {
+                break;
+            }
+            gossip(peers);
+        }
+        blocks
+            .into_iter()
+            .map(|block| unwrap!(block))
+            .collect::<Vec<_>>()
+    };
+
+    for &weight in &[0, MAX_WEIGHT + 1] {
+        assert_eq!(
+            peers[0].vote_for(Observation::ChangeWeight {
+                peer_id: ids[0].clone(),
+                weight,
+            }),
+            Err(Error::InvalidWeight)
+        );
+    }
+
+    let change_weight = Observation::ChangeWeight {
+        peer_id: ids[0].clone(),
+        weight: 4,
+    };
+    for peer in &mut peers {
+        unwrap!(peer.vote_for(change_weight.clone()));
+    }
+    let _ = gossip_until_block(&mut peers, &change_weight);
+    for peer in &peers {
+        let alice_index = unwrap!(peer.peer_list().get_index(&ids[0]));
+        assert_eq!(peer.meta_election().weight(alice_index), 4);
+        assert_eq!(peer.meta_election().voters_weight(), 7);
+    }
+
+    // Alice alone now holds more than a third of the weight, so nothing can be consensused
+    // without her, but she and two others are a quorum.
+    let voters: BTreeMap<_, _> = ids
+        .iter()
+        .map(|id| (id.clone(), if *id == ids[0] { 4 } else { 1 }))
+        .collect();
+    let opaque = Observation::OpaquePayload(Transaction::new("weighted"));
+    for peer in &mut peers {
+        unwrap!(peer.vote_for(opaque.clone()));
+    }
+    let mode = ConsensusMode::Supermajority;
+    let quorum_policy = QuorumPolicy::default();
+    for block in gossip_until_block(&mut peers, &opaque) {
+        unwrap!(block.verify_weighted(&voters, mode, quorum_policy));
+    }
+
+    // Three out of four voters are a quorum by count but not by weight.
+    let votes = ids[1..]
+        .iter()
+        .map(|id| (id.clone(), Vote::new(id, opaque.clone())))
+        .collect();
+    let block = unwrap!(Block::new(&votes));
+    let voter_ids = ids.iter().cloned().collect();
+    unwrap!(block.verify(&voter_ids, mode));
+    assert_eq!(
+        block.verify_weighted(&voters, mode, quorum_policy),
+        Err(Error::InsufficientVotes)
+    );
+}
+
+#[test]
+fn reconfigure() {
+    let mut common_rng = new_common_rng(SEED);
+    let genesis_group = btree_set![
+        PeerId::new("Alice"),
+        PeerId::new("Bob"),
+        PeerId::new("Carol"),
+        PeerId::new("Dave")
+    ];
+    let mut peers: Vec<TestPeer> = genesis_group
+        .iter()
+        .map(|peer_id| {
+            TestParsec::from_genesis(
+                peer_id.clone(),
+                &genesis_group,
+                ParsecConfig::default(),
+                new_rng(&mut common_rng),
+            )
+        })
+        .collect();
+    let ids: Vec<_> = genesis_group.iter().cloned().collect();
+    let dave = PeerId::new("Dave");
+    let eric = PeerId::new("Eric");
+
+    // Adding a current voter, removing a non-member or both adding and removing a peer is invalid.
+    let fred = PeerId::new("Fred");
+    for (add, remove) in &[
+        (btree_set![eric.clone()], btree_set![eric.clone()]),
+        (
+            btree_set![eric.clone(), ids[1].clone()],
+            btree_set![dave.clone()],
+        ),
+        (btree_set![eric.clone()], btree_set![dave.clone(), fred]),
+    ] {
+        let invalid = Observation::Reconfigure {
+            add: add.clone(),
+            remove: remove.clone(),
+            related_info: vec![],
+        };
+        assert_eq!(
+            peers[0].vote_for(invalid),
+            Err(Error::InvalidReconfiguration)
+        );
+    }
+
+    let reconfigure = Observation::Reconfigure {
+        add: btree_set![eric.clone()],
+        remove: btree_set![dave.clone()],
+        related_info: vec![],
+    };
+    for peer in &mut peers {
+        unwrap!(peer.vote_for(reconfigure.clone()));
+    }
+
+    // Gossip until Alice, Bob and Carol got the block. Dave stops being gossiped with once
+    // removed.
+    let mut done = [false; 3];
+    for _ in 0..100 {
+        for (peer, done) in peers.iter_mut().zip(done.iter_mut()) {
+            while let Some(block) = peer.poll() {
+                *done = *done || *block.payload() == reconfigure;
+            }
+        }
+        if done.iter().all(|done| *done) {
+            break;
+        }
+        for src in 0..ids.len() {
+            for dst in (0..ids.len()).filter(|dst| *dst != src) {
+                if !peers[src].gossip_recipients().any(|id| *id == ids[dst]) {
+                    continue;
+                }
+                let request = unwrap!(peers[src].create_gossip(&ids[dst]));
+                if let Ok(response) = peers[dst].handle_request(&ids[src], request) {
+                    let _ = peers[src].handle_response(&ids[dst], response);
+                }
+            }
+        }
+    }
+    assert!(done.iter().all(|done| *done));
+
+    for peer in &peers[..3] {
+        let eric_index = unwrap!(peer.peer_list().get_index(&eric));
+        assert!(peer.peer_list().peer_state(eric_index).can_vote());
+        let dave_index = unwrap!(peer.peer_list().get_index(&dave));
+        assert!(!peer.peer_list().peer_state(dave_index).can_vote());
+
+        // Both changes were applied together to the voters of the next meta-election.
+        let voters: BTreeSet<_> = peer
+            .meta_election()
+            .voters()
+            .iter()
+            .filter_map(|index| peer.peer_list().get(index).map(|peer| peer.id().clone()))
+            .collect();
+        assert_eq!(
+            voters,
+            btree_set![ids[0].clone(), ids[1].clone(), ids[2].clone(), eric.clone()]
+        );
+    }
+}
+
+#[test]
+fn rotate_key() {
+    let mut common_rng = new_common_rng(SEED);
+    let genesis_group = btree_set![
+        PeerId::new("Alice"),
+        PeerId::new("Bob"),
+        PeerId::new("Carol"),
+        PeerId::new("Dave")
+    ];
+    let mut peers: Vec<TestPeer> = genesis_group
+        .iter()
+        .map(|peer_id| {
+            TestParsec::from_genesis(
+                peer_id.clone(),
+                &genesis_group,
+                ParsecConfig::default(),
+                new_rng(&mut common_rng),
+            )
+        })
+        .collect();
+    let mut ids: Vec<_> = genesis_group.iter().cloned().collect();
+    let alice = ids[0].clone();
+    let new_alice = PeerId::new("NewAlice");
+
+    // Gossips until every peer polled a block with the given payload, and returns these blocks.
+    let gossip_until_block =
+        |peers: &mut Vec<TestPeer>, ids: &[PeerId], payload: &Observation<_, _>| {
+            let mut blocks: Vec<Option<Block<Transaction, PeerId>>> = vec![None; peers.len()];
+            for _ in 0..100 {
+                for (peer, block) in peers.iter_mut().zip(blocks.iter_mut()) {
+                    while block.is_none() {
+                        match peer.poll() {
+                            Some(polled) => {
+                                if polled.payload() == payload {
+                                    *block = Some(polled);
+                                }
+                            }
+                            None => break,
+                        }
+                    }
+                }
+                if blocks.iter().all(Option::is_some) {
+                    break;
+                }
+                for src in 0..ids.len() {
+                    for dst in (0..ids.len()).filter(|dst| *dst != src) {
+                        let request = unwrap!(peers[src].create_gossip(&ids[dst]));
+                        let response = unwrap!(peers[dst].handle_request(&ids[src], request));
+                        unwrap!(peers[src].handle_response(&ids[dst], response));
+                    }
+                }
+            }
+            blocks
+                .into_iter()
+                .map(|block| unwrap!(block))
+                .collect::<Vec<_>>()
+        };
+
+    // The rotation must be signed by both keys.
+    let forged = Observation::RotateKey {
+        old: ids[1].clone(),
+        new: new_alice.clone(),
+        proof: KeyRotationProof::new(&alice, &new_alice),
+    };
+    assert_eq!(peers[0].vote_for(forged), Err(Error::InvalidKeyRotation));
+
+    unwrap!(peers[0].rotate_key(new_alice.clone()));
+
+    // Only the public ID of the new key is saved, so the new key is passed again once restored.
+    let saved = unwrap!(peers[0].save());
+    peers[0] = unwrap!(TestParsec::restore(
+        alice.clone(),
+        &saved,
+        new_rng(&mut common_rng)
+    ));
+    unwrap!(peers[0].rotate_key(new_alice.clone()));
+
+    let rotate_key = Observation::RotateKey {
+        old: alice.clone(),
+        new: new_alice.clone(),
+        proof: KeyRotationProof::new(&alice, &new_alice),
+    };
+    for peer in &mut peers[1..] {
+        unwrap!(peer.vote_for(rotate_key.clone()));
+    }
+    let _ = gossip_until_block(&mut peers, &ids, &rotate_key);
+
+    assert_eq!(*peers[0].our_pub_id(), new_alice);
+    for peer in &peers[1..] {
+        let index = unwrap!(peer.peer_list().get_index(&new_alice));
+        assert_eq!(peer.peer_list().get_index(&alice), Some(index));
+        assert_eq!(*unwrap!(peer.peer_list().get(index)).id(), new_alice);
+        assert!(peer.peer_list().peer_state(index).can_vote());
+    }
+
+    // Alice's events are now signed with the new key, and accepted by the other peers.
+    ids[0] = new_alice.clone();
+    let opaque = Observation::OpaquePayload(Transaction::new("rotated"));
+    for peer in &mut peers {
+        unwrap!(peer.vote_for(opaque.clone()));
+    }
+    for block in gossip_until_block(&mut peers, &ids, &opaque) {
+        assert!(block.is_signed_by(&new_alice));
+        assert!(!block.is_signed_by(&alice));
+    }
+}
+
 #[test]
 fn remove_peer() {
     let mut common_rng = new_common_rng(SEED);
@@ -330,7 +1110,7 @@ fn remove_peer() {
         eric_id.clone(),
         &section,
         &section,
-        ConsensusMode::Supermajority,
+        ParsecConfig::default(),
         new_rng(&mut common_rng),
     );
 
@@ -388,33 +1168,257 @@ fn unpolled_observations() {
     assert_eq!(alice.our_unpolled_observations().count(), 1);
     assert_eq!(*unwrap!(alice.our_unpolled_observations().next()), add_eric);
 
-    // Call `poll()` and retry - should return none.
-    unwrap!(alice.poll());
-    assert!(alice.poll().is_none());
-    assert!(alice.our_unpolled_observations().next().is_none());
-    assert!(!alice.has_unpolled_observations());
+    // Call `poll()` and retry - should return none.
+    unwrap!(alice.poll());
+    assert!(alice.poll().is_none());
+    assert!(alice.our_unpolled_observations().next().is_none());
+    assert!(!alice.has_unpolled_observations());
+
+    // Vote for a new observation and check it is returned as unpolled, and that
+    // `has_unpolled_observations()` returns `true` again.
+    let vote = Observation::OpaquePayload(Transaction::new("ABCD"));
+    unwrap!(alice.vote_for(vote.clone()));
+
+    assert!(alice.has_unpolled_observations());
+    assert_eq!(alice.our_unpolled_observations().count(), 1);
+    assert_eq!(*unwrap!(alice.our_unpolled_observations().next()), vote);
+
+    // Reset, and re-run, this time adding Alice's vote early to check that it is returned in
+    // the correct order, i.e. after `Add(Eric)` at the point where `Add(Eric)` is consensused
+    // but has not been returned by `poll()`.
+    alice = TestParsec::from_parsed_contents(
+        parse_test_dot_file("alice.dot"),
+        new_rng(&mut common_rng),
+    );
+    unwrap!(alice.vote_for(vote.clone()));
+    let mut unpolled_observations = alice.our_unpolled_observations();
+    assert_eq!(*unwrap!(unpolled_observations.next()), add_eric);
+    assert_eq!(*unwrap!(unpolled_observations.next()), vote);
+    assert!(unpolled_observations.next().is_none());
+}
+
+// Returns Alice from the graph of `unpolled_observations` without A_17, the event in which she
+// reaches consensus on `Add(Eric)`, along with A_17.
+fn alice_before_add_eric<R: Rng>(rng: &mut R) -> (TestPeer, Event<PeerId>) {
+    let mut contents = unwrap!(parse_dot_file(
+        "input_graphs/functional_tests_unpolled_observations/alice.dot"
+    ));
+    let a_17 = unwrap!(contents.remove_last_event());
+    (
+        TestParsec::from_parsed_contents(contents, new_rng(rng)),
+        a_17,
+    )
+}
+
+#[test]
+fn save_and_restore() {
+    let mut common_rng = new_common_rng(SEED);
+    let (mut alice, a_17) = alice_before_add_eric(&mut common_rng);
+
+    let saved = unwrap!(alice.save());
+    let mut restored = unwrap!(TestParsec::restore(
+        PeerId::new("Alice"),
+        &saved,
+        new_rng(&mut common_rng),
+    ));
+    assert_eq!(Snapshot::new(&alice), Snapshot::new(&restored));
+    assert_eq!(
+        alice.our_unpolled_observations().collect::<Vec<_>>(),
+        restored.our_unpolled_observations().collect::<Vec<_>>()
+    );
+
+    // Both instances should reach the same consensus when given the same event.
+    unwrap!(alice.add_event(a_17.clone()));
+    unwrap!(restored.add_event(a_17));
+    assert_eq!(Snapshot::new(&alice), Snapshot::new(&restored));
+
+    let alice_blocks: Vec<_> = iter::from_fn(|| alice.poll()).collect();
+    let restored_blocks: Vec<_> = iter::from_fn(|| restored.poll()).collect();
+    assert!(!alice_blocks.is_empty());
+    assert_eq!(alice_blocks, restored_blocks);
+
+    // Restoring with a different ID or from corrupted bytes must fail.
+    assert_eq!(
+        TestParsec::restore(PeerId::new("Bob"), &saved, new_rng(&mut common_rng)).err(),
+        Some(Error::InvalidSavedState)
+    );
+    assert_eq!(
+        TestParsec::restore(
+            PeerId::new("Alice"),
+            &saved[..saved.len() - 1],
+            new_rng(&mut common_rng),
+        )
+        .err(),
+        Some(Error::InvalidSavedState)
+    );
+}
+
+#[test]
+fn save_and_restore_config() {
+    let mut common_rng = new_common_rng(SEED);
+    let peers = mock::create_ids(4);
+    let our_id = unwrap!(peers.first()).clone();
+    let peers = peers.into_iter().collect();
+
+    // Every setting differs from the default.
+    let config = ParsecConfig::default()
+        .with_consensus_mode(ConsensusMode::Single)
+        .with_quorum_policy(unwrap!(QuorumPolicy::new(7, 10)))
+        .with_malice_detection(false)
+        .with_detected_malice(MaliceKind::DuplicateVote, false)
+        .with_spam_policy(SpamPolicy {
+            max_payload_size: 100,
+            observation_allowance: 10,
+            gossip_allowance: 20,
+            section_ratio: 2,
+            window: 30,
+        })
+        .with_removal_threshold(MaliceKind::Fork, NonZeroUsize::new(2))
+        .with_unresponsive_threshold(NonZeroUsize::new(3))
+        .with_invalid_payload_threshold(NonZeroUsize::new(4))
+        .with_gossip_event_limit(NonZeroUsize::new(5))
+        .with_dkg_policy(DkgPolicy::Fixed(1))
+        .with_block_signatures(true)
+        .with_block_signature_timeout(NonZeroUsize::new(7))
+        .with_chained_blocks(true)
+        .with_log_level(LogLevelFilter::Warn);
+    assert_ne!(config, ParsecConfig::default());
+    let mut parsec = TestParsec::<Transaction, _>::from_genesis(
+        our_id.clone(),
+        &peers,
+        config.clone(),
+        new_rng(&mut common_rng),
+    );
+
+    // Settings changed after creating the instance are kept too.
+    parsec.set_gossip_event_limit(NonZeroUsize::new(6));
+    let config = config.with_gossip_event_limit(NonZeroUsize::new(6));
+
+    let restored = unwrap!(TestParsec::restore(
+        our_id,
+        &unwrap!(parsec.save()),
+        new_rng(&mut common_rng),
+    ));
+    assert_eq!(*restored.config(), config);
+}
+
+#[test]
+fn prune() {
+    let mut common_rng = new_common_rng(SEED);
+    let (mut alice, a_17) = alice_before_add_eric(&mut common_rng);
+    unwrap!(alice.add_event(a_17));
+    let blocks: Vec<_> = iter::from_fn(|| alice.poll()).collect();
+    assert!(!blocks.is_empty());
+
+    let hashes: Vec<_> = alice.graph().iter().map(|event| *event.hash()).collect();
+    let pruned = alice.prune();
+    assert!(pruned > 0);
+    assert_eq!(alice.graph().iter().count(), hashes.len() - pruned);
+    assert!(hashes.iter().all(|hash| alice.graph().contains(hash)));
+    assert_eq!(alice.prune(), 0);
+
+    // The pruned state must survive a save and restore, and Alice must still be able to vote.
+    let mut restored = unwrap!(TestParsec::restore(
+        PeerId::new("Alice"),
+        &unwrap!(alice.save()),
+        new_rng(&mut common_rng),
+    ));
+    assert_eq!(Snapshot::new(&alice), Snapshot::new(&restored));
+
+    let observation = Observation::OpaquePayload(Transaction::new("ABCD"));
+    unwrap!(restored.vote_for(observation.clone()));
+    assert!(restored.have_voted_for(&observation));
+}
+
+#[test]
+fn block_sink() {
+    type Batches = Rc<RefCell<Vec<ConsensusBatch<Transaction, PeerId>>>>;
+
+    struct Sink(Batches);
+
+    impl BlockSink<Transaction, PeerId> for Sink {
+        fn handle_block(&mut self, _: Block<Transaction, PeerId>) {
+            panic!("Every batch should be handled as a whole.");
+        }
+
+        fn handle_batch(&mut self, batch: ConsensusBatch<Transaction, PeerId>) {
+            self.0.borrow_mut().push(batch);
+        }
+    }
+
+    let mut common_rng = new_common_rng(SEED);
 
-    // Vote for a new observation and check it is returned as unpolled, and that
-    // `has_unpolled_observations()` returns `true` again.
-    let vote = Observation::OpaquePayload(Transaction::new("ABCD"));
-    unwrap!(alice.vote_for(vote.clone()));
+    let (mut polled, a_17) = alice_before_add_eric(&mut common_rng);
+    let (mut sinked, _) = alice_before_add_eric(&mut common_rng);
+
+    // The blocks already queued are handed to the sink on registration.
+    let queued_batches: Vec<_> = iter::from_fn(|| polled.batch_poll()).collect();
+    let batches = Rc::new(RefCell::new(vec![]));
+    assert!(sinked
+        .set_block_sink(Box::new(Sink(Rc::clone(&batches))))
+        .is_none());
+    assert_eq!(*batches.borrow(), queued_batches);
+    assert!(sinked.poll().is_none());
+
+    // New blocks are handed to the sink as soon as they are consensused, in the same batches as
+    // `batch_poll` returns them.
+    batches.borrow_mut().clear();
+    unwrap!(polled.add_event(a_17.clone()));
+    unwrap!(sinked.add_event(a_17));
+    let new_batches: Vec<_> = iter::from_fn(|| polled.batch_poll()).collect();
+    assert!(!new_batches.is_empty());
+    assert_eq!(*batches.borrow(), new_batches);
+    assert!(sinked.poll().is_none());
+    assert!(sinked.take_block_sink().is_some());
+}
 
-    assert!(alice.has_unpolled_observations());
-    assert_eq!(alice.our_unpolled_observations().count(), 1);
-    assert_eq!(*unwrap!(alice.our_unpolled_observations().next()), vote);
+#[test]
+fn batch_poll() {
+    let mut common_rng = new_common_rng(SEED);
 
-    // Reset, and re-run, this time adding Alice's vote early to check that it is returned in
-    // the correct order, i.e. after `Add(Eric)` at the point where `Add(Eric)` is consensused
-    // but has not been returned by `poll()`.
-    alice = TestParsec::from_parsed_contents(
-        parse_test_dot_file("alice.dot"),
-        new_rng(&mut common_rng),
-    );
-    unwrap!(alice.vote_for(vote.clone()));
-    let mut unpolled_observations = alice.our_unpolled_observations();
-    assert_eq!(*unwrap!(unpolled_observations.next()), add_eric);
-    assert_eq!(*unwrap!(unpolled_observations.next()), vote);
-    assert!(unpolled_observations.next().is_none());
+    let (mut alice, a_17) = alice_before_add_eric(&mut common_rng);
+    let a_17_hash = *a_17.hash();
+    let _ = iter::from_fn(|| alice.batch_poll()).count();
+    let election_index = alice.meta_election().consensus_history().len();
+
+    unwrap!(alice.add_event(a_17));
+    let batch = unwrap!(alice.batch_poll());
+    assert_eq!(*batch.deciding_event(), a_17_hash);
+    assert_eq!(batch.election_index(), election_index);
+    let eric = PeerId::new("Eric");
+    assert!(batch.blocks().iter().any(|block| match block.payload() {
+        Observation::Add { peer_id, .. } => *peer_id == eric,
+        _ => false,
+    }));
+}
+
+#[test]
+fn file_graph_storage() {
+    let mut common_rng = new_common_rng(SEED);
+    let path = env::temp_dir().join(format!("parsec_file_graph_storage_{}", process::id()));
+
+    let (mut memory, a_17) = alice_before_add_eric(&mut common_rng);
+    let (mut file, _) = alice_before_add_eric(&mut common_rng);
+    unwrap!(file.set_graph_storage(GraphStorage::File {
+        path: path.clone(),
+        cache_size: 4,
+    }));
+    assert_eq!(memory.graph(), file.graph());
+
+    // Both instances should reach the same consensus when given the same event.
+    unwrap!(memory.add_event(a_17.clone()));
+    unwrap!(file.add_event(a_17));
+    assert_eq!(Snapshot::new(&memory), Snapshot::new(&file));
+
+    let memory_blocks: Vec<_> = iter::from_fn(|| memory.poll()).collect();
+    let file_blocks: Vec<_> = iter::from_fn(|| file.poll()).collect();
+    assert!(!memory_blocks.is_empty());
+    assert_eq!(memory_blocks, file_blocks);
+
+    assert_eq!(memory.prune(), file.prune());
+    assert_eq!(memory.graph(), file.graph());
+
+    unwrap!(fs::remove_file(path));
 }
 
 #[test]
@@ -455,7 +1459,7 @@ fn gossip_after_fork() {
     let mut alice = TestParsec::from_genesis(
         alice_id.clone(),
         &genesis_group,
-        ConsensusMode::Supermajority,
+        ParsecConfig::default(),
         new_rng(&mut common_rng),
     );
 
@@ -481,7 +1485,7 @@ fn gossip_after_fork() {
     let mut bob = TestParsec::from_genesis(
         bob_id.clone(),
         &genesis_group,
-        ConsensusMode::Supermajority,
+        ParsecConfig::default(),
         new_rng(&mut common_rng),
     );
 
@@ -511,6 +1515,203 @@ fn gossip_after_fork() {
     assert!(bob.graph().contains(&a_2_fork_hash));
 }
 
+#[test]
+fn gossip_in_parts() {
+    let mut common_rng = new_common_rng(SEED);
+    let alice_id = PeerId::new("Alice");
+    let bob_id = PeerId::new("Bob");
+
+    let genesis_group = btree_set![
+        alice_id.clone(),
+        bob_id.clone(),
+        PeerId::new("Carol"),
+        PeerId::new("Dave")
+    ];
+
+    let mut alice = TestParsec::from_genesis(
+        alice_id.clone(),
+        &genesis_group,
+        ParsecConfig::default(),
+        new_rng(&mut common_rng),
+    );
+    let mut bob = TestParsec::from_genesis(
+        bob_id.clone(),
+        &genesis_group,
+        ParsecConfig::default(),
+        new_rng(&mut common_rng),
+    );
+
+    // Syncs can only be split once the peers agreed on a version supporting it.
+    let req = unwrap!(alice.create_gossip(&bob_id));
+    let resp = unwrap!(bob.handle_request(&alice_id, req));
+    unwrap!(alice.handle_response(&bob_id, resp));
+
+    let limit = 2;
+    for parsec in &mut [&mut alice, &mut bob] {
+        parsec.set_gossip_event_limit(NonZeroUsize::new(limit));
+        for i in 0..4 {
+            let payload = format!("{:?} {}", parsec.our_pub_id(), i);
+            unwrap!(parsec.vote_for(Observation::OpaquePayload(Transaction::new(payload))));
+        }
+    }
+    let alice_hashes: Vec<_> = alice.graph().iter().map(|event| *event.hash()).collect();
+    let bob_hashes: Vec<_> = bob.graph().iter().map(|event| *event.hash()).collect();
+
+    // Alice's request is split, Bob acknowledging each part but the last one.
+    let mut num_requests = 0;
+    let resp = loop {
+        let req = unwrap!(alice.create_gossip(&bob_id));
+        assert!(req.packed_events.len() <= limit);
+        num_requests += 1;
+
+        let has_more = req.has_more();
+        let resp = unwrap!(bob.handle_request(&alice_id, req));
+        if !has_more {
+            break resp;
+        }
+        assert!(resp.packed_events.is_empty());
+        assert!(resp.has_more());
+        unwrap!(alice.handle_response(&bob_id, resp));
+    };
+    assert!(num_requests > 1);
+    assert!(alice_hashes.iter().all(|hash| bob.graph().contains(hash)));
+
+    // Bob's response is split too, Alice asking for the rest of it with empty requests.
+    let mut num_responses = 1;
+    let mut resp = resp;
+    while resp.has_more() {
+        assert!(resp.packed_events.len() <= limit);
+        unwrap!(alice.handle_response(&bob_id, resp));
+
+        let req = unwrap!(alice.create_gossip(&bob_id));
+        assert!(req.packed_events.is_empty());
+        assert!(!req.has_more());
+        resp = unwrap!(bob.handle_request(&alice_id, req));
+        num_responses += 1;
+    }
+    assert!(resp.packed_events.len() <= limit);
+    unwrap!(alice.handle_response(&bob_id, resp));
+    assert!(num_responses > 1);
+    assert!(bob_hashes.iter().all(|hash| alice.graph().contains(hash)));
+
+    // The sync is complete, so the next gossip starts a new one.
+    let req = unwrap!(alice.create_gossip(&bob_id));
+    let last_event = unwrap!(alice.graph().get(alice.our_last_event_index()));
+    assert!(last_event.is_requesting());
+    assert_eq!(
+        unwrap!(req.packed_events.last()).compute_hash(),
+        *last_event.hash()
+    );
+}
+
+#[test]
+fn gossip_version_handshake() {
+    let mut common_rng = new_common_rng(SEED);
+    let alice_id = PeerId::new("Alice");
+    let bob_id = PeerId::new("Bob");
+
+    let genesis_group = btree_set![
+        alice_id.clone(),
+        bob_id.clone(),
+        PeerId::new("Carol"),
+        PeerId::new("Dave")
+    ];
+
+    let mut alice = TestPeer::from_genesis(
+        alice_id.clone(),
+        &genesis_group,
+        ParsecConfig::default(),
+        new_rng(&mut common_rng),
+    );
+    let mut bob = TestParsec::from_genesis(
+        bob_id.clone(),
+        &genesis_group,
+        ParsecConfig::default(),
+        new_rng(&mut common_rng),
+    );
+
+    // The first exchange carries the capabilities of both peers. The request uses the oldest
+    // version, as Alice doesn't know which ones Bob supports yet.
+    let req = unwrap!(alice.create_gossip(&bob_id));
+    assert_eq!(req.version(), MIN_PROTOCOL_VERSION);
+    assert_eq!(req.capabilities, Some(Capabilities::ours()));
+    let resp = unwrap!(bob.handle_request(&alice_id, req));
+    assert_eq!(resp.version(), PROTOCOL_VERSION);
+    assert_eq!(resp.capabilities, Some(Capabilities::ours()));
+    unwrap!(alice.handle_response(&bob_id, resp));
+
+    // Once agreed on a version, it isn't needed anymore, both ways.
+    let req = unwrap!(alice.create_gossip(&bob_id));
+    assert_eq!(req.version(), PROTOCOL_VERSION);
+    assert_eq!(req.capabilities, None);
+    let resp = unwrap!(bob.handle_request(&alice_id, req));
+    assert_eq!(resp.capabilities, None);
+    unwrap!(alice.handle_response(&bob_id, resp));
+    let req = unwrap!(bob.create_gossip(&alice_id));
+    assert_eq!(req.capabilities, None);
+
+    // Messages with versions we don't support are rejected.
+    let req = unwrap!(alice.create_gossip(&bob_id));
+    let req = req.with_version(PROTOCOL_VERSION + 1, None);
+    assert_eq!(
+        bob.handle_request(&alice_id, req),
+        Err(Error::IncompatibleVersion)
+    );
+
+    // As are peers with which no version can be agreed on.
+    let carol_id = PeerId::new("Carol");
+    let mut carol = TestParsec::from_genesis(
+        carol_id.clone(),
+        &genesis_group,
+        ParsecConfig::default(),
+        new_rng(&mut common_rng),
+    );
+    let req = unwrap!(carol.create_gossip(&alice_id));
+    let capabilities = Capabilities {
+        min_version: PROTOCOL_VERSION + 1,
+        max_version: PROTOCOL_VERSION + 2,
+    };
+    let req = req.with_version(PROTOCOL_VERSION + 1, Some(capabilities));
+    assert_eq!(
+        alice.handle_request(&carol_id, req),
+        Err(Error::IncompatibleVersion)
+    );
+
+    // Peers which only support the first version are sent whole syncs, in messages without the
+    // `has_more` flag.
+    let dave_id = PeerId::new("Dave");
+    let mut dave = TestParsec::from_genesis(
+        dave_id.clone(),
+        &genesis_group,
+        ParsecConfig::default(),
+        new_rng(&mut common_rng),
+    );
+    alice.set_gossip_event_limit(NonZeroUsize::new(1));
+    let capabilities = Capabilities {
+        min_version: 1,
+        max_version: 1,
+    };
+    let req = unwrap!(dave.create_gossip(&alice_id)).with_version(1, Some(capabilities));
+    let resp = unwrap!(alice.handle_request(&dave_id, req));
+    assert_eq!(resp.version(), 1);
+    assert!(!resp.has_more());
+    assert!(resp.packed_events.len() > 1);
+
+    let serialised = unwrap!(serialisation::serialise(&resp));
+    let deserialised: Response<Transaction, PeerId> =
+        unwrap!(serialisation::deserialise(&serialised));
+    assert_eq!(deserialised, resp);
+    let split_resp = resp
+        .clone()
+        .with_version(SPLIT_SYNC_VERSION, resp.capabilities);
+    let split_serialised = unwrap!(serialisation::serialise(&split_resp));
+    assert_eq!(split_serialised.len(), serialised.len() + 1);
+    assert_eq!(
+        unwrap!(Response::from_compact_bytes(&resp.to_compact_bytes())),
+        resp
+    );
+}
+
 #[test]
 fn sees() {
     let mut common_rng = new_common_rng(SEED);
@@ -549,16 +1750,16 @@ fn sees() {
     assert!(!a3.sees(c2_1));
 }
 
-#[cfg(feature = "malice-detection")]
 mod handle_malice {
     use super::*;
     use crate::{
-        dev_utils::{parse_test_dot_file, ParsedContents},
+        dev_utils::{parse_dot_file, parse_test_dot_file, ParsedContents},
+        evidence::{verify_evidence, verify_evidence_with_genesis},
         gossip::{Event, EventHash},
         id::SecretId,
         mock::{self, Transaction},
-        network_event::NetworkEvent,
-        observation::Malice,
+        network_event::{NetworkEvent, PayloadValidator},
+        observation::{Malice, MaliceKind, UnprovableMalice},
         peer_list::{PeerIndex, PeerList, PeerState},
         PackedEvent, Request, Response,
     };
@@ -694,7 +1895,7 @@ mod handle_malice {
         let e_1 = unwrap!(eric_contents.new_event_from_observation(
             e_0_index,
             Observation::Genesis {
-                group: genesis,
+                group: genesis.clone(),
                 related_info: vec![]
             },
         ));
@@ -720,6 +1921,12 @@ mod handle_malice {
 
         assert_eq!(offender, eric_id);
         assert_eq!(hash, e_1_hash);
+
+        // Eric's genesis vote follows his initial event, so the evidence only proves the malice to
+        // those knowing he isn't in the genesis group.
+        let evidence = unwrap!(alice.malice_evidence(&Malice::UnexpectedGenesis(hash)));
+        assert!(verify_evidence_with_genesis(&evidence, &eric_id, &genesis));
+        assert!(!verify_evidence(&evidence, &eric_id));
     }
 
     fn initialise_genesis_parsecs(count: usize) -> Vec<TestPeer> {
@@ -731,7 +1938,7 @@ mod handle_malice {
                 TestParsec::from_genesis(
                     id.clone(),
                     &genesis_ids,
-                    ConsensusMode::Supermajority,
+                    ParsecConfig::default(),
                     new_rng(&mut common_rng),
                 )
             })
@@ -887,7 +2094,7 @@ mod handle_malice {
         let invalid_req_hash = invalid_req.compute_hash();
         let mut packed_events = take_packed_events(&bob, bob.graph().len());
         packed_events.push(invalid_req.clone());
-        let invalid_response_msg = Response { packed_events };
+        let invalid_response_msg = Response::new(packed_events);
 
         let expected_malice = Malice::InvalidRequest(Box::new(invalid_req));
 
@@ -935,7 +2142,7 @@ mod handle_malice {
 
         let mut packed_events = take_packed_events(&bob, 2);
         packed_events.push(invalid_req.clone());
-        let invalid_response_msg = Response { packed_events };
+        let invalid_response_msg = Response::new(packed_events);
 
         assert_handling_invalid_response(
             &mut bob,
@@ -948,7 +2155,7 @@ mod handle_malice {
         packed_events = take_packed_events(&bob, 5);
         packed_events.push(invalid_req);
         // Knowledge of Alice and Bob, and the invalid_req.
-        let invalid_response_msg = Response { packed_events };
+        let invalid_response_msg = Response::new(packed_events);
 
         assert_handling_invalid_response(
             &mut bob,
@@ -957,6 +2164,10 @@ mod handle_malice {
             &expected_malice,
             &invalid_req_hash,
         );
+
+        // Check that the accusation can be proven to anyone knowing Bob's ID.
+        let evidence = unwrap!(alice.malice_evidence(&expected_malice));
+        assert!(verify_evidence(&evidence, bob.our_pub_id()));
     }
 
     #[test]
@@ -987,7 +2198,7 @@ mod handle_malice {
 
         let mut packed_events = take_packed_events(&bob, bob.graph().len());
         packed_events.push(invalid_req);
-        let invalid_response_msg = Response { packed_events };
+        let invalid_response_msg = Response::new(packed_events);
         assert_handling_invalid_response(
             &mut bob,
             &mut carol,
@@ -1017,7 +2228,7 @@ mod handle_malice {
         // If the response be sent to Carol, a response event shall not be created.
         let packed_events = take_packed_events(&bob, bob.graph().len());
         assert_eq!(
-            carol.handle_response(bob.our_pub_id(), Response { packed_events }),
+            carol.handle_response(bob.our_pub_id(), Response::new(packed_events)),
             Err(Error::InvalidMessage)
         );
         assert!(!carol.graph().iter().any(|event| event.is_response()));
@@ -1035,7 +2246,7 @@ mod handle_malice {
         let mut packed_events = take_packed_events(&carol, 2);
         packed_events.push(bob_request);
         packed_events.push(invalid_resp.clone());
-        let invalid_response_msg = Response { packed_events };
+        let invalid_response_msg = Response::new(packed_events);
         assert_handling_invalid_response(
             &mut carol,
             &mut bob,
@@ -1047,7 +2258,7 @@ mod handle_malice {
         packed_events = take_packed_events(&bob, 8);
         packed_events.push(invalid_resp);
         // Knowledge of Alice, Bob and Carol, and the invalid_resp.
-        let invalid_response_msg = Response { packed_events };
+        let invalid_response_msg = Response::new(packed_events);
         assert_handling_invalid_response(
             &mut carol,
             &mut dave,
@@ -1079,9 +2290,7 @@ mod handle_malice {
         let invalid_resp_hash = invalid_resp.compute_hash();
         let expected_malice = Malice::InvalidResponse(Box::new(invalid_resp.clone()));
 
-        let invalid_response_msg = Response {
-            packed_events: vec![invalid_resp.clone()],
-        };
+        let invalid_response_msg = Response::new(vec![invalid_resp.clone()]);
         assert_handling_invalid_response(
             &mut alice,
             &mut bob,
@@ -1093,7 +2302,7 @@ mod handle_malice {
         let mut packed_events = take_packed_events(&bob, 6);
         packed_events.push(invalid_resp);
         // Knowledge of Alice and Bob, and the invalid_resp.
-        let invalid_response_msg = Response { packed_events };
+        let invalid_response_msg = Response::new(packed_events);
         assert_handling_invalid_response(
             &mut alice,
             &mut carol,
@@ -1126,9 +2335,7 @@ mod handle_malice {
         let invalid_resp_hash = invalid_resp.compute_hash();
         let expected_malice = Malice::InvalidResponse(Box::new(invalid_resp.clone()));
 
-        let invalid_response_msg = Response {
-            packed_events: vec![valid_resp, invalid_resp.clone()],
-        };
+        let invalid_response_msg = Response::new(vec![valid_resp, invalid_resp.clone()]);
         assert_handling_invalid_response(
             &mut alice,
             &mut bob,
@@ -1139,7 +2346,7 @@ mod handle_malice {
 
         let mut packed_events = take_packed_events(&alice, alice.graph().len());
         packed_events.push(invalid_resp);
-        let invalid_response_msg = Response { packed_events };
+        let invalid_response_msg = Response::new(packed_events);
         assert_handling_invalid_response(
             &mut alice,
             &mut carol,
@@ -1198,6 +2405,335 @@ mod handle_malice {
         unwrap!(alice.unpack_and_add_event(second_duplicate_packed));
         assert_eq!(*alice.pending_accusations(), expected_accusations);
         assert!(alice.graph().contains(&second_duplicate_hash));
+
+        // Check that the accusation can be proven to anyone knowing Carol's ID.
+        let evidence = unwrap!(alice.malice_evidence(&expected_accusations[0].1));
+        assert!(verify_evidence(&evidence, carol.our_pub_id()));
+        assert!(!verify_evidence(&evidence, alice.our_pub_id()));
+    }
+
+    #[test]
+    fn duplicate_votes_not_detected() {
+        let mut common_rng = new_common_rng(SEED);
+        let mut carol = TestParsec::from_parsed_contents(
+            unwrap!(parse_dot_file(
+                "input_graphs/functional_tests_handle_malice_duplicate_votes/carol.dot"
+            )),
+            new_rng(&mut common_rng),
+        );
+
+        let duplicated_payload = Observation::OpaquePayload(Transaction::new("ABCD"));
+        let duplicate = unwrap!(
+            carol.new_event_from_observation(carol.our_last_event_index(), duplicated_payload)
+        );
+        let duplicate_hash = *duplicate.hash();
+        let duplicate_packed = carol.pack_event(&duplicate);
+
+        // Alice detects other kinds of malice, but not duplicate votes, so the duplicate is added
+        // to her graph without an accusation.
+        let mut alice = TestParsec::from_parsed_contents(
+            unwrap!(parse_dot_file(
+                "input_graphs/functional_tests_handle_malice_duplicate_votes/alice.dot"
+            )),
+            new_rng(&mut common_rng),
+        );
+        alice.set_config(
+            ParsecConfig::default().with_detected_malice(MaliceKind::DuplicateVote, false),
+        );
+        unwrap!(alice.unpack_and_add_event(duplicate_packed));
+        assert!(alice.pending_accusations().is_empty());
+        assert!(alice.graph().contains(&duplicate_hash));
+    }
+
+    #[test]
+    fn spam_observations() {
+        let (mut alice, mut bob) = unwrap!(initialise_genesis_parsecs(4)
+            .into_iter()
+            .take(2)
+            .collect_tuple());
+        alice.set_config(ParsecConfig::default().with_spam_policy(SpamPolicy {
+            observation_allowance: 2,
+            section_ratio: 1,
+            ..SpamPolicy::default()
+        }));
+
+        // Bob votes for far more observations than anyone else Alice knows of.
+        for i in 0..3 {
+            unwrap!(
+                bob.vote_for(Observation::OpaquePayload(Transaction::new(format!(
+                    "{}",
+                    i
+                ))))
+            );
+        }
+
+        let message = unwrap!(bob.create_gossip(alice.our_pub_id()));
+        unwrap!(alice.handle_request(bob.our_pub_id(), message));
+
+        let expected_malice = Malice::Unprovable(UnprovableMalice::Spam);
+        assert_peer_has_accused(&alice, vec![(bob.our_pub_id(), &expected_malice)]);
+    }
+
+    #[test]
+    fn spam_oversized_payload() {
+        let (mut alice, mut bob) = unwrap!(initialise_genesis_parsecs(4)
+            .into_iter()
+            .take(2)
+            .collect_tuple());
+        alice.set_config(ParsecConfig::default().with_spam_policy(SpamPolicy {
+            max_payload_size: 100,
+            ..SpamPolicy::default()
+        }));
+
+        unwrap!(bob.vote_for(Observation::OpaquePayload(Transaction::new(
+            "x".repeat(100)
+        ))));
+
+        let message = unwrap!(bob.create_gossip(alice.our_pub_id()));
+        unwrap!(alice.handle_request(bob.our_pub_id(), message));
+
+        let expected_malice = Malice::Unprovable(UnprovableMalice::Spam);
+        assert_peer_has_accused(&alice, vec![(bob.our_pub_id(), &expected_malice)]);
+    }
+
+    #[test]
+    fn spam_gossip() {
+        let (mut alice, mut bob) = unwrap!(initialise_genesis_parsecs(4)
+            .into_iter()
+            .take(2)
+            .collect_tuple());
+        alice.set_config(ParsecConfig::default().with_spam_policy(SpamPolicy {
+            gossip_allowance: 2,
+            section_ratio: 1,
+            ..SpamPolicy::default()
+        }));
+
+        // Bob's first two requests are within his allowance, but the third one isn't.
+        for _ in 0..2 {
+            let message = unwrap!(bob.create_gossip(alice.our_pub_id()));
+            let response = unwrap!(alice.handle_request(bob.our_pub_id(), message));
+            unwrap!(bob.handle_response(alice.our_pub_id(), response));
+        }
+        assert_peer_has_accused(&alice, vec![]);
+
+        let message = unwrap!(bob.create_gossip(alice.our_pub_id()));
+        unwrap!(alice.handle_request(bob.our_pub_id(), message));
+
+        let expected_malice = Malice::Unprovable(UnprovableMalice::Spam);
+        assert_peer_has_accused(&alice, vec![(bob.our_pub_id(), &expected_malice)]);
+    }
+
+    #[test]
+    fn spam_gossip_window() {
+        let (mut alice, mut bob) = unwrap!(initialise_genesis_parsecs(4)
+            .into_iter()
+            .take(2)
+            .collect_tuple());
+        alice.set_config(ParsecConfig::default().with_spam_policy(SpamPolicy {
+            gossip_allowance: 2,
+            section_ratio: 1,
+            window: 2,
+            ..SpamPolicy::default()
+        }));
+
+        // Each of Bob's requests makes Alice create a sync event, so his count restarts before it
+        // gets past his allowance.
+        for _ in 0..5 {
+            let message = unwrap!(bob.create_gossip(alice.our_pub_id()));
+            let response = unwrap!(alice.handle_request(bob.our_pub_id(), message));
+            unwrap!(bob.handle_response(alice.our_pub_id(), response));
+        }
+        assert_peer_has_accused(&alice, vec![]);
+    }
+
+    #[test]
+    fn no_spam_in_two_peer_section() {
+        let (mut alice, mut bob) =
+            unwrap!(initialise_genesis_parsecs(2).into_iter().collect_tuple());
+        let policy = SpamPolicy {
+            observation_allowance: 1,
+            gossip_allowance: 1,
+            section_ratio: 1,
+            ..SpamPolicy::default()
+        };
+        alice.set_config(ParsecConfig::default().with_spam_policy(policy));
+        bob.set_config(ParsecConfig::default().with_spam_policy(policy));
+
+        // Both peers vote and gossip far past their allowances, but with no other voter to compare
+        // them with, neither looks like a spammer.
+        for i in 0..5 {
+            unwrap!(
+                alice.vote_for(Observation::OpaquePayload(Transaction::new(format!(
+                    "alice {}",
+                    i
+                ))))
+            );
+            unwrap!(
+                bob.vote_for(Observation::OpaquePayload(Transaction::new(format!(
+                    "bob {}",
+                    i
+                ))))
+            );
+
+            let message = unwrap!(alice.create_gossip(bob.our_pub_id()));
+            let response = unwrap!(bob.handle_request(alice.our_pub_id(), message));
+            unwrap!(alice.handle_response(bob.our_pub_id(), response));
+
+            let message = unwrap!(bob.create_gossip(alice.our_pub_id()));
+            let response = unwrap!(alice.handle_request(bob.our_pub_id(), message));
+            unwrap!(bob.handle_response(alice.our_pub_id(), response));
+        }
+
+        assert_peer_has_accused(&alice, vec![]);
+        assert_peer_has_accused(&bob, vec![]);
+    }
+
+    #[test]
+    fn invalid_payload() {
+        struct Validator;
+
+        impl PayloadValidator<Transaction> for Validator {
+            fn is_valid(&self, payload: &Transaction) -> bool {
+                *payload != Transaction::new("invalid")
+                    && *payload != Transaction::new("invalid too")
+            }
+        }
+
+        let (mut alice, mut bob, mut carol) = unwrap!(initialise_genesis_parsecs(4)
+            .into_iter()
+            .take(3)
+            .collect_tuple());
+        alice.set_config(
+            ParsecConfig::default().with_invalid_payload_threshold(NonZeroUsize::new(1)),
+        );
+        assert!(alice.set_payload_validator(Box::new(Validator)).is_none());
+
+        let invalid = Observation::OpaquePayload(Transaction::new("invalid"));
+        assert_eq!(alice.vote_for(invalid.clone()), Err(Error::InvalidPayload));
+        unwrap!(alice.vote_for(Observation::OpaquePayload(Transaction::new("valid"))));
+
+        // Bob doesn't check the payloads, and neither does Carol, who gets his vote.
+        unwrap!(bob.vote_for(invalid));
+        let invalid_hash = *unwrap!(bob.graph().get(bob.our_last_event_index())).hash();
+        let message = unwrap!(bob.create_gossip(carol.our_pub_id()));
+        let _ = unwrap!(carol.handle_request(bob.our_pub_id(), message));
+        let expected_malice = Malice::Unprovable(UnprovableMalice::InvalidPayload);
+
+        // Alice adds Bob's vote to her graph, but never lets it reach consensus, and holds it
+        // against Bob rather than Carol, who gossips it to her.
+        let message = unwrap!(carol.create_gossip(alice.our_pub_id()));
+        let _ = unwrap!(alice.handle_request(carol.our_pub_id(), message));
+        let invalid_index = unwrap!(alice.graph().get_index(&invalid_hash));
+        assert!(!alice
+            .meta_election()
+            .unconsensused_events(None)
+            .any(|index| index == invalid_index));
+        assert_peer_has_accused(&alice, vec![]);
+
+        // Receiving the same vote again from Bob doesn't count it again.
+        let message = unwrap!(bob.create_gossip(alice.our_pub_id()));
+        assert!(message
+            .packed_events
+            .iter()
+            .any(|packed_event| packed_event.compute_hash() == invalid_hash));
+        let _ = unwrap!(alice.handle_request(bob.our_pub_id(), message));
+        assert_peer_has_accused(&alice, vec![]);
+
+        // The count survives a restart, while the validator has to be registered again.
+        let mut alice = unwrap!(TestParsec::restore(
+            alice.our_pub_id().clone(),
+            &unwrap!(alice.save()),
+            new_rng(&mut new_common_rng(SEED)),
+        ));
+        assert!(alice.set_payload_validator(Box::new(Validator)).is_none());
+
+        // Alice accuses Bob once he exceeds the threshold.
+        unwrap!(bob.vote_for(Observation::OpaquePayload(Transaction::new("invalid too"))));
+        let message = unwrap!(bob.create_gossip(alice.our_pub_id()));
+        let _ = unwrap!(alice.handle_request(bob.our_pub_id(), message));
+        assert_peer_has_accused(&alice, vec![(bob.our_pub_id(), &expected_malice)]);
+    }
+
+    #[test]
+    fn unresponsive_peer() {
+        let (mut alice, mut bob, mut carol) = unwrap!(initialise_genesis_parsecs(4)
+            .into_iter()
+            .take(3)
+            .collect_tuple());
+        alice.set_config(ParsecConfig::default().with_unresponsive_threshold(NonZeroUsize::new(2)));
+
+        // Bob never answers Alice's request.
+        let _ = unwrap!(alice.create_gossip(bob.our_pub_id()));
+
+        // A full sync with Carol takes two of Alice's sync events, which is within the threshold.
+        let mut sync_with_carol = |alice: &mut TestParsec<_, _>| {
+            let message = unwrap!(alice.create_gossip(carol.our_pub_id()));
+            let response = unwrap!(carol.handle_request(alice.our_pub_id(), message));
+            unwrap!(alice.handle_response(carol.our_pub_id(), response));
+        };
+        sync_with_carol(&mut alice);
+        assert_eq!(alice.unresponsive_peers().count(), 0);
+        assert_peer_has_accused(&alice, vec![]);
+
+        sync_with_carol(&mut alice);
+        assert_eq!(
+            alice.unresponsive_peers().collect_vec(),
+            vec![bob.our_pub_id()]
+        );
+        let expected_malice = Malice::Unprovable(UnprovableMalice::Unresponsive);
+        assert_peer_has_accused(&alice, vec![(bob.our_pub_id(), &expected_malice)]);
+
+        // Once Bob answers, he's no longer unresponsive.
+        let message = unwrap!(alice.create_gossip(bob.our_pub_id()));
+        let response = unwrap!(bob.handle_request(alice.our_pub_id(), message));
+        unwrap!(alice.handle_response(bob.our_pub_id(), response));
+        assert_eq!(alice.unresponsive_peers().count(), 0);
+    }
+
+    #[test]
+    fn unresponsive_peer_counts() {
+        let (mut alice, mut bob, mut carol) = unwrap!(initialise_genesis_parsecs(4)
+            .into_iter()
+            .take(3)
+            .collect_tuple());
+        alice.set_config(ParsecConfig::default().with_unresponsive_threshold(NonZeroUsize::new(4)));
+
+        let sync = |src: &mut TestParsec<_, _>, dst: &mut TestParsec<_, _>| {
+            let message = unwrap!(src.create_gossip(dst.our_pub_id()));
+            let response = unwrap!(dst.handle_request(src.our_pub_id(), message));
+            unwrap!(src.handle_response(dst.our_pub_id(), response));
+        };
+
+        // Agree on the gossip protocol version with Bob, so Alice can split her requests to him.
+        sync(&mut alice, &mut bob);
+        alice.set_gossip_event_limit(NonZeroUsize::new(1));
+
+        // Bob only acknowledges the first part of Alice's request, which still shows he's
+        // responsive.
+        let message = unwrap!(alice.create_gossip(bob.our_pub_id()));
+        assert!(message.has_more());
+        let response = unwrap!(bob.handle_request(alice.our_pub_id(), message));
+        assert!(response.has_more());
+        unwrap!(alice.handle_response(bob.our_pub_id(), response));
+        alice.set_gossip_event_limit(None);
+        for _ in 0..3 {
+            sync(&mut alice, &mut carol);
+        }
+        assert_eq!(alice.unresponsive_peers().count(), 0);
+
+        // Bob doesn't answer the next part, but his own requests make Alice's count for him decay,
+        // so he's unresponsive only once he's ignored her for long enough.
+        let _ = unwrap!(alice.create_gossip(bob.our_pub_id()));
+        sync(&mut alice, &mut carol);
+        sync(&mut bob, &mut alice);
+        sync(&mut alice, &mut carol);
+        assert_eq!(alice.unresponsive_peers().count(), 0);
+
+        sync(&mut alice, &mut carol);
+        assert_eq!(
+            alice.unresponsive_peers().collect_vec(),
+            vec![bob.our_pub_id()]
+        );
     }
 
     // This will be used to hold four peers initialised to support malice and accomplice testing:
@@ -1295,6 +2831,23 @@ mod handle_malice {
         env.assert_dave_accused_alice_only();
     }
 
+    #[test]
+    // Alice has falsely accused Carol of creating a fork, but Dave doesn't detect forks, so can't
+    // tell the accusation is invalid.
+    fn invalid_accusation_of_undetected_malice() {
+        let mut env = AccompliceEnvironment::new();
+        let alice_id = env.alice_id().clone();
+        let dave_id = env.dave_id().clone();
+        env.dave
+            .set_config(ParsecConfig::default().with_detected_malice(MaliceKind::Fork, false));
+
+        let message = unwrap!(env.alice.create_gossip(&dave_id));
+        unwrap!(env.dave.handle_request(&alice_id, message));
+
+        assert!(env.dave.graph().contains(&env.invalid_accusation_hash));
+        assert_peer_has_accused(&env.dave, vec![]);
+    }
+
     #[test]
     // Alice has falsely accused Carol of creating a fork.  Bob knows this, but as an accomplice,
     // hasn't accused Alice of `InvalidAccusation`.  Dave will detect this when Bob gossips to him.
@@ -1418,9 +2971,7 @@ mod handle_malice {
         let a_1 = unwrap!(nth_event(alice.graph(), 1).pack(alice.event_context()));
         let a_2_0 =
             PackedEvent::new_requesting(alice_id.clone(), bob_id.clone(), a_1.compute_hash());
-        let mut request = Request {
-            packed_events: vec![a_0.clone(), a_1.clone(), a_2_0.clone()],
-        };
+        let mut request = Request::new(vec![a_0.clone(), a_1.clone(), a_2_0.clone()]);
         unwrap!(bob.handle_request(&alice_id, request.clone()));
 
         // [A_0, A_1, A_2,1, A_3,0] will be sent to Carol.
@@ -1515,9 +3066,8 @@ mod handle_malice {
             PackedEvent::new_requesting(alice_id.clone(), bob_id.clone(), a_2_0.compute_hash());
         let a_3_1 =
             PackedEvent::new_requesting(alice_id.clone(), bob_id.clone(), a_2_1.compute_hash());
-        let mut request = Request {
-            packed_events: vec![a_0.clone(), a_1.clone(), a_2_0.clone(), a_3_0.clone()],
-        };
+        let mut request =
+            Request::new(vec![a_0.clone(), a_1.clone(), a_2_0.clone(), a_3_0.clone()]);
         unwrap!(bob.handle_request(&alice_id, request.clone()));
 
         // [A_0, A_1, A_2,1, A_3,1] will be sent to Bob second.  Bob should accuse A_1.
@@ -1580,9 +3130,7 @@ mod handle_malice {
         let a_1 = unwrap!(nth_event(alice.graph(), 1).pack(alice.event_context()));
         let a_2_0 =
             PackedEvent::new_requesting(alice_id.clone(), bob_id.clone(), a_1.compute_hash());
-        let mut request = Request {
-            packed_events: vec![a_0.clone(), a_1.clone(), a_2_0.clone()],
-        };
+        let mut request = Request::new(vec![a_0.clone(), a_1.clone(), a_2_0.clone()]);
         unwrap!(bob.handle_request(&alice_id, request.clone()));
 
         // [A_0, A_1, A_2,1] will be sent to Carol.
@@ -1652,13 +3200,11 @@ mod handle_malice {
         }
 
         // Send Bob's message to Alice.  B_2 should be rejected as invalid.
-        let message = Request {
-            packed_events: vec![
-                unwrap!(b_0.pack(bob.event_context())),
-                unwrap!(b_1.pack(bob.event_context())),
-                b_2_packed.clone(),
-            ],
-        };
+        let message = Request::new(vec![
+            unwrap!(b_0.pack(bob.event_context())),
+            unwrap!(b_1.pack(bob.event_context())),
+            b_2_packed.clone(),
+        ]);
         assert_eq!(
             alice.handle_request(bob.our_pub_id(), message),
             Err(Error::InvalidEvent)
@@ -1699,12 +3245,17 @@ mod handle_malice {
         invalid_parent_creator_test(InvalidCreatorFor::OtherParent);
     }
 
-    #[test]
-    fn premature_gossip() {
+    // Makes Alice gossip to Fred before he can handle it, and returns the result of Fred handling
+    // the request along with both instances.
+    fn handle_premature_gossip(
+        mut parsed_contents: ParsedContents,
+        config: ParsecConfig,
+    ) -> (
+        Result<Response<Transaction, PeerId>, Error>,
+        TestPeer,
+        TestPeer,
+    ) {
         let mut common_rng = new_common_rng(SEED);
-        // Generated with RNG seed: [411278735, 3293288956, 208850454, 2872654992].
-        // Copied from add_peer
-        let mut parsed_contents = parse_test_dot_file("alice.dot");
 
         // The final decision to add Frank is reached in E_25, so we remove this event.
         let _e_25 = unwrap!(parsed_contents.remove_last_event());
@@ -1737,7 +3288,7 @@ mod handle_malice {
             fred_id.clone(),
             &genesis_group,
             &genesis_group,
-            ConsensusMode::Supermajority,
+            config,
             new_rng(&mut common_rng),
         );
 
@@ -1750,6 +3301,16 @@ mod handle_malice {
         // Now Alice will prematurely gossip to Fred
         let request = unwrap!(alice.create_gossip(&fred_id));
         let result = fred.handle_request(&alice_id, request);
+        (result, alice, fred)
+    }
+
+    #[test]
+    fn premature_gossip() {
+        // Generated with RNG seed: [411278735, 3293288956, 208850454, 2872654992].
+        // Copied from add_peer
+        let parsed_contents = parse_test_dot_file("alice.dot");
+        let (result, alice, fred) =
+            handle_premature_gossip(parsed_contents, ParsecConfig::default());
 
         // check that Fred detected premature gossip
         assert_eq!(result, Err(Error::PrematureGossip));
@@ -1761,6 +3322,23 @@ mod handle_malice {
             .all(|ev| fred.graph().contains(ev.inner().hash())));
     }
 
+    #[test]
+    fn premature_gossip_with_malice_detection_disabled() {
+        let parsed_contents = unwrap!(parse_dot_file(
+            "input_graphs/functional_tests_handle_malice_premature_gossip/alice.dot"
+        ));
+        let config = ParsecConfig::default().with_malice_detection(false);
+        let (result, alice, fred) = handle_premature_gossip(parsed_contents, config);
+
+        // Fred doesn't check for malice, so he handles the request normally.
+        assert!(result.is_ok());
+        assert!(alice
+            .graph()
+            .iter()
+            .all(|ev| fred.graph().contains(ev.inner().hash())));
+        assert!(fred.pending_accusations().is_empty());
+    }
+
     #[test]
     fn missing_self_parent() {
         let (mut alice, mut bob) =
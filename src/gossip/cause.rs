@@ -229,8 +229,8 @@ pub(super) fn self_parent_hash<P: PublicId>(
     index: EventIndex,
 ) -> Result<EventHash, Error> {
     graph
-        .get(index)
-        .map(|event| *event.hash())
+        .get_hash(index)
+        .cloned()
         .ok_or(Error::UnknownSelfParent)
 }
 
@@ -239,8 +239,8 @@ pub(super) fn other_parent_hash<P: PublicId>(
     index: EventIndex,
 ) -> Result<EventHash, Error> {
     graph
-        .get(index)
-        .map(|event| *event.hash())
+        .get_hash(index)
+        .cloned()
         .ok_or(Error::UnknownOtherParent)
 }
 
@@ -0,0 +1,431 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+// Compact encoding of the events of a gossip message. The encoding consists of:
+//
+// * the protocol version of the message,
+// * the capabilities of the sender, if any: a single byte one followed by the minimum and maximum
+//   supported versions, or a single byte zero,
+// * the `has_more` flag of the message, as a single byte, from `SPLIT_SYNC_VERSION` on,
+// * the table of the IDs of the peers the events refer to: its length, followed by the IDs,
+// * the number of events, followed by the events.
+//
+// An event is encoded as the position of its creator in the peer table, the tag of its cause, the
+// fields of its cause and its signature. A parent is encoded as its distance back from the event
+// if it is in the same message, or zero followed by its full hash otherwise. Versions, lengths,
+// positions and distances are LEB128 varints, while IDs, votes, hashes and signatures are
+// serialised.
+//
+// The hashes of the parents in the same message are recomputed when decoding, so the decoded
+// events are identical to the encoded ones and their signatures can still be verified.
+
+use super::{
+    cause::Cause,
+    content::Content,
+    event_hash::EventHash,
+    messages::{Capabilities, Envelope},
+    packed_event::PackedEvent,
+};
+use crate::{
+    error::{Error, Result},
+    id::PublicId,
+    network_event::NetworkEvent,
+    serialise,
+    vote::Vote,
+};
+use maidsafe_utilities::serialisation;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    io::{Cursor, Read},
+};
+
+const INITIAL: u8 = 0;
+const REQUESTING: u8 = 1;
+const REQUEST: u8 = 2;
+const RESPONSE: u8 = 3;
+const OBSERVATION: u8 = 4;
+
+// Maximum number of bytes of a LEB128 encoded `u64`.
+const MAX_VARINT_LEN: usize = 10;
+
+pub(super) fn encode<T: NetworkEvent, P: PublicId>(
+    envelope: &Envelope,
+    packed_events: &[PackedEvent<T, P>],
+) -> Vec<u8> {
+    let mut peers = BTreeMap::new();
+    let mut peer_table = vec![];
+    for packed_event in packed_events {
+        let content = &packed_event.content;
+        let recipient = match content.cause {
+            Cause::Requesting { ref recipient, .. } => Some(recipient),
+            _ => None,
+        };
+        for peer_id in Some(&content.creator).into_iter().chain(recipient) {
+            let _ = peers.entry(peer_id).or_insert_with(|| {
+                peer_table.push(peer_id);
+                peer_table.len() - 1
+            });
+        }
+    }
+
+    let mut bytes = vec![];
+    write_varint(u64::from(envelope.version), &mut bytes);
+    if let Some(capabilities) = envelope.capabilities {
+        bytes.push(1);
+        write_varint(u64::from(capabilities.min_version), &mut bytes);
+        write_varint(u64::from(capabilities.max_version), &mut bytes);
+    } else {
+        bytes.push(0);
+    }
+    if envelope.has_more_flag() {
+        bytes.push(envelope.has_more as u8);
+    }
+    write_varint(peer_table.len() as u64, &mut bytes);
+    for peer_id in peer_table {
+        write_serialised(peer_id, &mut bytes);
+    }
+
+    let mut positions = BTreeMap::new();
+    write_varint(packed_events.len() as u64, &mut bytes);
+    for (position, packed_event) in packed_events.iter().enumerate() {
+        let content = &packed_event.content;
+        let write_parent = |parent: &EventHash, bytes: &mut Vec<u8>| {
+            if let Some(parent_position) = positions.get(parent) {
+                write_varint((position - parent_position) as u64, bytes);
+            } else {
+                write_varint(0, bytes);
+                write_serialised(parent, bytes);
+            }
+        };
+
+        write_varint(peers[&content.creator] as u64, &mut bytes);
+        match content.cause {
+            Cause::Initial => bytes.push(INITIAL),
+            Cause::Requesting {
+                ref self_parent,
+                ref recipient,
+            } => {
+                bytes.push(REQUESTING);
+                write_parent(self_parent, &mut bytes);
+                write_varint(peers[recipient] as u64, &mut bytes);
+            }
+            Cause::Request {
+                ref self_parent,
+                ref other_parent,
+            } => {
+                bytes.push(REQUEST);
+                write_parent(self_parent, &mut bytes);
+                write_parent(other_parent, &mut bytes);
+            }
+            Cause::Response {
+                ref self_parent,
+                ref other_parent,
+            } => {
+                bytes.push(RESPONSE);
+                write_parent(self_parent, &mut bytes);
+                write_parent(other_parent, &mut bytes);
+            }
+            Cause::Observation {
+                ref self_parent,
+                ref vote,
+            } => {
+                bytes.push(OBSERVATION);
+                write_parent(self_parent, &mut bytes);
+                write_serialised(vote, &mut bytes);
+            }
+        }
+        write_serialised(&packed_event.signature, &mut bytes);
+
+        let _ = positions.insert(packed_event.compute_hash(), position);
+    }
+
+    bytes
+}
+
+pub(super) fn decode<T: NetworkEvent, P: PublicId>(
+    bytes: &[u8],
+) -> Result<(Envelope, Vec<PackedEvent<T, P>>)> {
+    let mut cursor = Cursor::new(bytes);
+
+    let version = read_version(&mut cursor)?;
+    let capabilities = if read_flag(&mut cursor)? {
+        Some(Capabilities {
+            min_version: read_version(&mut cursor)?,
+            max_version: read_version(&mut cursor)?,
+        })
+    } else {
+        None
+    };
+    let mut envelope = Envelope {
+        version,
+        capabilities,
+        has_more: false,
+    };
+    if envelope.has_more_flag() {
+        envelope.has_more = read_flag(&mut cursor)?;
+    }
+
+    let peer_count = read_varint(&mut cursor)?;
+    let mut peer_table: Vec<P> = vec![];
+    for _ in 0..peer_count {
+        peer_table.push(read_serialised(&mut cursor)?);
+    }
+    let read_peer = |cursor: &mut Cursor<&[u8]>| -> Result<P> {
+        let index = read_varint(cursor)?;
+        peer_table
+            .get(index as usize)
+            .cloned()
+            .ok_or(Error::InvalidMessage)
+    };
+
+    let event_count = read_varint(&mut cursor)?;
+    let mut hashes = vec![];
+    let mut packed_events = vec![];
+    for _ in 0..event_count {
+        let read_parent = |cursor: &mut Cursor<&[u8]>| -> Result<EventHash> {
+            match read_varint(cursor)? as usize {
+                0 => read_serialised(cursor),
+                distance if distance <= hashes.len() => Ok(hashes[hashes.len() - distance]),
+                _ => Err(Error::InvalidMessage),
+            }
+        };
+
+        let creator = read_peer(&mut cursor)?;
+        let cause = match read_byte(&mut cursor)? {
+            INITIAL => Cause::Initial,
+            REQUESTING => Cause::Requesting {
+                self_parent: read_parent(&mut cursor)?,
+                recipient: read_peer(&mut cursor)?,
+            },
+            REQUEST => Cause::Request {
+                self_parent: read_parent(&mut cursor)?,
+                other_parent: read_parent(&mut cursor)?,
+            },
+            RESPONSE => Cause::Response {
+                self_parent: read_parent(&mut cursor)?,
+                other_parent: read_parent(&mut cursor)?,
+            },
+            OBSERVATION => Cause::Observation {
+                self_parent: read_parent(&mut cursor)?,
+                vote: read_serialised::<Vote<T, P>>(&mut cursor)?,
+            },
+            _ => return Err(Error::InvalidMessage),
+        };
+        let packed_event = PackedEvent {
+            content: Content { creator, cause },
+            signature: read_serialised(&mut cursor)?,
+        };
+
+        hashes.push(packed_event.compute_hash());
+        packed_events.push(packed_event);
+    }
+
+    if cursor.position() as usize != bytes.len() {
+        return Err(Error::InvalidMessage);
+    }
+
+    Ok((envelope, packed_events))
+}
+
+fn write_varint(mut value: u64, bytes: &mut Vec<u8>) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn write_serialised<D: Serialize + Debug>(data: &D, bytes: &mut Vec<u8>) {
+    bytes.extend(serialise(data));
+}
+
+fn read_byte(cursor: &mut Cursor<&[u8]>) -> Result<u8> {
+    let mut byte = [0];
+    cursor
+        .read_exact(&mut byte)
+        .map_err(|_| Error::InvalidMessage)?;
+    Ok(byte[0])
+}
+
+fn read_flag(cursor: &mut Cursor<&[u8]>) -> Result<bool> {
+    match read_byte(cursor)? {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(Error::InvalidMessage),
+    }
+}
+
+fn read_version(cursor: &mut Cursor<&[u8]>) -> Result<u32> {
+    let version = read_varint(cursor)?;
+    if version > u64::from(u32::MAX) {
+        return Err(Error::InvalidMessage);
+    }
+    Ok(version as u32)
+}
+
+fn read_varint(cursor: &mut Cursor<&[u8]>) -> Result<u64> {
+    let mut value = 0;
+    for shift in (0..MAX_VARINT_LEN).map(|index| 7 * index) {
+        let byte = read_byte(cursor)?;
+        let bits = u64::from(byte & 0x7f);
+        if bits << shift >> shift != bits {
+            return Err(Error::InvalidMessage);
+        }
+        value |= bits << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(Error::InvalidMessage)
+}
+
+fn read_serialised<D: DeserializeOwned>(cursor: &mut Cursor<&[u8]>) -> Result<D> {
+    serialisation::deserialise_from(cursor).map_err(|_| Error::InvalidMessage)
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::{
+        gossip::messages::{PROTOCOL_VERSION, SPLIT_SYNC_VERSION},
+        mock::{PeerId, Transaction},
+        observation::Observation,
+    };
+
+    // Returns events of Alice and Bob, some with parents in the returned list and some without.
+    fn packed_events() -> Vec<PackedEvent<Transaction, PeerId>> {
+        let alice = PeerId::new("Alice");
+        let bob = PeerId::new("Bob");
+
+        let a_0 = PackedEvent::new_initial(alice.clone());
+        let a_1 = PackedEvent::new_observation(
+            alice.clone(),
+            a_0.compute_hash(),
+            Observation::OpaquePayload(Transaction::new("one")),
+        );
+        let b_0 = PackedEvent::new_initial(bob.clone());
+        let b_1 = PackedEvent::new_requesting(bob.clone(), alice.clone(), b_0.compute_hash());
+        let a_2 = PackedEvent::new_request(alice.clone(), a_1.compute_hash(), b_1.compute_hash());
+        let b_2 = PackedEvent::new_response(bob.clone(), b_1.compute_hash(), a_2.compute_hash());
+        let a_3 = PackedEvent::new_requesting(alice, bob.clone(), a_2.compute_hash());
+
+        vec![a_1, b_1, a_2, b_2, a_3]
+    }
+
+    fn envelope() -> Envelope {
+        Envelope {
+            version: PROTOCOL_VERSION,
+            capabilities: None,
+            has_more: false,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let packed_events = packed_events();
+        let envelopes = vec![
+            envelope(),
+            Envelope {
+                has_more: true,
+                ..envelope()
+            },
+            Envelope {
+                capabilities: Some(Capabilities::ours()),
+                ..envelope()
+            },
+            Envelope {
+                version: SPLIT_SYNC_VERSION - 1,
+                ..envelope()
+            },
+        ];
+        for envelope in envelopes {
+            let bytes = encode(&envelope, &packed_events);
+            assert_eq!(
+                unwrap!(decode::<Transaction, PeerId>(&bytes)),
+                (envelope, packed_events.clone())
+            );
+        }
+
+        let bytes = encode::<Transaction, PeerId>(&envelope(), &[]);
+        assert_eq!(
+            unwrap!(decode::<Transaction, PeerId>(&bytes)),
+            (envelope(), vec![])
+        );
+    }
+
+    #[test]
+    fn has_more_flag_depends_on_version() {
+        let split = encode(&envelope(), &packed_events());
+        let unsplit_envelope = Envelope {
+            version: SPLIT_SYNC_VERSION - 1,
+            ..envelope()
+        };
+        let unsplit = encode(&unsplit_envelope, &packed_events());
+        assert_eq!(unsplit.len() + 1, split.len());
+    }
+
+    #[test]
+    fn smaller_than_serialised() {
+        let packed_events = packed_events();
+        assert!(encode(&envelope(), &packed_events).len() < serialise(&packed_events).len());
+    }
+
+    #[test]
+    fn invalid_bytes() {
+        let bytes = encode(&envelope(), &packed_events());
+
+        // Truncated.
+        assert_eq!(
+            decode::<Transaction, PeerId>(&bytes[..bytes.len() - 1]),
+            Err(Error::InvalidMessage)
+        );
+
+        // Trailing bytes.
+        let mut longer = bytes.clone();
+        longer.push(0);
+        assert_eq!(
+            decode::<Transaction, PeerId>(&longer),
+            Err(Error::InvalidMessage)
+        );
+
+        // Parent beyond the start of the message.
+        let mut bad_parent = vec![PROTOCOL_VERSION as u8, 0, 0, 1];
+        bad_parent.extend(serialise(&PeerId::new("Alice")));
+        bad_parent.extend(&[1, 0, REQUESTING, 1, 0]);
+        assert_eq!(
+            decode::<Transaction, PeerId>(&bad_parent),
+            Err(Error::InvalidMessage)
+        );
+    }
+
+    #[test]
+    fn varint() {
+        for &value in &[
+            0,
+            1,
+            0x7f,
+            0x80,
+            0x3fff,
+            0x4000,
+            u64::from(u32::MAX),
+            u64::MAX,
+        ] {
+            let mut bytes = vec![];
+            write_varint(value, &mut bytes);
+            assert_eq!(unwrap!(read_varint(&mut Cursor::new(&bytes[..]))), value);
+        }
+
+        // Too long, and overflowing.
+        let bytes = [0xff; MAX_VARINT_LEN + 1];
+        assert!(read_varint(&mut Cursor::new(&bytes[..])).is_err());
+        let bytes = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f];
+        assert!(read_varint(&mut Cursor::new(&bytes[..])).is_err());
+    }
+}
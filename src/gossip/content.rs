@@ -81,15 +81,18 @@ impl<P: PublicId> Content<VoteKey<P>, EventIndex, PeerIndex> {
         Ok((Self { creator, cause }, observation_for_store))
     }
 
+    // `index_by_creator` is the one of the event this is the content of, which determines the key
+    // it's signed with.
     pub(crate) fn pack<T: NetworkEvent, S: SecretId<PublicId = P>>(
         &self,
+        index_by_creator: usize,
         ctx: EventContextRef<T, S>,
     ) -> Result<Content<Vote<T, P>, EventHash, P>, Error> {
         Ok(Content {
             creator: ctx
                 .peer_list
                 .get(self.creator)
-                .map(|peer| peer.id().clone())
+                .map(|peer| peer.id_at(index_by_creator).clone())
                 .ok_or(Error::UnknownPeer)?,
             cause: self.cause.pack(ctx)?,
         })
@@ -35,10 +35,15 @@ use crate::{
 use itertools::Itertools;
 use std::fmt::{self, Debug, Display, Formatter};
 
+#[serde(bound = "")]
+#[derive(Clone, Serialize, Deserialize)]
 pub(crate) struct Event<P: PublicId> {
     content: Content<VoteKey<P>, EventIndex, PeerIndex>,
     // Creator's signature of `content`.
     signature: P::Signature,
+    // ID the recipient of a `Requesting` event is named by in the signed content. It may have
+    // rotated its key since.
+    recipient_id: Option<P>,
     cache: Cache,
 }
 
@@ -65,13 +70,9 @@ impl<P: PublicId> Event<P> {
             },
         };
 
-        Ok(Self::new(
-            hash,
-            signature,
-            content,
-            ctx.graph,
-            ctx.peer_list,
-        ))
+        let mut event = Self::new(hash, signature, content, ctx.graph, ctx.peer_list);
+        event.recipient_id = Some(recipient.clone());
+        Ok(event)
     }
 
     // Creates a new event as the result of receiving a gossip request message.
@@ -193,6 +194,7 @@ impl<P: PublicId> Event<P> {
         Self {
             content,
             signature,
+            recipient_id: None,
             cache,
         }
     }
@@ -217,6 +219,10 @@ impl<P: PublicId> Event<P> {
             return Ok(None);
         }
 
+        let recipient_id = match packed_event.content.cause {
+            Cause::Requesting { ref recipient, .. } => Some(recipient.clone()),
+            _ => None,
+        };
         let graph = ctx.graph;
         let peer_list = ctx.peer_list;
         let (content, observation_for_store) = Content::unpack(packed_event.content, ctx)?;
@@ -226,6 +232,7 @@ impl<P: PublicId> Event<P> {
             event: Self {
                 content,
                 signature: packed_event.signature,
+                recipient_id,
                 cache,
             },
             observation_for_store,
@@ -237,8 +244,19 @@ impl<P: PublicId> Event<P> {
         &self,
         ctx: EventContextRef<T, S>,
     ) -> Result<PackedEvent<T, P>, Error> {
+        let mut content = self.content.pack(self.index_by_creator(), ctx)?;
+        if let (
+            Cause::Requesting {
+                ref mut recipient, ..
+            },
+            Some(recipient_id),
+        ) = (&mut content.cause, &self.recipient_id)
+        {
+            *recipient = recipient_id.clone();
+        }
+
         Ok(PackedEvent {
-            content: self.content.pack(ctx)?,
+            content,
             signature: self.signature.clone(),
         })
     }
@@ -387,7 +405,6 @@ impl<P: PublicId> Event<P> {
         }
     }
 
-    #[cfg(feature = "malice-detection")]
     pub fn requesting_recipient(&self) -> Option<PeerIndex> {
         if let Cause::Requesting { recipient, .. } = self.content.cause {
             Some(recipient)
@@ -506,10 +523,13 @@ impl Event<PeerId> {
         peer_list: &mut PeerList<PeerId>,
         observations: &mut ObservationStore<Transaction, PeerId>,
     ) -> Self {
-        let recipient = match cause {
-            CauseInput::Requesting(ref recipient) => peer_list.get_index(recipient),
+        let recipient_id = match cause {
+            CauseInput::Requesting(ref recipient) => Some(recipient.clone()),
             _ => None,
         };
+        let recipient = recipient_id
+            .as_ref()
+            .and_then(|recipient| peer_list.get_index(recipient));
         let cause = Cause::new_from_dot_input(
             cause,
             creator,
@@ -561,6 +581,7 @@ impl Event<PeerId> {
         Self {
             content,
             signature,
+            recipient_id,
             cache,
         }
     }
@@ -584,6 +605,7 @@ pub(crate) enum CauseInput {
 }
 
 // Properties of `Event` that can be computed from its `Content`.
+#[derive(Clone, Serialize, Deserialize)]
 struct Cache {
     // Hash of `Event`s `Content`.
     hash: EventHash,
@@ -606,7 +628,7 @@ impl Cache {
         let self_parent = get_event(graph, content.self_parent());
         let other_parent = get_event(graph, content.other_parent());
 
-        let index_by_creator = compute_index_by_creator(self_parent);
+        let index_by_creator = compute_index_by_creator(content, self_parent, peer_list);
         let ancestor_info = compute_ancestor_info(
             content.creator,
             index_by_creator,
@@ -634,9 +656,18 @@ fn get_event<'a, P: PublicId>(
         .map(|event| event.inner())
 }
 
-fn compute_index_by_creator<P: PublicId>(self_parent: Option<&Event<P>>) -> usize {
+fn compute_index_by_creator<S: SecretId>(
+    content: &Content<VoteKey<S::PublicId>, EventIndex, PeerIndex>,
+    self_parent: Option<&Event<S::PublicId>>,
+    peer_list: &PeerList<S>,
+) -> usize {
     if let Some(self_parent) = self_parent {
         self_parent.index_by_creator() + 1
+    } else if content.self_parent().is_some() {
+        // The self-parent precedes the section's meta-election we were caught up with.
+        peer_list
+            .get(content.creator)
+            .map_or(0, |peer| peer.pruned_count())
     } else {
         // Initial event
         0
@@ -729,7 +760,7 @@ mod tests {
     ) -> (EventIndex, EventHash) {
         let hash = *initial_event.hash();
         assert!(!graph.contains(&hash));
-        (graph.insert(initial_event).event_index(), hash)
+        (unwrap!(graph.insert(initial_event)).event_index(), hash)
     }
 
     fn create_two_events(
@@ -834,8 +865,8 @@ mod tests {
     fn event_construction_from_request() {
         let (mut alice, a_0, bob, b_0) = create_two_events("Alice", "Bob");
         let b_0 = convert_event(&b_0, bob.as_ref(), alice.as_ref());
-        let a_0_index = alice.graph.insert(a_0).event_index();
-        let b_0_index = alice.graph.insert(b_0).event_index();
+        let a_0_index = unwrap!(alice.graph.insert(a_0)).event_index();
+        let b_0_index = unwrap!(alice.graph.insert(b_0)).event_index();
 
         // Alice receives request from Bob
         let event_from_request = unwrap!(Event::new_from_request(
@@ -862,7 +893,7 @@ mod tests {
     fn event_construction_from_request_without_self_parent_event_in_graph() {
         let (mut alice, _, bob, b_0) = create_two_events("Alice", "Bob");
         let b_0 = convert_event(&b_0, bob.as_ref(), alice.as_ref());
-        let b_0_index = alice.graph.insert(b_0).event_index();
+        let b_0_index = unwrap!(alice.graph.insert(b_0)).event_index();
 
         match Event::new_from_request(EventIndex::PHONY, b_0_index, alice.as_ref()) {
             Err(Error::UnknownSelfParent) => (),
@@ -874,7 +905,7 @@ mod tests {
     #[cfg(feature = "testing")]
     fn event_construction_from_request_without_other_parent_event_in_graph() {
         let (mut alice, a_0, _, _) = create_two_events("Alice", "Bob");
-        let a_0_index = alice.graph.insert(a_0).event_index();
+        let a_0_index = unwrap!(alice.graph.insert(a_0)).event_index();
 
         match Event::new_from_request(a_0_index, EventIndex::PHONY, alice.as_ref()) {
             Err(Error::UnknownOtherParent) => (),
@@ -886,8 +917,8 @@ mod tests {
     fn event_construction_from_response() {
         let (mut alice, a_0, bob, b_0) = create_two_events("Alice", "Bob");
         let b_0 = convert_event(&b_0, bob.as_ref(), alice.as_ref());
-        let a_0_index = alice.graph.insert(a_0).event_index();
-        let b_0_index = alice.graph.insert(b_0).event_index();
+        let a_0_index = unwrap!(alice.graph.insert(a_0)).event_index();
+        let b_0_index = unwrap!(alice.graph.insert(b_0)).event_index();
 
         let event_from_response = unwrap!(Event::new_from_response(
             a_0_index,
@@ -910,7 +941,7 @@ mod tests {
     #[test]
     fn event_construction_unpack() {
         let (mut alice, a_0) = create_event_with_single_peer("Alice");
-        let a_0_index = alice.graph.insert(a_0).event_index();
+        let a_0_index = unwrap!(alice.graph.insert(a_0)).event_index();
 
         // Our observation
         let net_event = Observation::OpaquePayload(Transaction::new("event_observed_by_alice"));
@@ -931,7 +962,7 @@ mod tests {
         assert_eq!(event_from_observation, unpacked_event);
         assert!(!alice.graph.contains(unpacked_event.hash()));
 
-        let _ = alice.graph.insert(unpacked_event);
+        let _ = unwrap!(alice.graph.insert(unpacked_event));
 
         assert!(unwrap!(Event::unpack(packed_event, alice.as_ref())).is_none());
     }
@@ -939,7 +970,7 @@ mod tests {
     #[test]
     fn event_construction_unpack_fail_with_wrong_signature() {
         let (mut alice, a_0) = create_event_with_single_peer("Alice");
-        let a_0_index = alice.graph.insert(a_0).event_index();
+        let a_0_index = unwrap!(alice.graph.insert(a_0)).event_index();
 
         // Our observation
         let net_event = Observation::OpaquePayload(Transaction::new("event_observed_by_alice"));
@@ -962,4 +993,65 @@ mod tests {
             panic!("Expected SignatureFailure, but got {:?}", error);
         }
     }
+
+    #[test]
+    fn event_pack_requesting_after_recipient_key_rotation() {
+        let (mut alice, a_0, _, _) = create_two_events("Alice", "Bob");
+        let a_0_index = unwrap!(alice.graph.insert(a_0)).event_index();
+        let bob_id = PeerId::new("Bob");
+        let requesting = unwrap!(Event::new_from_requesting(
+            a_0_index,
+            &bob_id,
+            alice.as_ref()
+        ));
+
+        // The packed event still names Bob by the key it was signed with.
+        let bob_index = unwrap!(alice.peer_list.get_index(&bob_id));
+        alice
+            .peer_list
+            .rotate_peer_key(bob_index, PeerId::new("NewBob"), a_0_index);
+        let packed_event = unwrap!(requesting.pack(alice.as_ref()));
+        assert_eq!(packed_event.compute_hash(), *requesting.hash());
+    }
+
+    #[test]
+    fn event_unpack_with_rotated_key() {
+        let (mut alice, a_0, mut bob, b_0) = create_two_events("Alice", "Bob");
+        let alice_id = alice.peer_list.our_pub_id().clone();
+        let a_0_for_bob = convert_event(&a_0, alice.as_ref(), bob.as_ref());
+        let b_0_for_alice = convert_event(&b_0, bob.as_ref(), alice.as_ref());
+        let a_0_index = unwrap!(alice.graph.insert(a_0)).event_index();
+        let b_0_index_for_alice = unwrap!(alice.graph.insert(b_0_for_alice)).event_index();
+        let _ = unwrap!(bob.graph.insert(a_0_for_bob));
+        let b_0_index = unwrap!(bob.graph.insert(b_0)).event_index();
+
+        // Bob reaches consensus on rotating Alice's key at his initial event.
+        let alice_index = unwrap!(bob.peer_list.get_index(&alice_id));
+        bob.peer_list
+            .rotate_peer_key(alice_index, PeerId::new("NewAlice"), b_0_index);
+
+        // The old key is still valid for Alice's events that don't know of the consensus...
+        let (observation, observation_for_store) = unwrap!(Event::new_from_observation(
+            a_0_index,
+            Observation::OpaquePayload(Transaction::new("before_rotation")),
+            alice.as_ref(),
+        ));
+        let (key, observation_info) = unwrap!(observation_for_store);
+        let _ = alice.observations.insert(key, observation_info);
+        let observation = convert_event(&observation, alice.as_ref(), bob.as_ref());
+        assert!(bob
+            .peer_list
+            .confirm_creator_id(&observation, &alice_id, &bob.graph));
+
+        // ...but not for the ones descending from it.
+        let request = unwrap!(Event::new_from_request(
+            a_0_index,
+            b_0_index_for_alice,
+            alice.as_ref()
+        ));
+        let request = convert_event(&request, alice.as_ref(), bob.as_ref());
+        assert!(!bob
+            .peer_list
+            .confirm_creator_id(&request, &alice_id, &bob.graph));
+    }
 }
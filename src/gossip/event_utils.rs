@@ -34,7 +34,7 @@ use std::{
 pub(super) type ForkMap = BTreeMap<usize, IndexSet>;
 
 // Immutable set of integer indices
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub(crate) struct IndexSet(FnvHashSet<usize>);
 
 impl IndexSet {
@@ -73,7 +73,7 @@ impl Debug for IndexSet {
 }
 
 // Information about ancestor events.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub(crate) struct AncestorInfo {
     // index-by-creator of the last event by the current peer that is ancestor of the current
     // event.
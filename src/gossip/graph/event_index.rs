@@ -8,7 +8,7 @@
 
 use std::usize;
 
-#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
 pub(crate) struct EventIndex(pub(super) usize);
 
 impl EventIndex {
@@ -16,6 +16,11 @@ impl EventIndex {
     #[cfg(any(test, feature = "testing"))]
     pub const PHONY: Self = EventIndex(usize::MAX);
 
+    #[cfg(test)]
+    pub fn from_topological_index(index: usize) -> Self {
+        EventIndex(index)
+    }
+
     pub fn topological_index(self) -> usize {
         self.0
     }
@@ -9,36 +9,47 @@
 mod ancestors;
 mod event_index;
 mod event_ref;
+mod store;
 
+pub use self::store::GraphStorage;
 pub(crate) use self::{ancestors::Ancestors, event_index::EventIndex, event_ref::IndexedEventRef};
 
+use self::store::{GraphStore, MemoryStore, Store};
 use super::{event::Event, event_hash::EventHash};
-use crate::id::PublicId;
-#[cfg(feature = "malice-detection")]
+use crate::{error::Error, id::PublicId};
 use fnv::FnvHashSet;
-use std::collections::{
-    btree_map::{BTreeMap, Entry},
-    BTreeSet,
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    cmp,
+    collections::{btree_map::BTreeMap, BTreeSet},
+    fmt::{self, Debug, Formatter},
+    io,
 };
 
 /// The gossip graph.
-#[derive(Eq, PartialEq, Debug)]
 pub(crate) struct Graph<P: PublicId> {
-    events: Vec<Event<P>>,
+    events: Store<P>,
+    // Hashes of the events removed by `prune`, in topological order. The event at position `i` in
+    // `events` has topological index `pruned_hashes.len() + i`.
+    pruned_hashes: Vec<EventHash>,
+    // Indices of all the events ever inserted, including the pruned ones.
     indices: BTreeMap<EventHash, EventIndex>,
     /// Indices of `Requesting` events with no associated descendant `Request`, and `Request`s with
     /// no associated descendant `Response`.
-    #[cfg(feature = "malice-detection")]
     awaiting_associated_events: FnvHashSet<EventIndex>,
+    // Whether the pruned events can still be the parents of the inserted ones, while the graph is
+    // being caught up with a section's.
+    catching_up: bool,
 }
 
 impl<P: PublicId> Default for Graph<P> {
     fn default() -> Self {
         Self {
-            events: Vec::new(),
+            events: Store::default(),
+            pruned_hashes: Vec::new(),
             indices: BTreeMap::new(),
-            #[cfg(feature = "malice-detection")]
             awaiting_associated_events: FnvHashSet::default(),
+            catching_up: false,
         }
     }
 }
@@ -48,16 +59,52 @@ impl<P: PublicId> Graph<P> {
         Self::default()
     }
 
-    /// Get index of an event with the given hash.
+    /// Moves all the events to a new store created for `storage`, and keeps them there from now
+    /// on.
+    pub fn set_storage(&mut self, storage: &GraphStorage) -> io::Result<()> {
+        let mut events = storage.create_store()?;
+        for position in 0..self.events.len() {
+            if let Some(event) = self.events.get(position) {
+                events.push(event.clone())?;
+            }
+        }
+        self.events = events;
+        Ok(())
+    }
+
+    /// Frees the memory the store holds for events beyond what it's configured to keep.
+    pub fn trim_cache(&mut self) {
+        self.events.trim_cache();
+    }
+
+    /// Get index of an event with the given hash. Returns `None` if the event has been pruned,
+    /// unless the graph is being caught up.
     pub fn get_index(&self, hash: &EventHash) -> Option<EventIndex> {
-        self.indices.get(hash).cloned()
+        self.indices
+            .get(hash)
+            .cloned()
+            .filter(|index| self.catching_up || !self.is_pruned(*index))
     }
 
-    /// Checks whether this graph contains an event with the given hash.
+    /// Checks whether this graph contains, or used to contain before being pruned, an event with
+    /// the given hash.
     pub fn contains(&self, hash: &EventHash) -> bool {
         self.indices.contains_key(hash)
     }
 
+    /// Returns the hash of the event with the given `index`. Unlike `get`, this works for pruned
+    /// events too.
+    pub fn get_hash(&self, index: EventIndex) -> Option<&EventHash> {
+        self.pruned_hashes
+            .get(index.0)
+            .or_else(|| self.get(index).map(|event| event.inner().hash()))
+    }
+
+    /// Returns whether the event with the given `index` has been removed by `prune`.
+    pub fn is_pruned(&self, index: EventIndex) -> bool {
+        index.0 < self.pruned_hashes.len()
+    }
+
     /// Insert new event into the graph.
     ///
     /// Returns `IndexedEventRef` to the newly inserted event.
@@ -69,35 +116,36 @@ impl<P: PublicId> Graph<P> {
     ///
     /// If the event is a `Request` or `Response`, the other_parent is removed from
     /// `awaiting_associated_events`.
-    pub fn insert(&mut self, event: Event<P>) -> IndexedEventRef<P> {
-        let index = match self.indices.entry(*event.hash()) {
-            Entry::Occupied(entry) => *entry.get(),
-            Entry::Vacant(entry) => {
-                let index = EventIndex(self.events.len());
+    ///
+    /// Returns `Error::PrunedEvent` if the event was pruned since it was inserted, and
+    /// `Error::StorageFailure` if the event couldn't be stored.
+    pub fn insert(&mut self, event: Event<P>) -> Result<IndexedEventRef<P>, Error> {
+        if let Some(index) = self.indices.get(event.hash()) {
+            return self.get(*index).ok_or(Error::PrunedEvent);
+        }
 
-                #[cfg(any(test, feature = "testing"))]
-                assert_ne!(index, EventIndex::PHONY);
+        let index = EventIndex(self.len());
 
-                self.events.push(event);
-                let _ = entry.insert(index);
+        #[cfg(any(test, feature = "testing"))]
+        assert_ne!(index, EventIndex::PHONY);
 
-                #[cfg(feature = "malice-detection")]
-                self.update_awaiting(index);
+        let (awaiting, awaited) = self.awaiting_and_awaited_indices(index, &event);
+        let hash = *event.hash();
+        self.events.push(event).map_err(|_| Error::StorageFailure)?;
+        let _ = self.indices.insert(hash, index);
 
-                index
-            }
-        };
+        let _ = awaiting.map(|awaiting| self.awaiting_associated_events.insert(awaiting));
+        let _ = awaited.map(|awaited| self.awaiting_associated_events.remove(&awaited));
 
-        IndexedEventRef {
-            index,
-            event: &self.events[index.0],
-        }
+        self.get(index).ok_or(Error::StorageFailure)
     }
 
-    /// Gets `Event` with the given `index`, if it exists.
+    /// Gets `Event` with the given `index`, if it exists and hasn't been pruned.
     pub fn get(&self, index: EventIndex) -> Option<IndexedEventRef<P>> {
-        self.events
-            .get(index.0)
+        index
+            .0
+            .checked_sub(self.pruned_hashes.len())
+            .and_then(|position| self.events.get(position))
             .map(|event| IndexedEventRef { index, event })
     }
 
@@ -106,9 +154,10 @@ impl<P: PublicId> Graph<P> {
         self.get_index(hash).and_then(|index| self.get(index))
     }
 
-    /// Number of events in this graph.
+    /// Number of events ever inserted into this graph, including the pruned ones. This is also
+    /// the topological index the next inserted event will get.
     pub fn len(&self) -> usize {
-        self.events.len()
+        self.pruned_hashes.len() + self.events.len()
     }
 
     /// Iterator over all events in this graph. Yields `IndexedEventRef`s.
@@ -116,17 +165,65 @@ impl<P: PublicId> Graph<P> {
         self.iter_from(0)
     }
 
-    /// Iterator over events in this graph starting at the given topological index.
+    /// Iterator over events in this graph starting at the given topological index. Pruned events
+    /// are skipped.
     pub fn iter_from(&self, start_index: usize) -> Iter<P> {
         Iter {
             events: &self.events,
-            index: start_index,
+            offset: self.pruned_hashes.len(),
+            index: start_index.saturating_sub(self.pruned_hashes.len()),
         }
     }
 
-    /// Iterator over event indices starting at the given topological index.
+    /// Iterator over event indices starting at the given topological index. Pruned events are
+    /// skipped.
     pub fn indices_from(&self, start_index: usize) -> impl Iterator<Item = EventIndex> {
-        (start_index..self.events.len()).map(EventIndex)
+        (cmp::max(start_index, self.pruned_hashes.len())..self.len()).map(EventIndex)
+    }
+
+    /// Removes all the events whose topological index is less than `end_index` and returns them.
+    ///
+    /// The indices of the remaining events don't change. The hashes of the removed events are
+    /// retained, so that copies of them received again are recognised as already known, and so
+    /// that the remaining events whose parents were removed can still be packed.
+    pub fn prune(&mut self, end_index: usize) -> Vec<Event<P>> {
+        let count = cmp::min(
+            end_index.saturating_sub(self.pruned_hashes.len()),
+            self.events.len(),
+        );
+        let pruned = self.events.remove_front(count);
+        self.pruned_hashes
+            .extend(pruned.iter().map(|event| *event.hash()));
+
+        let first_index = self.pruned_hashes.len();
+        self.awaiting_associated_events
+            .retain(|index| index.0 >= first_index);
+
+        pruned
+    }
+
+    /// Starts the empty graph of a peer catching up with a section from the events with the given
+    /// hashes, as if they had been pruned. Until `finish_catch_up` is called, the events inserted
+    /// can have them as parents, so that the graph can start from the section's current
+    /// meta-election rather than from its genesis.
+    pub fn start_catch_up(&mut self, hashes: Vec<EventHash>) {
+        if self.len() > 0 {
+            log_or_panic!("Only an empty graph can be caught up");
+            return;
+        }
+
+        for hash in hashes {
+            let _ = self
+                .indices
+                .insert(hash, EventIndex(self.pruned_hashes.len()));
+            self.pruned_hashes.push(hash);
+        }
+        self.catching_up = true;
+    }
+
+    /// Stops accepting events with pruned parents.
+    pub fn finish_catch_up(&mut self) {
+        self.catching_up = false;
     }
 
     /// Returns self-parent of the given event, if any.
@@ -183,7 +280,6 @@ impl<P: PublicId> Graph<P> {
     }
 }
 
-#[cfg(feature = "malice-detection")]
 impl<P: PublicId> Graph<P> {
     /// Returns true if the event specified by `index` should eventually but still doesn't have an
     /// associated `Request` or `Response` added to the graph.
@@ -227,8 +323,8 @@ impl<P: PublicId> Graph<P> {
     fn awaiting_and_awaited_indices(
         &self,
         index: EventIndex,
+        event: &Event<P>,
     ) -> (Option<EventIndex>, Option<EventIndex>) {
-        let event = &self.events[index.0];
         if event.is_requesting() {
             (Some(index), None)
         } else if event.is_request() {
@@ -248,26 +344,17 @@ impl<P: PublicId> Graph<P> {
             (None, None)
         }
     }
-
-    fn update_awaiting(&mut self, index: EventIndex) {
-        let (awaiting, awaited) = self.awaiting_and_awaited_indices(index);
-        let _ = awaiting.map(|awaiting| self.awaiting_associated_events.insert(awaiting));
-        let _ = awaited.map(|awaited| self.awaiting_associated_events.remove(&awaited));
-    }
 }
 
 #[cfg(any(all(test, feature = "mock"), feature = "testing"))]
 impl<P: PublicId> Graph<P> {
     /// Remove the topologically last event.
     pub fn remove_last(&mut self) -> Option<(EventIndex, Event<P>)> {
-        let index = EventIndex(self.events.len() - 1);
-        #[cfg(feature = "malice-detection")]
-        {
-            let (awaiting, awaited) = self.awaiting_and_awaited_indices(index);
-            let _ = awaiting.map(|awaiting| self.awaiting_associated_events.remove(&awaiting));
-            let _ = awaited.map(|awaited| self.awaiting_associated_events.insert(awaited));
-        }
+        let index = EventIndex(self.len() - 1);
         let event = self.events.pop()?;
+        let (awaiting, awaited) = self.awaiting_and_awaited_indices(index, &event);
+        let _ = awaiting.map(|awaiting| self.awaiting_associated_events.remove(&awaiting));
+        let _ = awaited.map(|awaited| self.awaiting_associated_events.insert(awaited));
         let _ = self.indices.remove(event.hash());
         Some((index, event))
     }
@@ -297,15 +384,87 @@ impl<P: PublicId> Graph<P> {
     }
 }
 
+// Only the events not pruned are serialised in full, the rest is the same as the in-memory layout.
+#[serde(bound = "")]
+#[derive(Serialize)]
+struct GraphRef<'a, P: PublicId> {
+    events: Vec<&'a Event<P>>,
+    pruned_hashes: &'a Vec<EventHash>,
+    indices: &'a BTreeMap<EventHash, EventIndex>,
+    awaiting_associated_events: &'a FnvHashSet<EventIndex>,
+}
+
+#[serde(bound = "")]
+#[derive(Deserialize)]
+struct SavedGraph<P: PublicId> {
+    events: Vec<Event<P>>,
+    pruned_hashes: Vec<EventHash>,
+    indices: BTreeMap<EventHash, EventIndex>,
+    awaiting_associated_events: FnvHashSet<EventIndex>,
+}
+
+impl<P: PublicId> Serialize for Graph<P> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GraphRef {
+            events: self.iter().map(|event| event.inner()).collect(),
+            pruned_hashes: &self.pruned_hashes,
+            indices: &self.indices,
+            awaiting_associated_events: &self.awaiting_associated_events,
+        }
+        .serialize(serializer)
+    }
+}
+
+// The deserialised graph always keeps its events in memory.
+impl<'de, P: PublicId> Deserialize<'de> for Graph<P> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let saved = SavedGraph::deserialize(deserializer)?;
+        Ok(Self {
+            events: Store::Memory(MemoryStore::from(saved.events)),
+            pruned_hashes: saved.pruned_hashes,
+            indices: saved.indices,
+            awaiting_associated_events: saved.awaiting_associated_events,
+            catching_up: false,
+        })
+    }
+}
+
+impl<P: PublicId> PartialEq for Graph<P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.awaiting_associated_events == other.awaiting_associated_events
+            && self.pruned_hashes == other.pruned_hashes
+            && self.indices == other.indices
+            && self
+                .iter()
+                .map(|event| event.inner())
+                .eq(other.iter().map(|event| event.inner()))
+    }
+}
+
+impl<P: PublicId> Eq for Graph<P> {}
+
+impl<P: PublicId> Debug for Graph<P> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_list()
+            .entries(self.iter().map(|event| event.inner()))
+            .finish()
+    }
+}
+
 impl<P: PublicId> IntoIterator for Graph<P> {
     type IntoIter = IntoIter<P>;
     type Item = <Self::IntoIter as Iterator>::Item;
 
     fn into_iter(self) -> Self::IntoIter {
         let mut events = self.events;
+        let count = events.len();
+        let mut events = events.remove_front(count);
         events.reverse();
 
-        IntoIter { events, index: 0 }
+        IntoIter {
+            events,
+            index: self.pruned_hashes.len(),
+        }
     }
 }
 
@@ -333,15 +492,15 @@ impl<'a, P: PublicId> IntoIterator for &'a Graph<P> {
     type Item = <Self::IntoIter as Iterator>::Item;
 
     fn into_iter(self) -> Self::IntoIter {
-        Iter {
-            events: &self.events,
-            index: 0,
-        }
+        self.iter()
     }
 }
 
 pub(crate) struct Iter<'a, P: PublicId + 'a> {
-    events: &'a [Event<P>],
+    events: &'a Store<P>,
+    // Topological index of the first event in `events`.
+    offset: usize,
+    // Position of the next event in `events`.
     index: usize,
 }
 
@@ -351,7 +510,7 @@ impl<'a, P: PublicId> Iterator for Iter<'a, P> {
     fn next(&mut self) -> Option<Self::Item> {
         let event = self.events.get(self.index)?;
         let item = IndexedEventRef {
-            index: EventIndex(self.index),
+            index: EventIndex(self.offset + self.index),
             event,
         };
         self.index += 1;
@@ -375,11 +534,12 @@ pub(crate) mod snapshot {
 
         /// Generate a snapshot without the last `ignore_last_events` events
         pub fn new_with_ignore<P: PublicId>(graph: &Graph<P>, ignore_last_events: usize) -> Self {
+            let count = graph.iter().count() - ignore_last_events;
             GraphSnapshot(
                 graph
                     .iter()
                     .map(|event| *event.hash())
-                    .take(graph.len() - ignore_last_events)
+                    .take(count)
                     .collect(),
             )
         }
@@ -388,7 +548,10 @@ pub(crate) mod snapshot {
 
 #[cfg(test)]
 mod tests {
-    use crate::dev_utils::parse_test_dot_file;
+    use crate::{
+        dev_utils::{parse_dot_file, parse_test_dot_file},
+        error::Error,
+    };
 
     #[test]
     fn ancestors_iterator() {
@@ -424,4 +587,19 @@ mod tests {
 
         assert_eq!(actual_indices, sorted_indices);
     }
+
+    #[test]
+    fn insert_pruned_event() {
+        let mut graph = unwrap!(parse_dot_file(
+            "input_graphs/gossip_graph_tests_ancestors_iterator/carol.dot"
+        ))
+        .graph;
+        let len = graph.len();
+
+        let mut pruned = graph.prune(2);
+        let event = unwrap!(pruned.pop());
+        assert!(graph.contains(event.hash()));
+        assert_eq!(graph.insert(event).err(), Some(Error::PrunedEvent));
+        assert_eq!(graph.len(), len);
+    }
 }
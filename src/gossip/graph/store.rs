@@ -0,0 +1,373 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::super::event::Event;
+use crate::id::PublicId;
+use elsa::FrozenMap;
+use fnv::FnvHashMap;
+use maidsafe_utilities::serialisation;
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, VecDeque},
+    fs::{File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+/// Where the events of the gossip graph are kept.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum GraphStorage {
+    /// All the events are kept in memory. This is the default.
+    Memory,
+    /// The events are appended to the file at `path`, which is created or truncated. Only up to
+    /// `cache_size` of the most recently used events are kept in memory.
+    ///
+    /// The file is never compacted, so events removed by `Parsec::prune` still take disk space
+    /// until `Parsec::set_graph_storage` moves the remaining events to another file.
+    File {
+        /// Path of the file the events are written to.
+        path: PathBuf,
+        /// Maximum number of events cached in memory.
+        cache_size: usize,
+    },
+}
+
+impl GraphStorage {
+    pub(crate) fn create_store<P: PublicId>(&self) -> io::Result<Store<P>> {
+        match *self {
+            GraphStorage::Memory => Ok(Store::Memory(MemoryStore::default())),
+            GraphStorage::File {
+                ref path,
+                cache_size,
+            } => Ok(Store::File(FileStore::new(path, cache_size)?)),
+        }
+    }
+}
+
+/// Storage backend of the gossip graph. Holds the events in topological order, addressed by
+/// their position, which is their topological index minus the number of events pruned from the
+/// front of the graph.
+pub(crate) trait GraphStore<P: PublicId> {
+    /// Number of stored events.
+    fn len(&self) -> usize;
+
+    /// Returns the event at the given position, if any.
+    fn get(&self, position: usize) -> Option<&Event<P>>;
+
+    /// Appends the event to the store. The event isn't stored if this fails.
+    fn push(&mut self, event: Event<P>) -> io::Result<()>;
+
+    /// Removes the last event and returns it.
+    #[cfg(any(all(test, feature = "mock"), feature = "testing"))]
+    fn pop(&mut self) -> Option<Event<P>>;
+
+    /// Removes the first `count` events and returns them.
+    fn remove_front(&mut self, count: usize) -> Vec<Event<P>>;
+
+    /// Frees the memory held for events beyond what the store is configured to keep.
+    fn trim_cache(&mut self) {}
+}
+
+/// The store of the gossip graph, for whichever kind of storage it uses.
+pub(crate) enum Store<P: PublicId> {
+    Memory(MemoryStore<P>),
+    File(FileStore<P>),
+}
+
+impl<P: PublicId> Default for Store<P> {
+    fn default() -> Self {
+        Store::Memory(MemoryStore::default())
+    }
+}
+
+impl<P: PublicId> GraphStore<P> for Store<P> {
+    fn len(&self) -> usize {
+        match self {
+            Store::Memory(store) => store.len(),
+            Store::File(store) => store.len(),
+        }
+    }
+
+    fn get(&self, position: usize) -> Option<&Event<P>> {
+        match self {
+            Store::Memory(store) => store.get(position),
+            Store::File(store) => store.get(position),
+        }
+    }
+
+    fn push(&mut self, event: Event<P>) -> io::Result<()> {
+        match self {
+            Store::Memory(store) => store.push(event),
+            Store::File(store) => store.push(event),
+        }
+    }
+
+    #[cfg(any(all(test, feature = "mock"), feature = "testing"))]
+    fn pop(&mut self) -> Option<Event<P>> {
+        match self {
+            Store::Memory(store) => store.pop(),
+            Store::File(store) => store.pop(),
+        }
+    }
+
+    fn remove_front(&mut self, count: usize) -> Vec<Event<P>> {
+        match self {
+            Store::Memory(store) => store.remove_front(count),
+            Store::File(store) => store.remove_front(count),
+        }
+    }
+
+    fn trim_cache(&mut self) {
+        match self {
+            Store::Memory(store) => store.trim_cache(),
+            Store::File(store) => store.trim_cache(),
+        }
+    }
+}
+
+/// Store keeping all the events in memory.
+pub(crate) struct MemoryStore<P: PublicId>(Vec<Event<P>>);
+
+impl<P: PublicId> Default for MemoryStore<P> {
+    fn default() -> Self {
+        MemoryStore(Vec::new())
+    }
+}
+
+impl<P: PublicId> From<Vec<Event<P>>> for MemoryStore<P> {
+    fn from(events: Vec<Event<P>>) -> Self {
+        MemoryStore(events)
+    }
+}
+
+impl<P: PublicId> GraphStore<P> for MemoryStore<P> {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn get(&self, position: usize) -> Option<&Event<P>> {
+        self.0.get(position)
+    }
+
+    fn push(&mut self, event: Event<P>) -> io::Result<()> {
+        self.0.push(event);
+        Ok(())
+    }
+
+    #[cfg(any(all(test, feature = "mock"), feature = "testing"))]
+    fn pop(&mut self) -> Option<Event<P>> {
+        self.0.pop()
+    }
+
+    fn remove_front(&mut self, count: usize) -> Vec<Event<P>> {
+        self.0.drain(..count).collect()
+    }
+}
+
+/// Store appending the events to a file, with an LRU cache of the most recently used ones in
+/// front of it.
+///
+/// Events loaded by `get` are added to the cache even if it's full, as the references handed out
+/// must stay valid while the store is borrowed, so can only be freed through `&mut self`. The
+/// cache is trimmed back to `cache_size` by every mutating call, and by `trim_cache`, which
+/// `Parsec` calls once it's done handling each call, so it only grows past `cache_size` by the
+/// events read within a single call.
+pub(crate) struct FileStore<P: PublicId> {
+    file: File,
+    // Offset in `file` and length of each stored event.
+    records: VecDeque<(u64, usize)>,
+    // Offset the next event will be written at.
+    end: u64,
+    // Number of events removed from the front. Events are cached under their position plus this,
+    // so that the keys remain valid after `remove_front`.
+    removed: usize,
+    cache: FrozenMap<usize, Box<Event<P>>>,
+    cache_size: usize,
+    recency: RefCell<Recency>,
+}
+
+impl<P: PublicId> FileStore<P> {
+    pub fn new(path: &Path, cache_size: usize) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        Ok(Self {
+            file,
+            records: VecDeque::new(),
+            end: 0,
+            removed: 0,
+            cache: FrozenMap::new(),
+            cache_size,
+            recency: RefCell::new(Recency::default()),
+        })
+    }
+
+    fn read(&self, position: usize) -> io::Result<Event<P>> {
+        let (offset, length) = self.records[position];
+        let mut file = &self.file;
+        let _ = file.seek(SeekFrom::Start(offset))?;
+        let mut bytes = vec![0; length];
+        file.read_exact(&mut bytes)?;
+        serialisation::deserialise(&bytes)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))
+    }
+
+    // Removes the event at `position` from the cache, or reads it from the file if not cached.
+    fn take(&mut self, position: usize) -> Option<Event<P>> {
+        let key = self.removed + position;
+        self.recency.borrow_mut().remove(key);
+        if let Some(event) = self.cache.as_mut().remove(&key) {
+            return Some(*event);
+        }
+
+        self.read(position)
+            .map_err(|error| log_or_panic!("Failed to read event from graph file: {:?}", error))
+            .ok()
+    }
+}
+
+impl<P: PublicId> GraphStore<P> for FileStore<P> {
+    fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    fn get(&self, position: usize) -> Option<&Event<P>> {
+        if position >= self.records.len() {
+            return None;
+        }
+
+        let key = self.removed + position;
+        self.recency.borrow_mut().touch(key);
+        if let Some(event) = self.cache.get(&key) {
+            return Some(event);
+        }
+
+        match self.read(position) {
+            Ok(event) => Some(self.cache.insert(key, Box::new(event))),
+            Err(error) => {
+                log_or_panic!("Failed to read event from graph file: {:?}", error);
+                None
+            }
+        }
+    }
+
+    fn push(&mut self, event: Event<P>) -> io::Result<()> {
+        let bytes = serialisation::serialise(&event)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
+        let offset = self.end;
+        let _ = self.file.seek(SeekFrom::Start(offset))?;
+        self.file.write_all(&bytes)?;
+
+        let key = self.removed + self.records.len();
+        self.records.push_back((offset, bytes.len()));
+        self.end += bytes.len() as u64;
+        self.recency.borrow_mut().touch(key);
+        let _ = self.cache.insert(key, Box::new(event));
+        self.trim_cache();
+        Ok(())
+    }
+
+    #[cfg(any(all(test, feature = "mock"), feature = "testing"))]
+    fn pop(&mut self) -> Option<Event<P>> {
+        let position = self.records.len().checked_sub(1)?;
+        let event = self.take(position);
+        if let Some((offset, _)) = self.records.pop_back() {
+            self.end = offset;
+            if let Err(error) = self.file.set_len(offset) {
+                log_or_panic!("Failed to truncate graph file: {:?}", error);
+            }
+        }
+        self.trim_cache();
+        event
+    }
+
+    fn remove_front(&mut self, count: usize) -> Vec<Event<P>> {
+        let events = (0..count)
+            .filter_map(|position| self.take(position))
+            .collect();
+        let _ = self.records.drain(..count);
+        self.removed += count;
+        self.trim_cache();
+        events
+    }
+
+    fn trim_cache(&mut self) {
+        while self.cache.len() > self.cache_size {
+            if let Some(key) = self.recency.borrow_mut().pop_oldest() {
+                let _ = self.cache.as_mut().remove(&key);
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+// Order in which the cached events were last used.
+#[derive(Default)]
+struct Recency {
+    tick: u64,
+    ticks_by_key: FnvHashMap<usize, u64>,
+    keys_by_tick: BTreeMap<u64, usize>,
+}
+
+impl Recency {
+    fn touch(&mut self, key: usize) {
+        self.tick += 1;
+        if let Some(old_tick) = self.ticks_by_key.insert(key, self.tick) {
+            let _ = self.keys_by_tick.remove(&old_tick);
+        }
+        let _ = self.keys_by_tick.insert(self.tick, key);
+    }
+
+    fn remove(&mut self, key: usize) {
+        if let Some(tick) = self.ticks_by_key.remove(&key) {
+            let _ = self.keys_by_tick.remove(&tick);
+        }
+    }
+
+    fn pop_oldest(&mut self) -> Option<usize> {
+        let tick = *self.keys_by_tick.keys().next()?;
+        let key = self.keys_by_tick.remove(&tick)?;
+        let _ = self.ticks_by_key.remove(&key);
+        Some(key)
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::{dev_utils::parse_dot_file, mock::PeerId};
+    use std::{env, fs};
+
+    #[test]
+    fn file_store_cache_is_trimmed() {
+        let path = env::temp_dir().join(format!("parsec_file_store_{}", std::process::id()));
+        let mut store = unwrap!(FileStore::<PeerId>::new(&path, 4));
+        let contents = unwrap!(parse_dot_file(
+            "input_graphs/functional_tests_unpolled_observations/alice.dot"
+        ));
+        for (_, event) in contents.graph {
+            unwrap!(store.push(event));
+        }
+        assert!(store.cache.len() <= 4);
+
+        // Reading every event caches them all until the store is trimmed.
+        for position in 0..store.len() {
+            assert!(store.get(position).is_some());
+        }
+        assert!(store.cache.len() > 4);
+        store.trim_cache();
+        assert!(store.cache.len() <= 4);
+        assert!(store.get(0).is_some());
+
+        unwrap!(fs::remove_file(path));
+    }
+}
@@ -6,30 +6,307 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{gossip::packed_event::PackedEvent, id::PublicId, network_event::NetworkEvent};
+use super::{compact, packed_event::PackedEvent};
+use crate::{error::Result, id::PublicId, network_event::NetworkEvent};
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    ser::SerializeTuple,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::{cmp, fmt, marker::PhantomData, result};
+
+/// The version of the gossip protocol implemented by this crate. It is increased whenever the
+/// format of `Request` or `Response`, or of the events they carry, changes.
+pub const PROTOCOL_VERSION: u32 = 2;
+/// The oldest version of the gossip protocol this crate can still talk.
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+// The first version in which a sync can be split into several messages. Messages of earlier
+// versions don't carry the `has_more` flag, and always hold the whole sync.
+pub(crate) const SPLIT_SYNC_VERSION: u32 = 2;
+
+// The range of gossip protocol versions supported by a peer. It is carried by the first messages
+// exchanged by two peers, for them to agree on the version to use.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub(crate) struct Capabilities {
+    pub min_version: u32,
+    pub max_version: u32,
+}
+
+impl Capabilities {
+    pub fn ours() -> Self {
+        Self {
+            min_version: MIN_PROTOCOL_VERSION,
+            max_version: PROTOCOL_VERSION,
+        }
+    }
+
+    // Returns the newest version supported by both `self` and `other`, if any.
+    pub fn negotiate(&self, other: &Self) -> Option<u32> {
+        let version = cmp::min(self.max_version, other.max_version);
+        if version >= cmp::max(self.min_version, other.min_version) {
+            Some(version)
+        } else {
+            None
+        }
+    }
+
+    pub fn supports(&self, version: u32) -> bool {
+        self.min_version <= version && version <= self.max_version
+    }
+}
+
+// The fields of a message other than its events.
+#[derive(PartialEq, Eq, Debug)]
+pub(super) struct Envelope {
+    pub version: u32,
+    pub capabilities: Option<Capabilities>,
+    pub has_more: bool,
+}
+
+impl Envelope {
+    // Returns whether the `has_more` flag is part of the format of the message.
+    pub fn has_more_flag(&self) -> bool {
+        self.version >= SPLIT_SYNC_VERSION
+    }
+}
+
+// The envelope and the events of a message.
+type MessageParts<T, P> = (Envelope, Vec<PackedEvent<T, P>>);
+
+// Serialises a message as a tuple of its fields, leaving out the ones its version doesn't have.
+fn serialize_message<T, P, S>(
+    envelope: &Envelope,
+    packed_events: &[PackedEvent<T, P>],
+    serializer: S,
+) -> result::Result<S::Ok, S::Error>
+where
+    T: NetworkEvent,
+    P: PublicId,
+    S: Serializer,
+{
+    let len = if envelope.has_more_flag() { 4 } else { 3 };
+    let mut tuple = serializer.serialize_tuple(len)?;
+    tuple.serialize_element(&envelope.version)?;
+    tuple.serialize_element(&envelope.capabilities)?;
+    tuple.serialize_element(packed_events)?;
+    if envelope.has_more_flag() {
+        tuple.serialize_element(&envelope.has_more)?;
+    }
+    tuple.end()
+}
+
+fn deserialize_message<'a, T, P, D>(deserializer: D) -> result::Result<MessageParts<T, P>, D::Error>
+where
+    T: NetworkEvent,
+    P: PublicId,
+    D: Deserializer<'a>,
+{
+    deserializer.deserialize_tuple(4, MessageVisitor(PhantomData))
+}
+
+struct MessageVisitor<T, P>(PhantomData<(T, P)>);
+
+impl<'a, T: NetworkEvent, P: PublicId> Visitor<'a> for MessageVisitor<T, P> {
+    type Value = MessageParts<T, P>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a gossip message")
+    }
+
+    fn visit_seq<A: SeqAccess<'a>>(self, mut seq: A) -> result::Result<Self::Value, A::Error> {
+        let version = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let capabilities = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        let packed_events = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+        let mut envelope = Envelope {
+            version,
+            capabilities,
+            has_more: false,
+        };
+        if envelope.has_more_flag() {
+            envelope.has_more = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(3, &self))?;
+        }
+        Ok((envelope, packed_events))
+    }
+}
 
 /// A gossip request message.
-#[serde(bound = "")]
-#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Request<T: NetworkEvent, P: PublicId> {
+    // Version of the gossip protocol this message conforms to.
+    pub(crate) version: u32,
+    // Capabilities of the sender, if it hasn't agreed on a protocol version with the recipient yet.
+    pub(crate) capabilities: Option<Capabilities>,
     pub(crate) packed_events: Vec<PackedEvent<T, P>>,
+    // Set if the sender split the sync into several messages and this isn't the last of them.
+    // Only serialised from `SPLIT_SYNC_VERSION` on.
+    pub(crate) has_more: bool,
 }
 
 impl<T: NetworkEvent, P: PublicId> Request<T, P> {
     pub(crate) fn new(packed_events: Vec<PackedEvent<T, P>>) -> Self {
-        Self { packed_events }
+        Self::new_part(packed_events, false)
+    }
+
+    pub(crate) fn new_part(packed_events: Vec<PackedEvent<T, P>>, has_more: bool) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            capabilities: None,
+            packed_events,
+            has_more,
+        }
+    }
+
+    pub(crate) fn with_version(self, version: u32, capabilities: Option<Capabilities>) -> Self {
+        Self {
+            version,
+            capabilities,
+            ..self
+        }
+    }
+
+    /// Returns the version of the gossip protocol this message conforms to.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Returns `true` if the sender has more events for this sync which it will send in further
+    /// messages.  The recipient should keep gossiping with the sender until this returns `false`.
+    pub fn has_more(&self) -> bool {
+        self.has_more
+    }
+
+    /// Encodes this request in a compact binary format. Events refer to their parents in the same
+    /// message by offset and to their creators by position in a table of peer IDs, so the encoding
+    /// is typically much smaller than the serialised request.
+    pub fn to_compact_bytes(&self) -> Vec<u8> {
+        compact::encode(&self.envelope(), &self.packed_events)
+    }
+
+    /// Decodes a request encoded by `to_compact_bytes`. Returns `Error::InvalidMessage` if `bytes`
+    /// is not a valid encoding. The version of the request is checked when it is handled.
+    pub fn from_compact_bytes(bytes: &[u8]) -> Result<Self> {
+        compact::decode(bytes)
+            .map(|(envelope, packed_events)| Self::from_parts(envelope, packed_events))
+    }
+
+    fn envelope(&self) -> Envelope {
+        Envelope {
+            version: self.version,
+            capabilities: self.capabilities,
+            has_more: self.has_more,
+        }
+    }
+
+    fn from_parts(envelope: Envelope, packed_events: Vec<PackedEvent<T, P>>) -> Self {
+        Self::new_part(packed_events, envelope.has_more)
+            .with_version(envelope.version, envelope.capabilities)
     }
 }
 
 /// A gossip response message.
-#[serde(bound = "")]
-#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Response<T: NetworkEvent, P: PublicId> {
+    // Version of the gossip protocol this message conforms to.
+    pub(crate) version: u32,
+    // Capabilities of the sender, if it hasn't agreed on a protocol version with the recipient yet.
+    pub(crate) capabilities: Option<Capabilities>,
     pub(crate) packed_events: Vec<PackedEvent<T, P>>,
+    // Set if the sender split the sync into several messages and this isn't the last of them.
+    // Only serialised from `SPLIT_SYNC_VERSION` on.
+    pub(crate) has_more: bool,
 }
 
 impl<T: NetworkEvent, P: PublicId> Response<T, P> {
     pub(crate) fn new(packed_events: Vec<PackedEvent<T, P>>) -> Self {
-        Self { packed_events }
+        Self::new_part(packed_events, false)
+    }
+
+    pub(crate) fn new_part(packed_events: Vec<PackedEvent<T, P>>, has_more: bool) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            capabilities: None,
+            packed_events,
+            has_more,
+        }
+    }
+
+    pub(crate) fn with_version(self, version: u32, capabilities: Option<Capabilities>) -> Self {
+        Self {
+            version,
+            capabilities,
+            ..self
+        }
+    }
+
+    /// Returns the version of the gossip protocol this message conforms to.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Returns `true` if the sender has more events for this sync which it will send in further
+    /// messages.  The recipient should keep gossiping with the sender until this returns `false`.
+    pub fn has_more(&self) -> bool {
+        self.has_more
+    }
+
+    /// Encodes this response in a compact binary format. Events refer to their parents in the same
+    /// message by offset and to their creators by position in a table of peer IDs, so the encoding
+    /// is typically much smaller than the serialised response.
+    pub fn to_compact_bytes(&self) -> Vec<u8> {
+        compact::encode(&self.envelope(), &self.packed_events)
+    }
+
+    /// Decodes a response encoded by `to_compact_bytes`. Returns `Error::InvalidMessage` if `bytes`
+    /// is not a valid encoding. The version of the response is checked when it is handled.
+    pub fn from_compact_bytes(bytes: &[u8]) -> Result<Self> {
+        compact::decode(bytes)
+            .map(|(envelope, packed_events)| Self::from_parts(envelope, packed_events))
+    }
+
+    fn envelope(&self) -> Envelope {
+        Envelope {
+            version: self.version,
+            capabilities: self.capabilities,
+            has_more: self.has_more,
+        }
+    }
+
+    fn from_parts(envelope: Envelope, packed_events: Vec<PackedEvent<T, P>>) -> Self {
+        Self::new_part(packed_events, envelope.has_more)
+            .with_version(envelope.version, envelope.capabilities)
+    }
+}
+
+impl<T: NetworkEvent, P: PublicId> Serialize for Request<T, P> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> result::Result<S::Ok, S::Error> {
+        serialize_message(&self.envelope(), &self.packed_events, serializer)
+    }
+}
+
+impl<'a, T: NetworkEvent, P: PublicId> Deserialize<'a> for Request<T, P> {
+    fn deserialize<D: Deserializer<'a>>(deserializer: D) -> result::Result<Self, D::Error> {
+        deserialize_message(deserializer)
+            .map(|(envelope, packed_events)| Self::from_parts(envelope, packed_events))
+    }
+}
+
+impl<T: NetworkEvent, P: PublicId> Serialize for Response<T, P> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> result::Result<S::Ok, S::Error> {
+        serialize_message(&self.envelope(), &self.packed_events, serializer)
+    }
+}
+
+impl<'a, T: NetworkEvent, P: PublicId> Deserialize<'a> for Response<T, P> {
+    fn deserialize<D: Deserializer<'a>>(deserializer: D) -> result::Result<Self, D::Error> {
+        deserialize_message(deserializer)
+            .map(|(envelope, packed_events)| Self::from_parts(envelope, packed_events))
     }
 }
@@ -8,6 +8,7 @@
 
 mod abstract_event;
 mod cause;
+mod compact;
 mod content;
 mod event;
 mod event_context;
@@ -28,9 +29,11 @@ pub(super) use self::{
     event::Event,
     event_context::EventContextRef,
     graph::{EventIndex, Graph, IndexedEventRef},
+    messages::{Capabilities, SPLIT_SYNC_VERSION},
 };
 pub use self::{
     event_hash::EventHash,
-    messages::{Request, Response},
+    graph::GraphStorage,
+    messages::{Request, Response, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION},
     packed_event::PackedEvent,
 };
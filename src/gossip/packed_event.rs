@@ -6,15 +6,13 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{content::Content, event_hash::EventHash};
+use super::{cause::Cause, content::Content, event_hash::EventHash};
+use crate::{hash::Hash, observation::Observation, serialise, NetworkEvent, PublicId, Vote};
 #[cfg(all(feature = "mock", any(feature = "testing", test)))]
 use crate::{
-    gossip::Cause,
     id::SecretId,
     mock::{PeerId, Transaction},
-    observation::Observation,
 };
-use crate::{hash::Hash, serialise, NetworkEvent, PublicId, Vote};
 use std::fmt::{self, Debug, Formatter};
 
 /// Packed event contains only content and signature.
@@ -39,9 +37,70 @@ impl<T: NetworkEvent, P: PublicId> Debug for PackedEvent<T, P> {
 }
 
 impl<T: NetworkEvent, P: PublicId> PackedEvent<T, P> {
+    /// Getter for the event's creator.
+    pub fn creator(&self) -> &P {
+        &self.content.creator
+    }
+
+    /// Getter for the event's self-parent.
+    pub fn self_parent(&self) -> Option<&EventHash> {
+        self.content.self_parent()
+    }
+
+    /// Getter for the event's other-parent.
+    pub fn other_parent(&self) -> Option<&EventHash> {
+        self.content.other_parent()
+    }
+
     pub(crate) fn compute_hash(&self) -> EventHash {
         EventHash(Hash::from(serialise(&self.content).as_slice()))
     }
+
+    // Returns the hash of the event if it's signed by its creator, otherwise `None`.
+    pub(crate) fn verify_signature(&self) -> Option<EventHash> {
+        let serialised_content = serialise(&self.content);
+        if self
+            .content
+            .creator
+            .verify_signature(&self.signature, &serialised_content)
+        {
+            Some(EventHash(Hash::from(serialised_content.as_slice())))
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn is_initial(&self) -> bool {
+        if let Cause::Initial = self.content.cause {
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn is_request(&self) -> bool {
+        if let Cause::Request { .. } = self.content.cause {
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn requesting_recipient(&self) -> Option<&P> {
+        if let Cause::Requesting { ref recipient, .. } = self.content.cause {
+            Some(recipient)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn payload(&self) -> Option<&Observation<T, P>> {
+        if let Cause::Observation { ref vote, .. } = self.content.cause {
+            Some(vote.payload())
+        } else {
+            None
+        }
+    }
 }
 
 #[cfg(all(feature = "mock", any(feature = "testing", test)))]
@@ -110,19 +169,4 @@ impl PackedEvent<Transaction, PeerId> {
         let signature = content.creator.sign_detached(&serialised_content);
         PackedEvent { content, signature }
     }
-
-    /// Getter for the event's creator.
-    pub fn creator(&self) -> &PeerId {
-        &self.content.creator
-    }
-
-    /// Getter for the event's self-parent.
-    pub fn self_parent(&self) -> Option<&EventHash> {
-        self.content.self_parent()
-    }
-
-    /// Getter for the event's self-parent.
-    pub fn other_parent(&self) -> Option<&EventHash> {
-        self.content.other_parent()
-    }
 }
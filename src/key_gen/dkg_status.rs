@@ -0,0 +1,76 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::KeyGen;
+use crate::id::{PublicId, SecretId};
+use std::{
+    collections::BTreeSet,
+    fmt::{self, Display, Formatter},
+};
+
+/// Progress of a distributed key generation session that is still running. See
+/// [`Parsec::dkg_status`](struct.Parsec.html#method.dkg_status).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DkgStatus<P: PublicId> {
+    participants: BTreeSet<P>,
+    threshold: usize,
+    complete_parts: usize,
+}
+
+impl<P: PublicId> DkgStatus<P> {
+    pub(crate) fn new<S: SecretId<PublicId = P>>(key_gen: &KeyGen<S>) -> Self {
+        Self {
+            participants: key_gen.public_keys().clone(),
+            threshold: key_gen.threshold(),
+            complete_parts: key_gen.count_complete(),
+        }
+    }
+
+    /// Returns the peers taking part in the session.
+    pub fn participants(&self) -> &BTreeSet<P> {
+        &self.participants
+    }
+
+    /// Returns the degree of the polynomial being generated: any `threshold + 1` of the
+    /// participants will be able to create a signature.
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Returns the number of `Part`s which have been acknowledged by enough participants.
+    pub fn complete_parts(&self) -> usize {
+        self.complete_parts
+    }
+
+    /// Returns `true` if enough parts are complete for the keys to be generated, which happens
+    /// as soon as the next `Ack` is handled.
+    pub fn is_ready(&self) -> bool {
+        self.complete_parts > self.threshold
+    }
+}
+
+/// Reason a distributed key generation session didn't produce keys. Returned inside
+/// `Error::FailedDkg`.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum DkgFailure {
+    /// The session was cancelled via [`Parsec::cancel_dkg`](
+    /// struct.Parsec.html#method.cancel_dkg).
+    Cancelled,
+    /// Our part of the key generation failed, e.g. because encrypting the values for the other
+    /// participants did not succeed.
+    KeyGen(String),
+}
+
+impl Display for DkgFailure {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            DkgFailure::Cancelled => write!(formatter, "cancelled"),
+            DkgFailure::KeyGen(error) => write!(formatter, "{}", error),
+        }
+    }
+}
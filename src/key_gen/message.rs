@@ -18,6 +18,14 @@ pub enum DkgMessage {
     Ack { key_gen_id: KeyGenId, ack: Ack },
 }
 
+impl DkgMessage {
+    pub fn key_gen_id(&self) -> KeyGenId {
+        match *self {
+            DkgMessage::Part { key_gen_id, .. } | DkgMessage::Ack { key_gen_id, .. } => key_gen_id,
+        }
+    }
+}
+
 impl fmt::Debug for DkgMessage {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -96,6 +96,7 @@
 //! key. No single node knows the secret master key.
 
 pub mod dkg_result;
+pub mod dkg_status;
 pub mod message;
 pub mod parsec_rng;
 mod rng_adapter;
@@ -200,7 +201,6 @@ impl ProposalState {
     }
 }
 
-#[cfg(feature = "dump-graphs")]
 impl serde::Serialize for ProposalState {
     fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
         let values: Vec<_> = self
@@ -245,8 +245,10 @@ pub enum AckOutcome {
 /// A synchronous algorithm for dealerless distributed key generation.
 ///
 /// It requires that all nodes handle all messages in the exact same order.
-#[cfg_attr(feature = "dump-graphs", derive(Serialize))]
-#[derive(Deserialize)]
+///
+/// The serialised form contains the secret values we received from other nodes, so it must only
+/// be used to persist our own state and never be sent to peers.
+#[derive(Serialize, Deserialize)]
 pub struct KeyGen<S: SecretId> {
     /// Our node ID.
     our_id: S::PublicId,
@@ -306,12 +308,16 @@ impl<S: SecretId> KeyGen<S> {
         Ok((key_gen, Some(Part(commit, rows))))
     }
 
-    #[allow(unused)]
     /// Returns the map of participating nodes and their public keys.
     pub fn public_keys(&self) -> &BTreeSet<S::PublicId> {
         &self.pub_keys
     }
 
+    /// Returns the degree of the generated polynomial.
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
     /// Handles a `Part` message. If it is valid, returns an `Ack` message to be broadcast.
     ///
     /// If we are only an observer, `None` is returned instead and no messages need to be sent.
@@ -537,8 +543,3 @@ pub enum PartFault {
     #[fail(display = "Row does not match the commitment")]
     RowCommitment,
 }
-
-/// Threshold to use for running DKG
-pub fn dkg_threshold(participants_count: usize) -> usize {
-    participants_count.saturating_sub(1) / 3
-}
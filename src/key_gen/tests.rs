@@ -23,9 +23,10 @@
 
 use std::collections::{BTreeMap, BTreeSet};
 
-use super::{dkg_threshold, KeyGen, PartOutcome};
+use super::{KeyGen, PartOutcome};
 use crate::mock::PeerId;
 use crate::{
+    config::QuorumPolicy,
     dev_utils::{Environment, RngChoice},
     serialise,
 };
@@ -139,7 +140,7 @@ fn test_key_gen_with(threshold: usize, node_num: usize) {
 }
 
 fn test_key_gen(node_num: usize) {
-    test_key_gen_with(dkg_threshold(node_num), node_num);
+    test_key_gen_with(QuorumPolicy::default().tolerated_faults(node_num), node_num);
 }
 
 #[test]
@@ -94,7 +94,10 @@
 //! existing section, to construct a `Parsec` instance.
 //! * Calling [`Parsec::vote_for`](struct.Parsec.html#method.vote_for) whenever the peer is
 //! supposed to vote for a transaction (be it an application-specific, opaque payload, or a section
-//! mutation: a peer joining or being removed).
+//! mutation: a peer joining or being removed). A
+//! [`PayloadValidator`](trait.PayloadValidator.html) can be registered via
+//! [`Parsec::set_payload_validator`](struct.Parsec.html#method.set_payload_validator) for votes for
+//! invalid application-specific payloads to be refused.
 //! * Calling [`Parsec::create_gossip`](struct.Parsec.html#method.create_gossip) at random points
 //! to exchange information with other peers. The function returns a message containing a gossip
 //! request to be sent to the gossip partner.
@@ -104,7 +107,11 @@
 //! response is received.
 //! * Calling [`Parsec::poll`](struct.Parsec.html#method.poll) to see whether there are new agreed
 //! blocks - this is typically called after `handle_request` or `handle_response` until it returns
-//! `None`.
+//! `None`. [`Parsec::batch_poll`](struct.Parsec.html#method.batch_poll) returns the blocks
+//! grouped by the meta-election which decided them instead, so that they can be applied
+//! atomically. Alternatively, a [`BlockSink`](trait.BlockSink.html) can be registered via
+//! [`Parsec::set_block_sink`](struct.Parsec.html#method.set_block_sink) to be handed the blocks as
+//! soon as they are agreed.
 //!
 //! The crate doesn't include any networking layer - sending and receiving messages is the
 //! consumer's responsibility.
@@ -178,8 +185,10 @@ extern crate unwrap;
 pub mod dev_utils;
 
 mod block;
+mod config;
 mod dump_graph;
 mod error;
+mod evidence;
 mod gossip;
 mod hash;
 mod id;
@@ -190,6 +199,7 @@ mod observation;
 mod parsec;
 mod parsec_helpers;
 mod peer_list;
+mod section_summary;
 mod vote;
 
 #[cfg(all(test, feature = "mock"))]
@@ -207,14 +217,29 @@ pub mod mock;
 #[cfg(feature = "dump-graphs")]
 pub use crate::dump_graph::{DumpGraphMode, DIR, DUMP_MODE};
 pub use crate::{
-    block::Block,
+    block::{
+        verify_chain, Block, BlockHash, BlockSignatureShare, BlockSink, ChainLink, ConsensusBatch,
+    },
+    config::{DkgPolicy, ParsecConfig, QuorumPolicy, SpamPolicy},
     error::{Error, Result},
-    gossip::{EventHash, PackedEvent, Request, Response},
+    evidence::{verify_evidence, verify_evidence_with_genesis, Evidence},
+    gossip::{
+        EventHash, GraphStorage, PackedEvent, Request, Response, MIN_PROTOCOL_VERSION,
+        PROTOCOL_VERSION,
+    },
     id::{Proof, PublicId, SecretId},
-    key_gen::dkg_result::*,
-    network_event::NetworkEvent,
-    observation::{ConsensusMode, Malice, Observation},
-    parsec::Parsec,
+    key_gen::{
+        dkg_result::*,
+        dkg_status::{DkgFailure, DkgStatus},
+    },
+    meta_voting::{CoinId, CoinShare},
+    network_event::{NetworkEvent, PayloadValidator},
+    observation::{
+        ConsensusMode, KeyRotationProof, Malice, MaliceKind, Observation, UnprovableMalice,
+        MAX_WEIGHT,
+    },
+    parsec::{KeyGenId, Parsec},
+    section_summary::{CatchUp, SectionSummary, SignedSectionSummary},
     vote::Vote,
 };
 
@@ -0,0 +1,425 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    block::{self, BlockHash, BlockSignatureShare},
+    gossip::EventIndex,
+    id::PublicId,
+    observation::ObservationHash,
+    serialise, DkgResult,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    mem,
+};
+use threshold_crypto::{
+    serde_impl::SerdeSecret, PublicKeySet, SecretKeyShare, Signature, SignatureShare,
+};
+
+/// Identifies a single toss of the common coin.
+#[serde(bound = "")]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Debug)]
+pub struct CoinId<P: PublicId> {
+    /// Number of meta-elections completed since the keys of the coin were generated.
+    pub election: usize,
+    /// Peer whose meta-votes the coin is tossed for.
+    pub peer_id: P,
+    /// Round of the meta-votes the coin is tossed for.
+    pub round: usize,
+}
+
+/// A peer's share of a toss of the common coin.
+#[serde(bound = "")]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Debug)]
+pub struct CoinShare<P: PublicId> {
+    /// The toss this is a share of.
+    pub coin_id: CoinId<P>,
+    /// Signature share of the serialised `coin_id`.
+    pub share: SignatureShare,
+}
+
+/// Keys of the threshold common coin, generated by a DKG among the voters.
+#[serde(bound = "")]
+#[derive(Serialize, Deserialize)]
+pub(crate) struct CoinKeys<P: PublicId> {
+    participants: BTreeSet<P>,
+    public_key_set: PublicKeySet,
+    secret_key_share: Option<SerdeSecret<SecretKeyShare>>,
+}
+
+impl<P: PublicId> CoinKeys<P> {
+    pub fn new(participants: BTreeSet<P>, dkg_result: &DkgResult) -> Self {
+        Self {
+            participants,
+            public_key_set: dkg_result.public_key_set.clone(),
+            secret_key_share: dkg_result.secret_key_share.clone().map(SerdeSecret),
+        }
+    }
+
+    /// Creates the keys of a peer which didn't take part in the DKG, from its public results.
+    pub fn from_public(participants: BTreeSet<P>, public_key_set: PublicKeySet) -> Self {
+        Self {
+            participants,
+            public_key_set,
+            secret_key_share: None,
+        }
+    }
+
+    pub fn participants(&self) -> &BTreeSet<P> {
+        &self.participants
+    }
+
+    pub fn public_key_set(&self) -> &PublicKeySet {
+        &self.public_key_set
+    }
+
+    /// Returns our share of the given toss, or `None` if we didn't take part in the DKG.
+    pub fn sign(&self, coin_id: CoinId<P>) -> Option<CoinShare<P>> {
+        let share = self.secret_key_share.as_ref()?.sign(serialise(&coin_id));
+        Some(CoinShare { coin_id, share })
+    }
+
+    /// Returns our share of the signature of the block with the given index, previous block hash
+    /// and payload hash, or `None` if we didn't take part in the DKG.
+    pub fn sign_block(
+        &self,
+        index: u64,
+        previous: Option<BlockHash>,
+        payload_hash: ObservationHash,
+    ) -> Option<BlockSignatureShare> {
+        let share = self.secret_key_share.as_ref()?.sign(block::signed_content(
+            index,
+            previous.as_ref(),
+            &payload_hash,
+        ));
+        Some(BlockSignatureShare {
+            index,
+            previous,
+            payload_hash,
+            share,
+        })
+    }
+
+    /// Returns the index of the share's creator among the participants if the share is valid.
+    pub fn verify_block_share(&self, peer_id: &P, share: &BlockSignatureShare) -> Option<usize> {
+        let participant_index = self.participant_index(peer_id)?;
+        if self
+            .public_key_set
+            .public_key_share(participant_index)
+            .verify(
+                &share.share,
+                block::signed_content(share.index, share.previous.as_ref(), &share.payload_hash),
+            )
+        {
+            Some(participant_index)
+        } else {
+            None
+        }
+    }
+
+    /// Combines the signature of a block if there are enough valid shares of it.
+    pub fn combine_block_shares(
+        &self,
+        shares: &BTreeMap<usize, SignatureShare>,
+    ) -> Option<Signature> {
+        if shares.len() <= self.threshold() {
+            return None;
+        }
+        self.public_key_set
+            .combine_signatures(shares.iter().take(self.threshold() + 1))
+            .ok()
+    }
+
+    fn participant_index(&self, peer_id: &P) -> Option<usize> {
+        self.participants.iter().position(|id| id == peer_id)
+    }
+
+    fn verify(&self, participant_index: usize, coin_share: &CoinShare<P>) -> bool {
+        self.public_key_set
+            .public_key_share(participant_index)
+            .verify(&coin_share.share, serialise(&coin_share.coin_id))
+    }
+
+    // The combined signature is the same for any `threshold + 1` valid shares, so is its parity.
+    fn combine<'a, I>(&self, shares: I) -> Option<bool>
+    where
+        I: IntoIterator<Item = (usize, &'a SignatureShare)>,
+    {
+        self.public_key_set
+            .combine_signatures(shares.into_iter().take(self.threshold() + 1))
+            .ok()
+            .map(|signature| signature.parity())
+    }
+
+    fn threshold(&self) -> usize {
+        self.public_key_set.threshold()
+    }
+}
+
+/// The common coin shares gossiped to us, and the tosses they decide.
+#[serde(bound = "")]
+#[derive(Serialize, Deserialize)]
+pub(crate) struct CoinShares<P: PublicId> {
+    // Number of meta-elections completed since the current keys were generated.
+    election: usize,
+    // Valid shares of the tosses of the current meta-election, with the indices of the events
+    // carrying them and the indices of their creators among the DKG participants.
+    shares: BTreeMap<CoinId<P>, BTreeMap<EventIndex, (usize, SignatureShare)>>,
+    // Results of the tosses of the current meta-election we have enough valid shares of.
+    results: BTreeMap<CoinId<P>, bool>,
+    // Shares of later meta-elections, or received before the keys they belong to were generated.
+    // These are validated once their meta-election starts.
+    pending: Vec<(EventIndex, P, CoinShare<P>)>,
+    // Tosses of the current meta-election we've created our share of.
+    ours: BTreeSet<CoinId<P>>,
+}
+
+impl<P: PublicId> Default for CoinShares<P> {
+    fn default() -> Self {
+        Self {
+            election: 0,
+            shares: BTreeMap::new(),
+            results: BTreeMap::new(),
+            pending: Vec::new(),
+            ours: BTreeSet::new(),
+        }
+    }
+}
+
+impl<P: PublicId> CoinShares<P> {
+    pub fn election(&self) -> usize {
+        self.election
+    }
+
+    /// Records the share carried by the event at `event_index`, created by `creator`.
+    pub fn add(
+        &mut self,
+        keys: Option<&CoinKeys<P>>,
+        event_index: EventIndex,
+        creator: &P,
+        coin_share: CoinShare<P>,
+    ) {
+        let keys = match keys {
+            Some(keys) if coin_share.coin_id.election == self.election => keys,
+            Some(_) if coin_share.coin_id.election < self.election => return,
+            _ => {
+                self.pending
+                    .push((event_index, creator.clone(), coin_share));
+                return;
+            }
+        };
+
+        let participant_index = match keys.participant_index(creator) {
+            Some(participant_index) if keys.verify(participant_index, &coin_share) => {
+                participant_index
+            }
+            _ => {
+                debug!("Ignoring invalid coin share {:?}", coin_share);
+                return;
+            }
+        };
+
+        let shares = self
+            .shares
+            .entry(coin_share.coin_id.clone())
+            .or_insert_with(BTreeMap::new);
+        let _ = shares.insert(event_index, (participant_index, coin_share.share));
+
+        // Shares are deterministic, so more than one share from the same creator are duplicates.
+        let unique_shares: BTreeMap<_, _> = shares
+            .values()
+            .map(|(participant_index, share)| (*participant_index, share))
+            .collect();
+        if unique_shares.len() > keys.threshold() && !self.results.contains_key(&coin_share.coin_id)
+        {
+            if let Some(result) = keys.combine(unique_shares) {
+                let _ = self.results.insert(coin_share.coin_id, result);
+            }
+        }
+    }
+
+    /// Moves on to the next meta-election.
+    pub fn next_election(&mut self, keys: Option<&CoinKeys<P>>) {
+        self.start_election(self.election + 1, keys)
+    }
+
+    /// Starts counting the meta-elections from zero, for newly generated keys.
+    pub fn reset(&mut self, keys: Option<&CoinKeys<P>>) {
+        self.start_election(0, keys)
+    }
+
+    /// Starts counting the meta-elections from `election`, for a peer caught up with a section.
+    pub fn catch_up(&mut self, election: usize, keys: Option<&CoinKeys<P>>) {
+        self.start_election(election, keys)
+    }
+
+    fn start_election(&mut self, election: usize, keys: Option<&CoinKeys<P>>) {
+        self.election = election;
+        self.shares.clear();
+        self.results.clear();
+        self.ours.clear();
+        for (event_index, creator, coin_share) in mem::replace(&mut self.pending, vec![]) {
+            self.add(keys, event_index, &creator, coin_share);
+        }
+    }
+
+    /// Returns the result of the toss, if more than `threshold` of its valid shares are carried
+    /// by events for which `can_see` returns `true`.
+    pub fn toss<F>(&self, keys: &CoinKeys<P>, coin_id: &CoinId<P>, can_see: F) -> Option<bool>
+    where
+        F: Fn(EventIndex) -> bool,
+    {
+        let result = self.results.get(coin_id)?;
+        let visible_shares = self
+            .shares
+            .get(coin_id)?
+            .iter()
+            .filter(|(event_index, _)| can_see(**event_index))
+            .map(|(_, (participant_index, _))| participant_index)
+            .collect::<BTreeSet<_>>()
+            .len();
+        if visible_shares > keys.threshold() {
+            Some(*result)
+        } else {
+            None
+        }
+    }
+
+    /// Records that we're creating our share of the toss. Returns `false` if we already did.
+    pub fn add_ours(&mut self, coin_id: CoinId<P>) -> bool {
+        self.ours.insert(coin_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mock::{PeerId, Transaction},
+        observation::Observation,
+    };
+    use threshold_crypto::{poly::Poly, SecretKeySet};
+
+    // Returns the peers and each one's coin keys, with threshold 1.
+    fn setup() -> (Vec<PeerId>, Vec<CoinKeys<PeerId>>) {
+        let peer_ids = PeerId::named_peer_ids()[0..4].to_vec();
+        let participants: BTreeSet<_> = peer_ids.iter().cloned().collect();
+        let secret_key_set = SecretKeySet::from(Poly::monomial(1) + 7u64);
+        let keys = (0..peer_ids.len())
+            .map(|index| {
+                let dkg_result = DkgResult::new(
+                    secret_key_set.public_keys(),
+                    Some(secret_key_set.secret_key_share(index)),
+                );
+                CoinKeys::new(participants.clone(), &dkg_result)
+            })
+            .collect();
+        (peer_ids, keys)
+    }
+
+    fn coin_id(election: usize, peer_id: &PeerId) -> CoinId<PeerId> {
+        CoinId {
+            election,
+            peer_id: peer_id.clone(),
+            round: 0,
+        }
+    }
+
+    fn index(index: usize) -> EventIndex {
+        EventIndex::from_topological_index(index)
+    }
+
+    #[test]
+    fn toss_needs_more_than_threshold_visible_shares() {
+        let (peer_ids, keys) = setup();
+        let coin_id = coin_id(0, &peer_ids[0]);
+        let share = |signer: usize| unwrap!(keys[signer].sign(coin_id.clone()));
+
+        let mut shares = CoinShares::default();
+        shares.add(Some(&keys[0]), index(0), &peer_ids[0], share(0));
+        assert_eq!(shares.toss(&keys[0], &coin_id, |_| true), None);
+
+        shares.add(Some(&keys[0]), index(1), &peer_ids[1], share(1));
+        let result = unwrap!(shares.toss(&keys[0], &coin_id, |_| true));
+        assert_eq!(
+            shares.toss(&keys[0], &coin_id, |event_index| event_index != index(1)),
+            None
+        );
+
+        // Any other set of shares gives the same result.
+        let mut other_shares = CoinShares::default();
+        other_shares.add(Some(&keys[3]), index(2), &peer_ids[2], share(2));
+        other_shares.add(Some(&keys[3]), index(3), &peer_ids[3], share(3));
+        assert_eq!(
+            other_shares.toss(&keys[3], &coin_id, |_| true),
+            Some(result)
+        );
+    }
+
+    #[test]
+    fn invalid_and_duplicate_shares_are_ignored() {
+        let (peer_ids, keys) = setup();
+        let coin_id = coin_id(0, &peer_ids[0]);
+        let share = |signer: usize| unwrap!(keys[signer].sign(coin_id.clone()));
+
+        let mut shares = CoinShares::default();
+        shares.add(Some(&keys[0]), index(0), &peer_ids[0], share(0));
+        shares.add(Some(&keys[0]), index(1), &peer_ids[0], share(0));
+        shares.add(Some(&keys[0]), index(2), &peer_ids[2], share(1));
+        assert_eq!(shares.toss(&keys[0], &coin_id, |_| true), None);
+    }
+
+    #[test]
+    fn shares_of_later_elections_are_kept_until_they_start() {
+        let (peer_ids, keys) = setup();
+        let coin_id = coin_id(1, &peer_ids[0]);
+        let share = |signer: usize| unwrap!(keys[signer].sign(coin_id.clone()));
+
+        let mut shares = CoinShares::default();
+        shares.add(Some(&keys[0]), index(0), &peer_ids[0], share(0));
+        shares.add(None, index(1), &peer_ids[1], share(1));
+        assert_eq!(shares.toss(&keys[0], &coin_id, |_| true), None);
+
+        shares.next_election(Some(&keys[0]));
+        assert!(shares.toss(&keys[0], &coin_id, |_| true).is_some());
+
+        shares.next_election(Some(&keys[0]));
+        assert_eq!(shares.toss(&keys[0], &coin_id, |_| true), None);
+    }
+
+    #[test]
+    fn block_signature_needs_more_than_threshold_valid_shares() {
+        let (peer_ids, keys) = setup();
+        let payload_hash = ObservationHash::from(
+            &Observation::<Transaction, PeerId>::OpaquePayload(Transaction::new("ABCD")),
+        );
+        let share = |signer: usize| unwrap!(keys[signer].sign_block(7, None, payload_hash));
+
+        // A share verified against another peer's key is rejected.
+        assert_eq!(keys[0].verify_block_share(&peer_ids[1], &share(0)), None);
+
+        let mut shares = BTreeMap::new();
+        for signer in 0..2 {
+            assert!(keys[0].combine_block_shares(&shares).is_none());
+            let index = unwrap!(keys[0].verify_block_share(&peer_ids[signer], &share(signer)));
+            let _ = shares.insert(index, share(signer).share);
+        }
+
+        let signature = unwrap!(keys[0].combine_block_shares(&shares));
+        assert!(keys[0]
+            .public_key_set
+            .public_key()
+            .verify(&signature, block::signed_content(7, None, &payload_hash)));
+
+        // The signature doesn't hold for the same payload at another position in the history.
+        assert!(!keys[0]
+            .public_key_set
+            .public_key()
+            .verify(&signature, block::signed_content(8, None, &payload_hash)));
+    }
+}
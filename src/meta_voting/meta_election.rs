@@ -23,7 +23,7 @@ lazy_static! {
     static ref EMPTY_BTREESET_EVENT_INDEX: BTreeSet<EventIndex> = BTreeSet::new();
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub(crate) struct UnconsensusedEvents {
     // Set of all events that carry a payload that hasn't yet been consensused.
     pub(crate) ordered_indices: BTreeSet<EventIndex>,
@@ -31,13 +31,16 @@ pub(crate) struct UnconsensusedEvents {
     pub(crate) indices_by_key: FnvHashMap<ObservationKey, BTreeSet<EventIndex>>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub(crate) struct MetaElection {
     // Set of meta-events corresponding to the events in the gossip graph.
     pub(crate) meta_events: FnvHashMap<EventIndex, MetaEvent>,
     // Set of peers participating in this meta-election, i.e. all voters at the time the current
     // meta-election was started.
     pub(crate) voters: PeerIndexSet,
+    // Weights of the voters whose weight isn't the default of one, as of the start of the current
+    // meta-election. A consensused `ChangeWeight` only takes effect here, once the next one starts.
+    pub(crate) weights: PeerIndexMap<usize>,
     // The indices of events for each peer that have a non-empty set of `interesting_content`.
     // The second element allow fast lookup for existing interesting_content.
     pub(crate) interesting_events: PeerIndexMap<(Vec<EventIndex>, FnvHashSet<ObservationKey>)>,
@@ -57,6 +60,7 @@ impl MetaElection {
         MetaElection {
             meta_events: FnvHashMap::default(),
             voters,
+            weights: PeerIndexMap::default(),
             interesting_events: PeerIndexMap::default(),
             unconsensused_events: UnconsensusedEvents::default(),
             consensus_history: Vec::new(),
@@ -65,6 +69,20 @@ impl MetaElection {
         }
     }
 
+    /// Creates the meta-election a peer caught up with a section starts at, given the voters and
+    /// their weights as of its start, and the keys the section consensused before it.
+    pub fn caught_up(
+        voters: PeerIndexSet,
+        weights: PeerIndexMap<usize>,
+        consensus_history: Vec<ObservationKey>,
+    ) -> Self {
+        MetaElection {
+            weights,
+            consensus_history,
+            ..Self::new(voters)
+        }
+    }
+
     pub fn add_meta_event<P: PublicId>(&mut self, builder: MetaEventBuilder<P>) {
         let event_index = builder.event().event_index();
         let creator = builder.event().creator();
@@ -103,6 +121,25 @@ impl MetaElection {
         &self.voters
     }
 
+    /// Weight of the given peer's votes in the current meta-election.
+    pub fn weight(&self, peer_index: PeerIndex) -> usize {
+        self.weights.get(peer_index).cloned().unwrap_or(1)
+    }
+
+    /// Sum of the weights of the given peers. As the weights are at most `MAX_WEIGHT`, it only
+    /// saturates for sections far larger than possible.
+    pub fn weight_of<I: IntoIterator<Item = PeerIndex>>(&self, peers: I) -> usize {
+        peers
+            .into_iter()
+            .map(|peer_index| self.weight(peer_index))
+            .fold(0, usize::saturating_add)
+    }
+
+    /// Sum of the weights of the voters participating in the current meta-election.
+    pub fn voters_weight(&self) -> usize {
+        self.weight_of(&self.voters)
+    }
+
     pub fn consensus_history(&self) -> &[ObservationKey] {
         &self.consensus_history
     }
@@ -135,6 +172,10 @@ impl MetaElection {
         self.continue_consensus_start_index
     }
 
+    pub fn new_consensus_start_index(&self) -> usize {
+        self.new_consensus_start_index
+    }
+
     /// Starts new election.
     pub fn new_election<P: PublicId>(
         &mut self,
@@ -228,6 +269,16 @@ impl MetaElection {
                     if !self.voters.remove(peer_index) {
                         log_or_panic!("Meta election doesn't contain {:?}", peer_index);
                     }
+                    let _ = self.weights.remove(peer_index);
+                }
+                PeerListChange::ChangeWeight(peer_index, weight) => {
+                    if !self.voters.contains(peer_index) {
+                        log_or_panic!("Meta election doesn't contain {:?}", peer_index);
+                    } else if weight == 1 {
+                        let _ = self.weights.remove(peer_index);
+                    } else {
+                        let _ = self.weights.insert(peer_index, weight);
+                    }
                 }
             }
         }
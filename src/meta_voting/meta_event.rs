@@ -14,7 +14,7 @@ use crate::{
     peer_list::{PeerIndex, PeerIndexMap, PeerIndexSet},
 };
 
-#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub(crate) struct MetaEvent {
     pub observer: Observer,
     // Hashes of payloads of all the votes deemed interesting by this event.
@@ -22,7 +22,7 @@ pub(crate) struct MetaEvent {
     pub meta_votes: PeerIndexMap<Vec<MetaVote>>,
 }
 
-#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub(crate) enum Observer {
     // This event is observer (it has supermajority of observees and it is the first such event of
     // the same creator).
@@ -15,6 +15,7 @@ use super::{
     meta_vote_counts::MetaVoteCounts,
     meta_vote_values::{MetaVoteValues, Step},
 };
+use crate::config::QuorumPolicy;
 use std::{
     collections::BTreeMap,
     fmt::{self, Debug, Formatter},
@@ -64,33 +65,53 @@ impl MetaVote {
 
     pub fn new_for_observer(
         initial_estimate: bool,
-        others: &[&[MetaVote]],
-        total_peers: NonZeroUsize,
+        weight: usize,
+        others: &[(&[MetaVote], usize)],
+        total_weight: NonZeroUsize,
+        quorum_policy: QuorumPolicy,
     ) -> Vec<Self> {
         let initial = Self {
             values: MetaVoteValues::from_initial_estimate(initial_estimate),
             ..Default::default()
         };
-        Self::next_votes(&[initial], others, &BTreeMap::new(), total_peers)
+        Self::next_votes(
+            &[initial],
+            weight,
+            others,
+            &BTreeMap::new(),
+            total_weight,
+            quorum_policy,
+        )
     }
 
     /// Create temporary next meta-votes. They must be finalized by calling `next_final` before
     /// passing them to `MetaEvent`.
     pub fn next_temp(
         parent: &[MetaVote],
-        others: &[&[MetaVote]],
-        total_peers: NonZeroUsize,
+        weight: usize,
+        others: &[(&[MetaVote], usize)],
+        total_weight: NonZeroUsize,
+        quorum_policy: QuorumPolicy,
     ) -> Vec<Self> {
-        Self::next_votes(parent, others, &BTreeMap::new(), total_peers)
+        Self::next_votes(
+            parent,
+            weight,
+            others,
+            &BTreeMap::new(),
+            total_weight,
+            quorum_policy,
+        )
     }
 
     /// Finalize temporary meta-votes.
     pub fn next_final(
         temp: &[MetaVote],
+        weight: usize,
         coin_tosses: &BTreeMap<usize, bool>,
-        total_peers: NonZeroUsize,
+        total_weight: NonZeroUsize,
+        quorum_policy: QuorumPolicy,
     ) -> Vec<Self> {
-        Self::next_votes(temp, &[], coin_tosses, total_peers)
+        Self::next_votes(temp, weight, &[], coin_tosses, total_weight, quorum_policy)
     }
 
     pub fn decision(&self) -> Option<bool> {
@@ -100,15 +121,24 @@ impl MetaVote {
         }
     }
 
+    /// Returns whether the coin toss of this meta-vote's round may be needed to decide it, i.e. it's
+    /// undecided and either at the genuine flip step or waiting for the toss.
+    pub fn awaits_coin_toss(&self) -> bool {
+        !self.is_decided()
+            && (self.step == Step::GenuineFlip || self.values.is_waiting_for_coin_toss())
+    }
+
     fn next_votes(
         prev: &[MetaVote],
-        others: &[&[MetaVote]],
+        weight: usize,
+        others: &[(&[MetaVote], usize)],
         coin_tosses: &BTreeMap<usize, bool>,
-        total_peers: NonZeroUsize,
+        total_weight: NonZeroUsize,
+        quorum_policy: QuorumPolicy,
     ) -> Vec<Self> {
         let mut next = Vec::new();
         for vote in prev {
-            let counts = MetaVoteCounts::new(vote, others, total_peers);
+            let counts = MetaVoteCounts::new(vote, weight, others, total_weight, quorum_policy);
             let mut updated = *vote;
             updated.update(counts, &coin_tosses);
             let decided = vote.is_decided();
@@ -118,9 +148,14 @@ impl MetaVote {
             }
         }
 
-        while let Some(next_meta_vote) =
-            Self::next_vote(next.last(), others, &coin_tosses, total_peers)
-        {
+        while let Some(next_meta_vote) = Self::next_vote(
+            next.last(),
+            weight,
+            others,
+            &coin_tosses,
+            total_weight,
+            quorum_policy,
+        ) {
             next.push(next_meta_vote);
         }
 
@@ -146,20 +181,23 @@ impl MetaVote {
 
     fn next_vote(
         parent: Option<&Self>,
-        others: &[&[MetaVote]],
+        weight: usize,
+        others: &[(&[MetaVote], usize)],
         coin_tosses: &BTreeMap<usize, bool>,
-        total_peers: NonZeroUsize,
+        total_weight: NonZeroUsize,
+        quorum_policy: QuorumPolicy,
     ) -> Option<MetaVote> {
         let parent = parent?;
 
         if parent.is_decided() {
             return None;
         }
-        let counts = MetaVoteCounts::new(parent, others, total_peers);
+        let counts = MetaVoteCounts::new(parent, weight, others, total_weight, quorum_policy);
         if counts.is_supermajority(counts.aux_values_set()) {
             let coin_toss = coin_tosses.get(&parent.round);
             let mut next = parent.increase_step(&counts, coin_toss.cloned());
-            let new_counts = MetaVoteCounts::new(&next, others, total_peers);
+            let new_counts =
+                MetaVoteCounts::new(&next, weight, others, total_weight, quorum_policy);
             next.update(new_counts, &coin_tosses);
             Some(next)
         } else {
@@ -202,7 +240,7 @@ mod tests {
             step: Step::ForcedTrue,
             values: MetaVoteValues::Decided(true),
         };
-        let total_peers = 7;
+        let total_weight = 7;
 
         collected_votes.push(vec![decided_meta_vote]);
 
@@ -211,17 +249,19 @@ mod tests {
             step: Step::ForcedTrue,
             values: MetaVoteValues::Undecided(UndecidedMetaVoteValues::default()),
         };
-        for _ in 1..total_peers - 1 {
+        for _ in 1..total_weight - 1 {
             collected_votes.push(vec![undecided_meta_vote]);
         }
 
         for votes in collected_votes.iter() {
-            others.push(votes.as_slice());
+            others.push((votes.as_slice(), 1));
         }
         let result = MetaVote::new_for_observer(
             true,
+            1,
             others.as_slice(),
-            NonZeroUsize::new(total_peers).unwrap(),
+            NonZeroUsize::new(total_weight).unwrap(),
+            QuorumPolicy::default(),
         );
         assert_eq!(result.len(), 1);
         assert_eq!(result[0], decided_meta_vote);
@@ -232,7 +272,7 @@ mod tests {
         let mut collected_votes = vec![];
         let mut others = vec![];
 
-        let total_peers = 7;
+        let total_weight = 7;
         let undecided_meta_vote = MetaVote {
             round: 0,
             step: Step::ForcedTrue,
@@ -242,17 +282,19 @@ mod tests {
                 AuxValue::new(Some(false)),
             )),
         };
-        for _ in 0..total_peers - 1 {
+        for _ in 0..total_weight - 1 {
             collected_votes.push(vec![undecided_meta_vote]);
         }
 
         for votes in collected_votes.iter() {
-            others.push(votes.as_slice());
+            others.push((votes.as_slice(), 1));
         }
         let result = MetaVote::new_for_observer(
             true,
+            1,
             others.as_slice(),
-            NonZeroUsize::new(total_peers).unwrap(),
+            NonZeroUsize::new(total_weight).unwrap(),
+            QuorumPolicy::default(),
         );
         assert_eq!(result.len(), 2);
         let expected_meta_votes = vec![
@@ -283,7 +325,7 @@ mod tests {
         let mut collected_votes = vec![];
         let mut others = vec![];
 
-        let total_peers = 7;
+        let total_weight = 7;
         let undecided_meta_vote_1 = MetaVote {
             round: 0,
             step: Step::ForcedTrue,
@@ -302,17 +344,19 @@ mod tests {
                 AuxValue::new(Some(false)),
             )),
         };
-        for _ in 0..total_peers - 1 {
+        for _ in 0..total_weight - 1 {
             collected_votes.push(vec![undecided_meta_vote_1, undecided_meta_vote_2]);
         }
 
         for votes in collected_votes.iter() {
-            others.push(votes.as_slice());
+            others.push((votes.as_slice(), 1));
         }
         let result = MetaVote::new_for_observer(
             true,
+            1,
             others.as_slice(),
-            NonZeroUsize::new(total_peers).unwrap(),
+            NonZeroUsize::new(total_weight).unwrap(),
+            QuorumPolicy::default(),
         );
         assert_eq!(result.len(), 2);
         let expected_meta_votes = vec![
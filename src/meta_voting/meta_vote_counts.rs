@@ -7,7 +7,7 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::meta_vote::MetaVote;
-use crate::observation::is_more_than_two_thirds;
+use crate::config::QuorumPolicy;
 use std::iter;
 use std::num::NonZeroUsize;
 use std::ops::AddAssign;
@@ -23,7 +23,8 @@ pub(crate) struct MetaVoteCounts {
     pub aux_values_true: usize,
     pub aux_values_false: usize,
     pub decision: Option<bool>,
-    pub total_peers: NonZeroUsize,
+    pub total_weight: NonZeroUsize,
+    pub quorum_policy: QuorumPolicy,
 }
 
 impl AddAssign for MetaVoteCounts {
@@ -41,8 +42,14 @@ impl AddAssign for MetaVoteCounts {
 impl MetaVoteCounts {
     // Construct a `MetaVoteCounts` by collecting details from all meta votes which are for the
     // given `parent`'s `round` and `step`.  These results will include info from our own `parent`
-    // meta vote.
-    pub fn new(parent: &MetaVote, others: &[&[MetaVote]], total_peers: NonZeroUsize) -> Self {
+    // meta vote.  Each vote contributes the weight of the voter which cast it.
+    pub fn new(
+        parent: &MetaVote,
+        weight: usize,
+        others: &[(&[MetaVote], usize)],
+        total_weight: NonZeroUsize,
+        quorum_policy: QuorumPolicy,
+    ) -> Self {
         let mut counts = MetaVoteCounts {
             estimates_true: 0,
             estimates_false: 0,
@@ -51,19 +58,21 @@ impl MetaVoteCounts {
             aux_values_true: 0,
             aux_values_false: 0,
             decision: None,
-            total_peers,
+            total_weight,
+            quorum_policy,
         };
-        for vote in others
+        for (vote, weight) in others
             .iter()
-            .filter_map(|other| {
+            .filter_map(|(other, weight)| {
                 other
                     .iter()
                     .filter(|vote| vote.round_and_step() == parent.round_and_step())
                     .last()
+                    .map(|vote| (vote, *weight))
             })
-            .chain(iter::once(parent))
+            .chain(iter::once((parent, weight)))
         {
-            let contribution = vote.values.count(total_peers);
+            let contribution = vote.values.count(weight, total_weight, quorum_policy);
             counts += contribution;
         }
 
@@ -75,27 +84,32 @@ impl MetaVoteCounts {
     }
 
     pub fn is_supermajority(&self, count: usize) -> bool {
-        is_more_than_two_thirds(count, self.total_peers())
+        self.quorum_policy.is_quorum(count, self.total_weight())
     }
 
-    pub fn is_at_least_one_third(&self, count: usize) -> bool {
-        3 * count >= self.total_peers()
+    // Returns whether `count` peers include at least one honest peer.
+    pub fn is_more_than_tolerated_faults(&self, count: usize) -> bool {
+        self.quorum_policy
+            .is_more_than_tolerated_faults(count, self.total_weight())
     }
 
     pub fn check_exceeding(&self) {
-        let is_exceeding = self.estimates_true > self.total_peers()
-            || self.estimates_false > self.total_peers()
-            || self.bin_values_true > self.total_peers()
-            || self.bin_values_false > self.total_peers()
-            || self.aux_values_true > self.total_peers()
-            || self.aux_values_false > self.total_peers();
+        let is_exceeding = self.estimates_true > self.total_weight()
+            || self.estimates_false > self.total_weight()
+            || self.bin_values_true > self.total_weight()
+            || self.bin_values_false > self.total_weight()
+            || self.aux_values_true > self.total_weight()
+            || self.aux_values_false > self.total_weight();
 
         if is_exceeding {
-            log_or_panic!("Having count exceeding total peers {:?}", self);
+            log_or_panic!("Having count exceeding total weight {:?}", self);
         }
     }
 
-    pub fn default_counts(total_peers: NonZeroUsize) -> MetaVoteCounts {
+    pub fn default_counts(
+        total_weight: NonZeroUsize,
+        quorum_policy: QuorumPolicy,
+    ) -> MetaVoteCounts {
         MetaVoteCounts {
             estimates_true: 0,
             estimates_false: 0,
@@ -104,12 +118,13 @@ impl MetaVoteCounts {
             aux_values_true: 0,
             aux_values_false: 0,
             decision: None,
-            total_peers,
+            total_weight,
+            quorum_policy,
         }
     }
 
-    fn total_peers(&self) -> usize {
-        self.total_peers.get()
+    fn total_weight(&self) -> usize {
+        self.total_weight.get()
     }
 }
 
@@ -126,127 +141,127 @@ mod tests {
 
     #[test]
     fn count_estimates() {
-        let total_peers = NonZeroUsize::new(4).unwrap();
+        let total_weight = NonZeroUsize::new(4).unwrap();
 
-        let actual = counts_with_estimates(1, 0, 0, 0, total_peers);
+        let actual = counts_with_estimates(1, 0, 0, 0, total_weight);
         let expected = MetaVoteCounts {
             estimates_true: 0,
             estimates_false: 0,
-            ..MetaVoteCounts::default_counts(total_peers)
+            ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
         };
         assert_eq!(actual, expected);
 
-        let actual = counts_with_estimates(0, 1, 0, 0, total_peers);
+        let actual = counts_with_estimates(0, 1, 0, 0, total_weight);
         let expected = MetaVoteCounts {
             estimates_true: 1,
             estimates_false: 0,
-            ..MetaVoteCounts::default_counts(total_peers)
+            ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
         };
         assert_eq!(actual, expected);
 
-        let actual = counts_with_estimates(0, 0, 1, 0, total_peers);
+        let actual = counts_with_estimates(0, 0, 1, 0, total_weight);
         let expected = MetaVoteCounts {
             estimates_true: 0,
             estimates_false: 1,
-            ..MetaVoteCounts::default_counts(total_peers)
+            ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
         };
         assert_eq!(actual, expected);
 
-        let actual = counts_with_estimates(0, 0, 0, 1, total_peers);
+        let actual = counts_with_estimates(0, 0, 0, 1, total_weight);
         let expected = MetaVoteCounts {
             estimates_true: 1,
             estimates_false: 1,
-            ..MetaVoteCounts::default_counts(total_peers)
+            ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
         };
         assert_eq!(actual, expected);
 
-        let actual = counts_with_estimates(1, 1, 1, 1, total_peers);
+        let actual = counts_with_estimates(1, 1, 1, 1, total_weight);
         let expected = MetaVoteCounts {
             estimates_true: 2,
             estimates_false: 2,
-            ..MetaVoteCounts::default_counts(total_peers)
+            ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
         };
         assert_eq!(actual, expected);
 
-        let actual = counts_with_estimates(1, 2, 3, 1, total_peers);
+        let actual = counts_with_estimates(1, 2, 3, 1, total_weight);
         let expected = MetaVoteCounts {
             estimates_true: 3,
             estimates_false: 4,
-            ..MetaVoteCounts::default_counts(total_peers)
+            ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
         };
         assert_eq!(actual, expected);
     }
 
     #[test]
     fn count_aux_values() {
-        let total_peers = NonZeroUsize::new(4).unwrap();
+        let total_weight = NonZeroUsize::new(4).unwrap();
 
-        let actual = counts_with_aux_values(1, 0, 0, total_peers);
+        let actual = counts_with_aux_values(1, 0, 0, total_weight);
         let expected = MetaVoteCounts {
             aux_values_true: 0,
             aux_values_false: 0,
-            ..MetaVoteCounts::default_counts(total_peers)
+            ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
         };
         assert_eq!(actual, expected);
 
-        let actual = counts_with_aux_values(0, 1, 0, total_peers);
+        let actual = counts_with_aux_values(0, 1, 0, total_weight);
         let expected = MetaVoteCounts {
             aux_values_true: 1,
             aux_values_false: 0,
-            ..MetaVoteCounts::default_counts(total_peers)
+            ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
         };
         assert_eq!(actual, expected);
 
-        let actual = counts_with_aux_values(0, 0, 1, total_peers);
+        let actual = counts_with_aux_values(0, 0, 1, total_weight);
         let expected = MetaVoteCounts {
             aux_values_true: 0,
             aux_values_false: 1,
-            ..MetaVoteCounts::default_counts(total_peers)
+            ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
         };
         assert_eq!(actual, expected);
 
-        let actual = counts_with_aux_values(1, 2, 3, total_peers);
+        let actual = counts_with_aux_values(1, 2, 3, total_weight);
         let expected = MetaVoteCounts {
             aux_values_true: 2,
             aux_values_false: 3,
-            ..MetaVoteCounts::default_counts(total_peers)
+            ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
         };
         assert_eq!(actual, expected);
     }
 
     #[test]
     fn count_decision() {
-        let total_peers = NonZeroUsize::new(4).unwrap();
+        let total_weight = NonZeroUsize::new(4).unwrap();
 
-        let actual = counts_with_decisions(&[None], total_peers);
+        let actual = counts_with_decisions(&[None], total_weight);
         let expected = MetaVoteCounts {
             decision: None,
-            ..MetaVoteCounts::default_counts(total_peers)
+            ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
         };
         assert_eq!(actual, expected);
 
-        let actual = counts_with_decisions(&[Some(false)], total_peers);
+        let actual = counts_with_decisions(&[Some(false)], total_weight);
         let expected = MetaVoteCounts {
             estimates_false: 1,
             bin_values_false: 1,
             aux_values_false: 1,
             decision: Some(false),
-            ..MetaVoteCounts::default_counts(total_peers)
+            ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
         };
         assert_eq!(actual, expected);
 
-        let actual = counts_with_decisions(&[Some(true)], total_peers);
+        let actual = counts_with_decisions(&[Some(true)], total_weight);
         let expected = MetaVoteCounts {
             estimates_true: 1,
             bin_values_true: 1,
             aux_values_true: 1,
             decision: Some(true),
-            ..MetaVoteCounts::default_counts(total_peers)
+            ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
         };
         assert_eq!(actual, expected);
 
         // Only the first non-none decision counts.
-        let actual = counts_with_decisions(&[None, Some(true), Some(false)], total_peers);
+        let actual = counts_with_decisions(&[None, Some(true), Some(false)], total_weight);
         let expected = MetaVoteCounts {
             estimates_true: 1,
             estimates_false: 1,
@@ -255,11 +270,11 @@ mod tests {
             aux_values_true: 1,
             aux_values_false: 1,
             decision: Some(true),
-            ..MetaVoteCounts::default_counts(total_peers)
+            ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
         };
         assert_eq!(actual, expected);
 
-        let actual = counts_with_decisions(&[None, Some(false), Some(true)], total_peers);
+        let actual = counts_with_decisions(&[None, Some(false), Some(true)], total_weight);
         let expected = MetaVoteCounts {
             estimates_true: 1,
             estimates_false: 1,
@@ -268,24 +283,24 @@ mod tests {
             aux_values_true: 1,
             aux_values_false: 1,
             decision: Some(false),
-            ..MetaVoteCounts::default_counts(total_peers)
+            ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
         };
         assert_eq!(actual, expected);
 
-        let actual = counts_with_decisions(&[Some(true), None], total_peers);
+        let actual = counts_with_decisions(&[Some(true), None], total_weight);
         let expected = MetaVoteCounts {
             estimates_true: 1,
             bin_values_true: 1,
             aux_values_true: 1,
             decision: Some(true),
-            ..MetaVoteCounts::default_counts(total_peers)
+            ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
         };
         assert_eq!(actual, expected)
     }
 
     #[test]
     fn only_votes_with_the_same_round_and_step_as_parent_are_counted() {
-        let total_peers = NonZeroUsize::new(4).unwrap();
+        let total_weight = NonZeroUsize::new(4).unwrap();
 
         let parent_vote = MetaVote {
             step: Step::ForcedTrue,
@@ -330,12 +345,14 @@ mod tests {
 
         let actual = MetaVoteCounts::new(
             &parent_vote,
-            &[&[vote0], &[vote1], &[vote2, vote3]],
-            total_peers,
+            1,
+            &[(&[vote0], 1), (&[vote1], 1), (&[vote2, vote3], 1)],
+            total_weight,
+            QuorumPolicy::default(),
         );
         let expected = MetaVoteCounts {
             estimates_true: 2,
-            ..MetaVoteCounts::default_counts(total_peers)
+            ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
         };
         assert_eq!(actual, expected);
     }
@@ -345,7 +362,7 @@ mod tests {
         num_true: usize,
         num_false: usize,
         num_both: usize,
-        total_peers: NonZeroUsize,
+        total_weight: NonZeroUsize,
     ) -> MetaVoteCounts {
         let repeat_votes = |count, estimates| {
             iter::repeat(MetaVote {
@@ -365,14 +382,14 @@ mod tests {
             .chain(repeat_votes(num_both, BoolSet::Both))
             .collect();
 
-        counts_with_votes(&votes, total_peers)
+        counts_with_votes(&votes, total_weight)
     }
 
     fn counts_with_aux_values(
         num_empty: usize,
         num_true: usize,
         num_false: usize,
-        total_peers: NonZeroUsize,
+        total_weight: NonZeroUsize,
     ) -> MetaVoteCounts {
         let repeat_votes = |count, aux_value| {
             iter::repeat(MetaVote {
@@ -391,12 +408,12 @@ mod tests {
             .chain(repeat_votes(num_false, Some(false)))
             .collect();
 
-        counts_with_votes(&votes, total_peers)
+        counts_with_votes(&votes, total_weight)
     }
 
     fn counts_with_decisions(
         decisions: &[Option<bool>],
-        total_peers: NonZeroUsize,
+        total_weight: NonZeroUsize,
     ) -> MetaVoteCounts {
         let votes: Vec<_> = decisions
             .iter()
@@ -419,12 +436,21 @@ mod tests {
                 }
             })
             .collect();
-        counts_with_votes(&votes, total_peers)
+        counts_with_votes(&votes, total_weight)
     }
 
-    fn counts_with_votes(votes: &[MetaVote], total_peers: NonZeroUsize) -> MetaVoteCounts {
+    fn counts_with_votes(votes: &[MetaVote], total_weight: NonZeroUsize) -> MetaVoteCounts {
         let parent_vote = MetaVote::default();
-        let votes: Vec<_> = votes.iter().map(slice::from_ref).collect();
-        MetaVoteCounts::new(&parent_vote, votes.as_slice(), total_peers)
+        let votes: Vec<_> = votes
+            .iter()
+            .map(|vote| (slice::from_ref(vote), 1))
+            .collect();
+        MetaVoteCounts::new(
+            &parent_vote,
+            1,
+            votes.as_slice(),
+            total_weight,
+            QuorumPolicy::default(),
+        )
     }
 }
@@ -7,6 +7,7 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{bool_set::BoolSet, meta_vote_counts::MetaVoteCounts};
+use crate::config::QuorumPolicy;
 use std::fmt::{self, Debug, Formatter};
 use std::num::NonZeroUsize;
 
@@ -40,10 +41,11 @@ impl Estimates {
                 self.0 = BoolSet::Single(toss);
             }
         } else {
-            if counts.is_at_least_one_third(counts.estimates_true) && self.0.insert(true) {
+            if counts.is_more_than_tolerated_faults(counts.estimates_true) && self.0.insert(true) {
                 counts.estimates_true += 1;
             }
-            if counts.is_at_least_one_third(counts.estimates_false) && self.0.insert(false) {
+            if counts.is_more_than_tolerated_faults(counts.estimates_false) && self.0.insert(false)
+            {
                 counts.estimates_false += 1;
             }
         }
@@ -185,38 +187,51 @@ impl MetaVoteValues {
         MetaVoteValues::Undecided(values)
     }
 
-    pub fn count(self, total_peers: NonZeroUsize) -> MetaVoteCounts {
-        // Counts the contribution of these MetaVoteValues
-        let mut counts = MetaVoteCounts::default_counts(total_peers);
+    // Returns whether the estimates were cleared to wait for a coin toss.
+    pub fn is_waiting_for_coin_toss(self) -> bool {
+        match self {
+            MetaVoteValues::Decided(_) => false,
+            MetaVoteValues::Undecided(values) => values.estimates.0.is_empty(),
+        }
+    }
+
+    pub fn count(
+        self,
+        weight: usize,
+        total_weight: NonZeroUsize,
+        quorum_policy: QuorumPolicy,
+    ) -> MetaVoteCounts {
+        // Counts the contribution of these MetaVoteValues, cast by a voter of the given weight
+        let mut counts = MetaVoteCounts::default_counts(total_weight, quorum_policy);
         match self {
             MetaVoteValues::Decided(value) => {
                 counts.decision = Some(value);
                 if value {
-                    counts.estimates_true = 1;
-                    counts.bin_values_true = 1;
-                    counts.aux_values_true = 1;
+                    counts.estimates_true = weight;
+                    counts.bin_values_true = weight;
+                    counts.aux_values_true = weight;
                 } else {
-                    counts.estimates_false = 1;
-                    counts.bin_values_false = 1;
-                    counts.aux_values_false = 1;
+                    counts.estimates_false = weight;
+                    counts.bin_values_false = weight;
+                    counts.aux_values_false = weight;
                 }
             }
             MetaVoteValues::Undecided(values) => {
                 if values.estimates.0.contains(true) {
-                    counts.estimates_true = 1;
+                    counts.estimates_true = weight;
                 }
                 if values.estimates.0.contains(false) {
-                    counts.estimates_false = 1;
+                    counts.estimates_false = weight;
                 }
                 if values.bin_values.0.contains(true) {
-                    counts.bin_values_true = 1;
+                    counts.bin_values_true = weight;
                 }
                 if values.bin_values.0.contains(false) {
-                    counts.bin_values_false = 1;
+                    counts.bin_values_false = weight;
                 }
                 match values.aux_value.0 {
-                    Some(true) => counts.aux_values_true = 1,
-                    Some(false) => counts.aux_values_false = 1,
+                    Some(true) => counts.aux_values_true = weight,
+                    Some(false) => counts.aux_values_false = weight,
                     None => (),
                 }
             }
@@ -441,11 +456,12 @@ mod tests {
     #[test]
     /// Assert counting on initial constructed MetaVoteValues is correct.
     fn meta_vote_value_initial_count() {
-        let total_peers = NonZeroUsize::new(4).unwrap();
+        let total_weight = NonZeroUsize::new(4).unwrap();
         let mvv = MetaVoteValues::from_initial_estimate(true);
-        let mvc = mvv.count(total_peers);
+        let mvc = mvv.count(1, total_weight, QuorumPolicy::default());
 
-        let mut expected_mvc = MetaVoteCounts::default_counts(total_peers);
+        let mut expected_mvc =
+            MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default());
         expected_mvc.estimates_true = 1;
 
         assert_eq!(expected_mvc, mvc);
@@ -454,11 +470,11 @@ mod tests {
     #[test]
     /// Assert calculation of new estimate is correct.
     fn meta_vote_value_caculate_new_estimate() {
-        let total_peers = NonZeroUsize::new(4).unwrap();
+        let total_weight = NonZeroUsize::new(4).unwrap();
         // Default meta_vote_values calculates new estimate from toss coin only.
         {
             let mut mvv = MetaVoteValues::default();
-            let mut counts = MetaVoteCounts::default_counts(total_peers);
+            let mut counts = MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default());
             mvv.calculate_new_estimates(&mut counts, Some(true));
 
             let expected_mvv = MetaVoteValues::Undecided(UndecidedMetaVoteValues {
@@ -478,7 +494,7 @@ mod tests {
                 let mut counts = MetaVoteCounts {
                     estimates_true: 1,
                     estimates_false: 1,
-                    ..MetaVoteCounts::default_counts(total_peers)
+                    ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
                 };
                 let expected_mvv = mvv;
                 mvv.calculate_new_estimates(&mut counts, None);
@@ -493,7 +509,7 @@ mod tests {
                 let mut counts = MetaVoteCounts {
                     estimates_true: 2,
                     estimates_false: 3,
-                    ..MetaVoteCounts::default_counts(total_peers)
+                    ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
                 };
                 mvv.calculate_new_estimates(&mut counts, None);
 
@@ -511,7 +527,7 @@ mod tests {
     #[test]
     /// Assert calculation of new binary value is correct.
     fn meta_vote_value_caculate_new_binary_value() {
-        let total_peers = NonZeroUsize::new(4).unwrap();
+        let total_weight = NonZeroUsize::new(4).unwrap();
         // Default meta_vote_values calculates new binary value from estimates counts.
         {
             let mut mvv = MetaVoteValues::default();
@@ -521,7 +537,7 @@ mod tests {
                 let mut counts = MetaVoteCounts {
                     estimates_true: 2,
                     estimates_false: 2,
-                    ..MetaVoteCounts::default_counts(total_peers)
+                    ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
                 };
                 let expected_mvv = mvv;
                 mvv.calculate_new_bin_values(&mut counts);
@@ -536,7 +552,7 @@ mod tests {
                 let mut counts = MetaVoteCounts {
                     estimates_true: 3,
                     estimates_false: 3,
-                    ..MetaVoteCounts::default_counts(total_peers)
+                    ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
                 };
                 mvv.calculate_new_bin_values(&mut counts);
 
@@ -562,7 +578,7 @@ mod tests {
                 let mut counts = MetaVoteCounts {
                     estimates_true: 2,
                     estimates_false: 2,
-                    ..MetaVoteCounts::default_counts(total_peers)
+                    ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
                 };
                 let expected_mvv = mvv;
                 mvv.calculate_new_bin_values(&mut counts);
@@ -577,7 +593,7 @@ mod tests {
                 let mut counts = MetaVoteCounts {
                     estimates_true: 3,
                     estimates_false: 3,
-                    ..MetaVoteCounts::default_counts(total_peers)
+                    ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
                 };
                 mvv.calculate_new_bin_values(&mut counts);
 
@@ -595,16 +611,17 @@ mod tests {
     #[test]
     /// Assert calculation of new aux value is correct.
     fn meta_vote_value_caculate_new_aux_value() {
-        let total_peers = NonZeroUsize::new(4).unwrap();
+        let total_weight = NonZeroUsize::new(4).unwrap();
         // Default meta_vote_values calculates new aux value.  No matter the status of the previous
         // binary value, The meta_vote_values and the meta_vote_counts shall not be updated.
         {
             let mut mvv = MetaVoteValues::default();
-            let mut counts = MetaVoteCounts::default_counts(total_peers);
+            let mut counts = MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default());
             let expected_mvv = mvv;
             mvv.calculate_new_auxiliary_value(&mut counts, BinValues::default());
 
-            let expected_counts = MetaVoteCounts::default_counts(total_peers);
+            let expected_counts =
+                MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default());
             assert_eq!(mvv, expected_mvv);
             assert_eq!(counts, expected_counts);
 
@@ -622,18 +639,21 @@ mod tests {
 
             // When the previous binary value is non-empty, nothing shall be updated.
             {
-                let mut counts = MetaVoteCounts::default_counts(total_peers);
+                let mut counts =
+                    MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default());
                 let expected_mvv = mvv;
                 mvv.calculate_new_auxiliary_value(&mut counts, BinValues(BoolSet::Single(false)));
 
-                let expected_counts = MetaVoteCounts::default_counts(total_peers);
+                let expected_counts =
+                    MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default());
                 assert_eq!(mvv, expected_mvv);
                 assert_eq!(counts, expected_counts);
             }
 
             // When the previous binary value is empty.
             {
-                let mut counts = MetaVoteCounts::default_counts(total_peers);
+                let mut counts =
+                    MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default());
                 mvv.calculate_new_auxiliary_value(&mut counts, BinValues(BoolSet::Empty));
 
                 let expected_mvv = MetaVoteValues::Undecided(UndecidedMetaVoteValues {
@@ -643,7 +663,7 @@ mod tests {
                 });
                 let expected_counts = MetaVoteCounts {
                     aux_values_false: 1,
-                    ..MetaVoteCounts::default_counts(total_peers)
+                    ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
                 };
                 assert_eq!(mvv, expected_mvv);
                 assert_eq!(counts, expected_counts);
@@ -656,7 +676,7 @@ mod tests {
                 bin_values: BinValues(BoolSet::Both),
                 ..Default::default()
             });
-            let mut counts = MetaVoteCounts::default_counts(total_peers);
+            let mut counts = MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default());
             mvv.calculate_new_auxiliary_value(&mut counts, BinValues(BoolSet::Empty));
 
             let expected_mvv = MetaVoteValues::Undecided(UndecidedMetaVoteValues {
@@ -666,7 +686,7 @@ mod tests {
             });
             let expected_counts = MetaVoteCounts {
                 aux_values_true: 1,
-                ..MetaVoteCounts::default_counts(total_peers)
+                ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
             };
             assert_eq!(mvv, expected_mvv);
             assert_eq!(counts, expected_counts);
@@ -676,13 +696,13 @@ mod tests {
     #[test]
     /// Assert calculation of new decision is correct.
     fn meta_vote_value_caculate_new_decision() {
-        let total_peers = NonZeroUsize::new(4).unwrap();
+        let total_weight = NonZeroUsize::new(4).unwrap();
         // Decision deduced from current binary value and counts' aux_value.
         {
             let counts = MetaVoteCounts {
                 aux_values_true: 3,
                 aux_values_false: 3,
-                ..MetaVoteCounts::default_counts(total_peers)
+                ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
             };
             // Decided on ForcedTrue step.
             let mut mvv = MetaVoteValues::Undecided(UndecidedMetaVoteValues {
@@ -709,14 +729,14 @@ mod tests {
 
         // Decision deduced from counts' decision
         {
-            let counts = MetaVoteCounts::default_counts(total_peers);
+            let counts = MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default());
             let mut mvv = MetaVoteValues::default();
             mvv.calculate_new_decision(&counts, Step::GenuineFlip);
             assert_eq!(mvv, MetaVoteValues::default());
 
             let counts = MetaVoteCounts {
                 decision: Some(false),
-                ..MetaVoteCounts::default_counts(total_peers)
+                ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
             };
             mvv.calculate_new_decision(&counts, Step::GenuineFlip);
             assert_eq!(mvv, MetaVoteValues::Decided(false));
@@ -726,16 +746,16 @@ mod tests {
     #[test]
     /// Assert updating during increasing step is correct.
     fn meta_vote_value_increase_step() {
-        let total_peers = NonZeroUsize::new(4).unwrap();
+        let total_weight = NonZeroUsize::new(4).unwrap();
         let supermajority_counts = MetaVoteCounts {
             aux_values_true: 3,
             aux_values_false: 3,
-            ..MetaVoteCounts::default_counts(total_peers)
+            ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
         };
         let less_supermajority_counts = MetaVoteCounts {
             aux_values_true: 2,
             aux_values_false: 2,
-            ..MetaVoteCounts::default_counts(total_peers)
+            ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
         };
         let expected_mvv_true = MetaVoteValues::Undecided(UndecidedMetaVoteValues {
             estimates: Estimates(BoolSet::Single(true)),
@@ -799,7 +819,7 @@ mod tests {
             let counts = MetaVoteCounts {
                 aux_values_true: 2,
                 aux_values_false: 3,
-                ..MetaVoteCounts::default_counts(total_peers)
+                ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
             };
             mvv.increase_step(&counts, None, Step::GenuineFlip);
             assert_eq!(mvv, expected_mvv_false);
@@ -816,13 +836,13 @@ mod tests {
     #[test]
     /// Assert meta_vote_value can be updated correctly.
     fn meta_vote_value_update() {
-        let total_peers = NonZeroUsize::new(4).unwrap();
+        let total_weight = NonZeroUsize::new(4).unwrap();
         // Updated to decided whenever counts contains decision.
         {
             let mut mvv = MetaVoteValues::default();
             let counts = MetaVoteCounts {
                 decision: Some(true),
-                ..MetaVoteCounts::default_counts(total_peers)
+                ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
             };
             mvv.update(counts, Some(false), Step::GenuineFlip);
             assert_eq!(mvv, MetaVoteValues::Decided(true));
@@ -836,7 +856,7 @@ mod tests {
                 estimates_false: 3,
                 bin_values_false: 3,
                 aux_values_false: 3,
-                ..MetaVoteCounts::default_counts(total_peers)
+                ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
             };
             mvv.update(counts, None, Step::ForcedFalse);
             assert_eq!(mvv, MetaVoteValues::Decided(false));
@@ -851,7 +871,7 @@ mod tests {
                 estimates_true: 2,
                 bin_values_true: 3,
                 aux_values_true: 3,
-                ..MetaVoteCounts::default_counts(total_peers)
+                ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
             };
             mvv.update(counts, None, Step::ForcedTrue);
             assert_eq!(mvv, MetaVoteValues::Decided(true));
@@ -866,7 +886,7 @@ mod tests {
                 estimates_false: 3,
                 bin_values_false: 3,
                 aux_values_false: 3,
-                ..MetaVoteCounts::default_counts(total_peers)
+                ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
             };
 
             let expected_mvv = MetaVoteValues::Undecided(UndecidedMetaVoteValues {
@@ -888,7 +908,7 @@ mod tests {
                 estimates_false: 3,
                 bin_values_false: 3,
                 aux_values_false: 2,
-                ..MetaVoteCounts::default_counts(total_peers)
+                ..MetaVoteCounts::default_counts(total_weight, QuorumPolicy::default())
             };
             mvv.update(counts, None, Step::ForcedTrue);
 
@@ -7,12 +7,14 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 mod bool_set;
+mod common_coin;
 mod meta_election;
 mod meta_event;
 mod meta_vote;
 mod meta_vote_counts;
 mod meta_vote_values;
 
+pub use self::common_coin::{CoinId, CoinShare};
 #[cfg(any(all(test, feature = "mock"), feature = "dump-graphs"))]
 pub(crate) use self::meta_election::snapshot::MetaElectionSnapshot;
 #[cfg(any(test, feature = "testing"))]
@@ -20,6 +22,7 @@ pub(crate) use self::meta_election::UnconsensusedEvents;
 #[cfg(any(test, feature = "testing"))]
 pub(crate) use self::{bool_set::BoolSet, meta_vote_values::Step};
 pub(crate) use self::{
+    common_coin::{CoinKeys, CoinShares},
     meta_election::MetaElection,
     meta_event::{MetaEvent, MetaEventBuilder, Observer},
     meta_vote::MetaVote,
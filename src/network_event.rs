@@ -15,3 +15,14 @@ pub trait NetworkEvent:
     Clone + Eq + Ord + PartialEq + PartialOrd + Serialize + DeserializeOwned + Debug
 {
 }
+
+/// Check of the application-specific payloads, which `Parsec` consults before voting for an
+/// `Observation::OpaquePayload` and before counting a gossiped vote for one. See
+/// [Parsec::set_payload_validator](struct.Parsec.html#method.set_payload_validator).
+///
+/// All the peers should use the same validator: the events carrying a vote for an invalid payload
+/// are kept in the gossip graph, but the vote is ignored by the peers deeming it invalid.
+pub trait PayloadValidator<T: NetworkEvent> {
+    /// Returns whether `payload` is valid.
+    fn is_valid(&self, payload: &T) -> bool;
+}
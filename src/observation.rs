@@ -7,10 +7,13 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
-    gossip::{EventHash, PackedEvent},
+    block::BlockSignatureShare,
+    evidence::{verify_evidence_with_genesis, Evidence},
+    gossip::{EventContextRef, EventHash, IndexedEventRef, PackedEvent},
     hash::Hash,
     id::{PublicId, SecretId},
     key_gen::message::DkgMessage,
+    meta_voting::CoinShare,
     network_event::NetworkEvent,
     peer_list::{Peer, PeerIndex, PeerList},
     serialise, DkgResultWrapper,
@@ -50,6 +53,38 @@ pub enum Observation<T: NetworkEvent, P: PublicId> {
         /// Extra arbitrary information for use by the client
         related_info: Vec<u8>,
     },
+    /// Vote to add and remove several peers at once. Once consensused, the whole change is applied
+    /// together, so no intermediate set of voters is ever used.
+    Reconfigure {
+        /// Public ids of the peers to be added
+        add: BTreeSet<P>,
+        /// Public ids of the peers to be removed
+        remove: BTreeSet<P>,
+        /// Extra arbitrary information for use by the client
+        related_info: Vec<u8>,
+    },
+    /// Vote to let a member switch to a new key without leaving the section. Once consensused, the
+    /// member is known by `new`, and its events from then on are signed with it.
+    RotateKey {
+        /// Public id the member is currently known by
+        old: P,
+        /// Public id the member switches to
+        new: P,
+        /// Proof that the owners of both keys agree to the rotation
+        proof: KeyRotationProof<P>,
+    },
+    /// Vote to change how much the indicated voter's votes count for. Every voter starts with a
+    /// weight of one, and agreement is reached once the agreeing voters' weights add up to a
+    /// quorum of the total weight of the voters.
+    ///
+    /// The weights don't apply to distributed key generation: each participant holds a single
+    /// key share, so the threshold of the generated keys is a number of participants.
+    ChangeWeight {
+        /// Public id of the voter whose weight changes
+        peer_id: P,
+        /// The new weight, from one to [`MAX_WEIGHT`](constant.MAX_WEIGHT.html)
+        weight: usize,
+    },
     /// Output only: Do not vote for it.
     /// Vote to accuse a peer of malicious behaviour.
     Accusation {
@@ -78,6 +113,14 @@ pub enum Observation<T: NetworkEvent, P: PublicId> {
     /// Vote for the next message (Part or Ack) to be handled for the Distributed Key Generation
     /// algorithm used by our common coin.
     DkgMessage(DkgMessage),
+    /// Internal only: Do not vote for it or expect it to come in blocks.
+    /// Our share of a toss of the common coin. Never consensused, the shares are read directly
+    /// from the gossip graph.
+    CoinShare(CoinShare<P>),
+    /// Internal only: Do not vote for it or expect it to come in blocks.
+    /// Our share of the threshold signature of a consensused block. Never consensused, the shares
+    /// are read directly from the gossip graph.
+    BlockSignatureShare(BlockSignatureShare),
 }
 
 impl<T: NetworkEvent, P: PublicId> Observation<T, P> {
@@ -98,10 +141,29 @@ impl<T: NetworkEvent, P: PublicId> Observation<T, P> {
         }
     }
 
+    /// Is this observation an internal `CoinShare`
+    pub fn is_coin_share(&self) -> bool {
+        match *self {
+            Observation::CoinShare(_) => true,
+            _ => false,
+        }
+    }
+
     /// Is this observation an internal and should not be published in a `Block`
     pub fn is_internal(&self) -> bool {
         match *self {
-            Observation::DkgMessage(_) | Observation::StartDkg(_) => true,
+            Observation::DkgMessage(_)
+            | Observation::StartDkg(_)
+            | Observation::CoinShare(_)
+            | Observation::BlockSignatureShare(_) => true,
+            _ => false,
+        }
+    }
+
+    // Is this observation a share of a threshold signature, which is never consensused.
+    pub(crate) fn is_signature_share(&self) -> bool {
+        match *self {
+            Observation::CoinShare(_) | Observation::BlockSignatureShare(_) => true,
             _ => false,
         }
     }
@@ -121,6 +183,15 @@ impl<T: NetworkEvent, P: PublicId> Debug for Observation<T, P> {
             Observation::Genesis { group, .. } => write!(formatter, "Genesis({:?})", group),
             Observation::Add { peer_id, .. } => write!(formatter, "Add({:?})", peer_id),
             Observation::Remove { peer_id, .. } => write!(formatter, "Remove({:?})", peer_id),
+            Observation::Reconfigure { add, remove, .. } => {
+                write!(formatter, "Reconfigure({:?}, {:?})", add, remove)
+            }
+            Observation::RotateKey { old, new, .. } => {
+                write!(formatter, "RotateKey({:?}, {:?})", old, new)
+            }
+            Observation::ChangeWeight { peer_id, weight } => {
+                write!(formatter, "ChangeWeight({:?}, {})", peer_id, weight)
+            }
             Observation::Accusation { offender, malice } => {
                 write!(formatter, "Accusation {{ {:?}, {:?} }}", offender, malice)
             }
@@ -130,6 +201,10 @@ impl<T: NetworkEvent, P: PublicId> Debug for Observation<T, P> {
                 dkg_result,
             } => write!(formatter, "({:?}, {:?})", participants, dkg_result),
             Observation::DkgMessage(msg) => write!(formatter, "{:?}", msg),
+            Observation::CoinShare(coin_share) => write!(formatter, "{:?}", coin_share.coin_id),
+            Observation::BlockSignatureShare(share) => {
+                write!(formatter, "BlockSignatureShare({:?})", share.payload_hash)
+            }
             Observation::OpaquePayload(payload) => {
                 write!(formatter, "OpaquePayload({:?})", payload)
             }
@@ -137,6 +212,36 @@ impl<T: NetworkEvent, P: PublicId> Debug for Observation<T, P> {
     }
 }
 
+/// Signatures of a key rotation by both the old and the new key. See `Observation::RotateKey`.
+#[serde(bound = "")]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Debug)]
+pub struct KeyRotationProof<P: PublicId> {
+    old_signature: P::Signature,
+    new_signature: P::Signature,
+}
+
+impl<P: PublicId> KeyRotationProof<P> {
+    /// Creates the proof of rotating from `old_id` to `new_id`.
+    pub fn new<S: SecretId<PublicId = P>>(old_id: &S, new_id: &S) -> Self {
+        let data = Self::signed_content(old_id.public_id(), new_id.public_id());
+        Self {
+            old_signature: old_id.sign_detached(&data),
+            new_signature: new_id.sign_detached(&data),
+        }
+    }
+
+    /// Returns whether this is a valid proof of rotating from `old` to `new`.
+    pub fn is_valid(&self, old: &P, new: &P) -> bool {
+        let data = Self::signed_content(old, new);
+        old.verify_signature(&self.old_signature, &data)
+            && new.verify_signature(&self.new_signature, &data)
+    }
+
+    fn signed_content(old: &P, new: &P) -> Vec<u8> {
+        serialise(&(old, new))
+    }
+}
+
 /// Type of malicious behaviour.
 #[serde(bound = "")]
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Debug)]
@@ -171,6 +276,37 @@ pub enum Malice<T: NetworkEvent, P: PublicId> {
     Accomplice(EventHash, Box<Malice<T, P>>),
 }
 
+/// Kind of malicious behaviour, without the evidence. Used to choose which kinds of malice are
+/// detected; see [ParsecConfig::with_detected_malice](
+/// struct.ParsecConfig.html#method.with_detected_malice).
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+pub enum MaliceKind {
+    /// See `Malice::UnexpectedGenesis`.
+    UnexpectedGenesis,
+    /// See `Malice::DuplicateVote`.
+    DuplicateVote,
+    /// See `Malice::MissingGenesis`.
+    MissingGenesis,
+    /// See `Malice::IncorrectGenesis`.
+    IncorrectGenesis,
+    /// See `Malice::Fork`.
+    Fork,
+    /// See `Malice::InvalidAccusation`.
+    InvalidAccusation,
+    /// See `Malice::OtherParentBySameCreator`.
+    OtherParentBySameCreator,
+    /// See `Malice::SelfParentByDifferentCreator`.
+    SelfParentByDifferentCreator,
+    /// See `Malice::InvalidRequest`.
+    InvalidRequest,
+    /// See `Malice::InvalidResponse`.
+    InvalidResponse,
+    /// See `Malice::Unprovable`.
+    Unprovable,
+    /// See `Malice::Accomplice`.
+    Accomplice,
+}
+
 #[cfg(any(test, feature = "testing"))]
 #[derive(Debug)]
 pub(crate) enum MaliceInput {
@@ -178,8 +314,88 @@ pub(crate) enum MaliceInput {
     InvalidAccusation(String),
 }
 
-#[cfg(feature = "malice-detection")]
 impl<T: NetworkEvent, P: PublicId> Malice<T, P> {
+    /// Returns the kind of this malice.
+    pub fn kind(&self) -> MaliceKind {
+        match *self {
+            Malice::UnexpectedGenesis(_) => MaliceKind::UnexpectedGenesis,
+            Malice::DuplicateVote(_, _) => MaliceKind::DuplicateVote,
+            Malice::MissingGenesis(_) => MaliceKind::MissingGenesis,
+            Malice::IncorrectGenesis(_) => MaliceKind::IncorrectGenesis,
+            Malice::Fork(_) => MaliceKind::Fork,
+            Malice::InvalidAccusation(_) => MaliceKind::InvalidAccusation,
+            Malice::OtherParentBySameCreator(_) => MaliceKind::OtherParentBySameCreator,
+            Malice::SelfParentByDifferentCreator(_) => MaliceKind::SelfParentByDifferentCreator,
+            Malice::InvalidRequest(_) => MaliceKind::InvalidRequest,
+            Malice::InvalidResponse(_) => MaliceKind::InvalidResponse,
+            Malice::Unprovable(_) => MaliceKind::Unprovable,
+            Malice::Accomplice(_, _) => MaliceKind::Accomplice,
+        }
+    }
+
+    // Returns the evidence of this malice made of the relevant events from the gossip graph, if
+    // they are there and prove it to a verifier knowing the given genesis group.
+    pub(crate) fn evidence<S: SecretId<PublicId = P>>(
+        &self,
+        context: EventContextRef<T, S>,
+        genesis_group: &BTreeSet<P>,
+    ) -> Option<Evidence<T, P>> {
+        let evidence = Evidence::new(self.clone(), self.evidence_events(context)?);
+        let offender = evidence.offender()?.clone();
+        if verify_evidence_with_genesis(&evidence, &offender, genesis_group) {
+            Some(evidence)
+        } else {
+            None
+        }
+    }
+
+    fn evidence_events<S: SecretId<PublicId = P>>(
+        &self,
+        context: EventContextRef<T, S>,
+    ) -> Option<Vec<PackedEvent<T, P>>> {
+        let pack = |event: IndexedEventRef<P>| event.pack(context).ok();
+        let pack_by_hash = |hash: Option<&EventHash>| {
+            hash.and_then(|hash| context.graph.get_by_hash(hash))
+                .and_then(pack)
+        };
+
+        match *self {
+            Malice::Fork(ref self_parent_hash) => {
+                let self_parent = context.graph.get_by_hash(self_parent_hash)?;
+                let self_parent_index = self_parent.event_index();
+                context
+                    .peer_list
+                    .events_by_index(self_parent.creator(), self_parent.index_by_creator() + 1)
+                    .filter_map(|index| context.graph.get(index))
+                    .filter(|event| event.self_parent() == Some(self_parent_index))
+                    .take(2)
+                    .map(pack)
+                    .collect()
+            }
+            Malice::DuplicateVote(ref first_hash, ref second_hash) => Some(vec![
+                pack_by_hash(Some(first_hash))?,
+                pack_by_hash(Some(second_hash))?,
+            ]),
+            Malice::UnexpectedGenesis(ref hash) => {
+                let event = context.graph.get_by_hash(hash)?;
+                let self_parent = context.graph.self_parent(event)?;
+                Some(vec![pack(event)?, pack(self_parent)?])
+            }
+            Malice::OtherParentBySameCreator(ref event) | Malice::InvalidRequest(ref event) => {
+                Some(vec![(**event).clone(), pack_by_hash(event.other_parent())?])
+            }
+            Malice::SelfParentByDifferentCreator(ref event) => {
+                Some(vec![(**event).clone(), pack_by_hash(event.self_parent())?])
+            }
+            Malice::MissingGenesis(_)
+            | Malice::IncorrectGenesis(_)
+            | Malice::InvalidAccusation(_)
+            | Malice::InvalidResponse(_)
+            | Malice::Unprovable(_)
+            | Malice::Accomplice(..) => None,
+        }
+    }
+
     pub(crate) fn is_provable(&self) -> bool {
         match *self {
             Malice::Unprovable(_) => false,
@@ -223,12 +439,17 @@ impl<T: NetworkEvent, P: PublicId> Malice<T, P> {
     }
 }
 
-// For internal diagnostics only. The value is ignored in comparison, ordering or hashing.
+/// Kind of malice which can't be proven to other peers. For internal diagnostics only: the value is
+/// ignored in comparison, ordering or hashing, and isn't serialised.
 #[derive(Clone, Debug)]
 pub enum UnprovableMalice {
-    // A node is spamming us.
+    /// A node is spamming us.
     Spam,
-    // Other, unspecified malice.
+    /// A node doesn't answer our gossip requests.
+    Unresponsive,
+    /// A node keeps gossiping votes for payloads our `PayloadValidator` deems invalid.
+    InvalidPayload,
+    /// Other, unspecified malice.
     Unspecified,
 }
 
@@ -298,7 +519,8 @@ impl Debug for ObservationHash {
 }
 
 // Container for observation with its metadata.
-#[derive(Debug)]
+#[serde(bound = "")]
+#[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct ObservationInfo<T: NetworkEvent, P: PublicId> {
     pub(crate) observation: Observation<T, P>,
     pub(crate) consensused: bool,
@@ -322,7 +544,7 @@ pub(crate) type ObservationStore<T, P> = BTreeMap<ObservationKey, ObservationInf
 pub(crate) type ObservationForStore<T, P> = Option<(ObservationKey, ObservationInfo<T, P>)>;
 
 // Key to compare observations.
-#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
 pub(crate) enum ObservationKey {
     Single(ObservationHash, PeerIndex),
     Supermajority(ObservationHash),
@@ -383,11 +605,12 @@ impl ObservationKey {
 }
 
 /// Number of votes necessary to reach consensus on an `OpaquePayload`.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum ConsensusMode {
     /// One vote is enough.
     Single,
-    /// Supermajority (more than 2/3) is required.
+    /// Supermajority (more than 2/3 by default, see [`QuorumPolicy`](struct.QuorumPolicy.html)) is
+    /// required.
     Supermajority,
 }
 
@@ -403,9 +626,12 @@ impl ConsensusMode {
     }
 }
 
-/// Returns whether `small` is more than two thirds of `large`.
-pub fn is_more_than_two_thirds(small: usize, large: usize) -> bool {
-    3 * small > 2 * large
+/// The largest weight `Observation::ChangeWeight` can give a voter. It keeps the total weight of
+/// a section, and the quorum computations on it, from overflowing.
+pub const MAX_WEIGHT: usize = 1 << 16;
+
+pub(crate) fn is_valid_weight(weight: usize) -> bool {
+    weight > 0 && weight <= MAX_WEIGHT
 }
 
 #[cfg(any(all(test, feature = "mock"), feature = "dump-graphs"))]
@@ -8,43 +8,58 @@
 
 #[cfg(all(test, feature = "mock"))]
 use crate::dev_utils::ParsedContents;
-#[cfg(all(test, feature = "malice-detection", feature = "mock"))]
+#[cfg(all(test, feature = "mock"))]
 use crate::gossip::EventHash;
 #[cfg(all(test, any(feature = "testing", feature = "mock")))]
 use crate::gossip::GraphSnapshot;
-#[cfg(feature = "malice-detection")]
-use crate::observation::Malice;
+#[cfg(any(feature = "testing", all(test, feature = "mock")))]
+use crate::mock::{PeerId, Transaction};
 use crate::{
-    block::{Block, BlockGroup},
+    block::{
+        Block, BlockGroup, BlockHash, BlockSignatureShare, BlockSink, ChainLink, ConsensusBatch,
+    },
+    config::ParsecConfig,
     dump_graph,
     error::{Error, Result},
+    evidence::Evidence,
     gossip::{
-        Event, EventContextRef, EventIndex, Graph, IndexedEventRef, PackedEvent, Request, Response,
+        Capabilities, Event, EventContextRef, EventIndex, Graph, GraphStorage, IndexedEventRef,
+        PackedEvent, Request, Response, MIN_PROTOCOL_VERSION, SPLIT_SYNC_VERSION,
     },
+    hash::Hash,
     id::{PublicId, SecretId},
     key_gen::{
-        dkg_threshold, message::DkgMessage, parsec_rng::ParsecRng, Ack, AckOutcome, KeyGen, Part,
-        PartOutcome,
+        dkg_status::{DkgFailure, DkgStatus},
+        message::DkgMessage,
+        parsec_rng::ParsecRng,
+        Ack, AckOutcome, KeyGen, Part, PartOutcome,
     },
-    meta_voting::{MetaElection, MetaEvent, MetaEventBuilder, MetaVote, Observer},
-    network_event::NetworkEvent,
+    meta_voting::{
+        CoinId, CoinKeys, CoinShare, CoinShares, MetaElection, MetaEvent, MetaEventBuilder,
+        MetaVote, Observer,
+    },
+    network_event::{NetworkEvent, PayloadValidator},
     observation::{
-        is_more_than_two_thirds, ConsensusMode, Observation, ObservationHash, ObservationKey,
-        ObservationStore,
+        is_valid_weight, ConsensusMode, KeyRotationProof, Malice, MaliceKind, Observation,
+        ObservationHash, ObservationInfo, ObservationKey, ObservationStore, UnprovableMalice,
     },
     parsec_helpers::find_interesting_content_for_event,
-    peer_list::{Peer, PeerIndex, PeerIndexMap, PeerIndexSet, PeerList, PeerListChange, PeerState},
-};
-#[cfg(any(feature = "testing", all(test, feature = "mock")))]
-use crate::{
-    hash::Hash,
-    mock::{PeerId, Transaction},
+    peer_list::{
+        Peer, PeerIndex, PeerIndexMap, PeerIndexSet, PeerList, PeerListChange, PeerState,
+        SavedPeerList,
+    },
+    section_summary::{self, CatchUp, SectionSummary, SignedSectionSummary},
+    serialise,
 };
 use itertools::Itertools;
+use log::LogLevel;
+use maidsafe_utilities::serialisation;
+use serde::de::DeserializeOwned;
 #[cfg(any(test, feature = "testing"))]
 use std::ops::{Deref, DerefMut};
 use std::{
     collections::{BTreeMap, BTreeSet, VecDeque},
+    io::Cursor,
     iter,
     marker::PhantomData,
     mem,
@@ -52,7 +67,18 @@ use std::{
     usize,
 };
 
-pub(crate) type KeyGenId = usize;
+// Logs at the given level, unless it is more verbose than the log level configured for `$parsec`.
+macro_rules! log_at {
+    ($parsec:expr, $level:expr, $($arg:tt)+) => {
+        if $level <= $parsec.config.log_level() {
+            log!($level, $($arg)+);
+        }
+    };
+}
+
+/// Identifier of a distributed key generation session. Sessions are numbered in the order their
+/// `StartDkg` observations get consensused, so the identifiers are the same on all peers.
+pub type KeyGenId = usize;
 
 /// The main object which manages creating and receiving gossip about network events from peers, and
 /// which provides a sequence of consensused [Block](struct.Block.html)s by applying the PARSEC
@@ -91,30 +117,97 @@ pub(crate) type KeyGenId = usize;
 /// Most public methods return an error if called after the owning peer has been removed from the
 /// section, i.e. a block with payload `Observation::Remove(our_id)` has been made stable.
 ///
+/// The common coin deciding the meta-election is tossed with threshold signatures of the voters,
+/// using the keys generated once an `Observation::StartDkg` of exactly the current voters is
+/// consensused. Until then, including after any change of the voters until the next such DKG, the
+/// coin falls back to alternating with the round. That coin is common, but predictable, so liveness
+/// isn't guaranteed against an adversary controlling the delivery of the gossip messages.
+///
 /// For more details, see the descriptions of methods below.
 pub struct Parsec<T: NetworkEvent, S: SecretId> {
     // The PeerInfo of other nodes.
     peer_list: PeerList<S>,
+    // The peers present at the startup of the section.
+    genesis_group: BTreeSet<S::PublicId>,
+    // Our next secret ID, held until our vote to rotate to it is consensused.
+    pending_key_rotation: Option<S>,
+    // Public ID of the secret ID we voted to rotate to before being saved and restored, until it's
+    // passed to `rotate_key` again.
+    restored_key_rotation: Option<S::PublicId>,
     // Set of active distributed key generation, with a KeyGenId used by `DkgMessage`.
     key_gen: BTreeMap<KeyGenId, KeyGen<S>>,
     // Next KeyGenId
     key_gen_next_id: KeyGenId,
+    // Distributed key generations which failed or were cancelled, with the reason.
+    failed_key_gens: BTreeMap<KeyGenId, DkgFailure>,
     // The Gossip graph.
     graph: Graph<S::PublicId>,
     // Information about observations stored in the graph, mapped to their hashes.
     observations: ObservationStore<T, S::PublicId>,
+    // Keys of the consensused observations whose information was dropped by `prune`, mapped to
+    // whether they were voted for by us.
+    pruned_observations: BTreeMap<ObservationKey, bool>,
     // Consensused network events that have not been returned via `poll()` yet.
     consensused_blocks: VecDeque<BlockGroup<T, S::PublicId>>,
+    // Receiver of the consensused blocks, if registered. If so, the blocks are handed to it
+    // instead of being queued in `consensused_blocks`.
+    block_sink: Option<Box<dyn BlockSink<T, S::PublicId>>>,
+    // Check of the opaque payloads, if registered.
+    payload_validator: Option<Box<dyn PayloadValidator<T>>>,
     // The map of meta votes of the events on each consensus block.
     meta_election: MetaElection,
-    consensus_mode: ConsensusMode,
+    config: ParsecConfig,
     // Dkg messages to raise at the end of processing of current gossip message.
     pending_dkg_msgs: Vec<DkgMessage>,
+    // Keys of the common coin, from the last DKG among exactly the voters at the time.
+    coin_keys: Option<CoinKeys<S::PublicId>>,
+    // Common coin shares carried by the events in the gossip graph.
+    coin_shares: CoinShares<S::PublicId>,
+    // Our common coin shares to raise at the end of processing of current gossip message.
+    pending_coin_shares: Vec<CoinShare<S::PublicId>>,
+    // Consensused blocks held back until their threshold signature can be combined.
+    unsigned_blocks: VecDeque<BlockGroup<T, S::PublicId>>,
+    // Signature shares of the blocks not output yet, with their creators, by block index.
+    block_signature_shares: BTreeMap<u64, Vec<(S::PublicId, BlockSignatureShare)>>,
+    // Index of the next block to reach consensus, which the signature of the block covers.
+    next_block_index: u64,
+    // Number of our sync events since the first blocks in `unsigned_blocks` were held back.
+    block_signature_wait: usize,
+    // Our block signature shares to raise at the end of processing of current gossip message.
+    pending_block_signature_shares: Vec<BlockSignatureShare>,
+    // Hash of the last block to reach consensus, if the blocks are chained.
+    chain_tip: Option<BlockHash>,
+    // Summary of the section we're joining, until we've been caught up with the section from the
+    // meta-election it was taken at.
+    section_summary: Option<SectionSummary<S::PublicId>>,
     // Accusations to raise at the end of the processing of current gossip message.
-    #[cfg(feature = "malice-detection")]
     pending_accusations: Accusations<T, S::PublicId>,
+    // Numbers of accusations which reached consensus, by offender and kind of malice.
+    consensused_accusations: BTreeMap<(S::PublicId, MaliceKind), usize>,
+    // Removals to vote for at the end of the processing of current gossip message.
+    pending_removals: Vec<Observation<T, S::PublicId>>,
     // Events to be inserted into the gossip graph when this node becomes voter.
     pending_events: Vec<PendingEvent<T, S::PublicId>>,
+    // Events of our requests which didn't fit into the messages sent so far, mapped to recipients.
+    pending_requests: BTreeMap<PeerIndex, VecDeque<EventIndex>>,
+    // Events of our responses which didn't fit into the messages sent so far, mapped to recipients.
+    pending_responses: BTreeMap<PeerIndex, VecDeque<EventIndex>>,
+    // Peers which sent us only part of their response so far.
+    awaiting_responses: BTreeSet<PeerIndex>,
+    // Gossip protocol versions agreed on with peers.
+    peer_versions: BTreeMap<PeerIndex, u32>,
+    // Counts of what each peer has sent us, to detect spam.
+    spam_stats: BTreeMap<PeerIndex, SpamStats>,
+    // Number of our sync events since `spam_stats` was last cleared.
+    spam_window_syncs: usize,
+    // Numbers of our sync events since the oldest of our requests each peer hasn't answered yet,
+    // halved whenever the peer gossips to us.
+    unanswered_requests: BTreeMap<PeerIndex, usize>,
+    // Numbers of events carrying votes for invalid payloads each peer gossiped to us.
+    invalid_payloads: BTreeMap<PeerIndex, usize>,
+    // Peers we've accused of unprovable malice. As all unprovable malice is alike, accusing a peer
+    // of it twice would be a duplicate vote.
+    unprovably_accused: BTreeSet<PeerIndex>,
     // True to disable processing consensus on this instance to speed up processing for irrelevant
     // parsec instances.
     #[cfg(any(test, feature = "testing"))]
@@ -131,20 +224,25 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
     /// startup.
     /// * `genesis_related_info` extra arbitrary information attached to the genesis event for use
     /// by the client.
-    /// * `consensus_mode` determines how many votes are needed for an observation to become a
-    /// candidate for consensus. For more details, see [ConsensusMode](enum.ConsensusMode.html)
+    /// * `config` holds the settings of this instance, including how many votes are needed for an
+    /// observation to become a candidate for consensus. For more details, see
+    /// [ParsecConfig](struct.ParsecConfig.html)
     /// * `secure_rng` cryptographically secure RNG to use for DKG key generation.
     pub fn from_genesis(
         our_id: S,
         genesis_group: &BTreeSet<S::PublicId>,
         genesis_related_info: Vec<u8>,
-        consensus_mode: ConsensusMode,
+        config: ParsecConfig,
         secure_rng: Box<dyn rand::Rng>,
     ) -> Self {
         if !genesis_group.contains(our_id.public_id()) {
             log_or_panic!("Genesis group must contain us");
         }
 
+        if !config.quorum_policy().is_valid_for(genesis_group.len()) {
+            log_or_panic!("Quorum policy must tolerate a faulty voter of the genesis group");
+        }
+
         let mut peer_list = PeerList::new(our_id);
         let genesis_indices: PeerIndexSet = genesis_group
             .iter()
@@ -159,19 +257,25 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             })
             .collect();
 
-        let mut parsec = Self::empty(peer_list, genesis_indices, consensus_mode, secure_rng);
-
-        // Add initial event.
-        parsec.add_initial_event();
+        let mut parsec = Self::empty(
+            peer_list,
+            genesis_group,
+            genesis_indices,
+            config,
+            secure_rng,
+        );
 
-        // Add event carrying genesis observation.
+        // Add initial event, then event carrying genesis observation.
         let genesis_observation = Observation::Genesis {
             group: genesis_group.clone(),
             related_info: genesis_related_info,
         };
-        let event = parsec.our_last_event_index().and_then(|self_parent| {
-            parsec.new_event_from_observation(self_parent, genesis_observation)
-        });
+        let event = parsec
+            .add_initial_event()
+            .and_then(|()| parsec.our_last_event_index())
+            .and_then(|self_parent| {
+                parsec.new_event_from_observation(self_parent, genesis_observation)
+            });
         if let Err(error) = event.and_then(|event| parsec.add_event(event)) {
             log_or_panic!(
                 "{:?} initialising Parsec failed when adding the genesis observation: {:?}",
@@ -185,19 +289,25 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
 
     /// Creates a new `Parsec` for a peer that is joining an existing section.
     ///
+    /// The peer is gossiped the graph from the genesis on, so it reaches consensus on the whole
+    /// history of the section and outputs the same blocks as the other peers, with the same
+    /// indices and chain links. See [`from_section_summary`](#method.from_section_summary) for
+    /// starting from the section's current meta-election instead.
+    ///
     /// * `our_id` is the value that will identify the owning peer in the network.
     /// * `genesis_group` is the set of public IDs of the peers that were present at the section
     /// startup.
     /// * `section` is the set of public IDs of the peers that constitute the section at the time
     /// of joining. They are the peers this `Parsec` instance will accept gossip from.
-    /// * `consensus_mode` determines how many votes are needed for an observation to become a
-    /// candidate for consensus. For more details, see [ConsensusMode](enum.ConsensusMode.html)
+    /// * `config` holds the settings of this instance, including how many votes are needed for an
+    /// observation to become a candidate for consensus. For more details, see
+    /// [ParsecConfig](struct.ParsecConfig.html)
     /// * `secure_rng` cryptographically secure RNG to use for DKG key generation.
     pub fn from_existing(
         our_id: S,
         genesis_group: &BTreeSet<S::PublicId>,
         section: &BTreeSet<S::PublicId>,
-        consensus_mode: ConsensusMode,
+        config: ParsecConfig,
         secure_rng: Box<dyn rand::Rng>,
     ) -> Self {
         if genesis_group.is_empty() {
@@ -216,6 +326,10 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             log_or_panic!("Section can't already contain us");
         }
 
+        if !config.quorum_policy().is_valid_for(section.len()) {
+            log_or_panic!("Quorum policy must tolerate a faulty voter of the section");
+        }
+
         let mut peer_list = PeerList::new(our_id);
 
         // Add ourselves
@@ -235,31 +349,124 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             let _ = peer_list.add_peer(peer_id.clone(), PeerState::SEND);
         }
 
-        Self::empty(peer_list, genesis_indices, consensus_mode, secure_rng)
+        Self::empty(
+            peer_list,
+            genesis_group,
+            genesis_indices,
+            config,
+            secure_rng,
+        )
+    }
+
+    /// Creates a new `Parsec` for a peer that is joining an existing section, to be caught up with
+    /// the section from its current meta-election on rather than gossiped its graph from the
+    /// genesis on.
+    ///
+    /// * `our_id` is the value that will identify the owning peer in the network.
+    /// * `genesis_group` is the set of public IDs of the peers that were present at the section
+    /// startup.
+    /// * `section` is the set of public IDs of the voters of the section, as known to the caller.
+    /// * `signed_summaries` are the [`SectionSummary`](struct.SectionSummary.html)s obtained from
+    /// the members of the section with [`section_summary`](#method.section_summary). One of them
+    /// has to be validly signed by a quorum of `section`, as per `config`, otherwise
+    /// `Error::InsufficientVotes` is returned.
+    /// * `config` holds the settings of this instance. For more details, see
+    /// [ParsecConfig](struct.ParsecConfig.html). `Error::InvalidQuorumPolicy` is returned if its
+    /// quorum policy doesn't tolerate a faulty voter of `section`.
+    /// * `secure_rng` cryptographically secure RNG to use for DKG key generation.
+    ///
+    /// The new instance then has to be passed the [`CatchUp`](struct.CatchUp.html) one of the
+    /// voters creates for it at the same meta-election, with
+    /// [`handle_catch_up`](#method.handle_catch_up). Until then,
+    /// [`is_catching_up`](#method.is_catching_up) returns `true`.
+    pub fn from_section_summary(
+        our_id: S,
+        genesis_group: &BTreeSet<S::PublicId>,
+        section: &BTreeSet<S::PublicId>,
+        signed_summaries: &[SignedSectionSummary<S::PublicId>],
+        config: ParsecConfig,
+        secure_rng: Box<dyn rand::Rng>,
+    ) -> Result<Self> {
+        if !config.quorum_policy().is_valid_for(section.len()) {
+            return Err(Error::InvalidQuorumPolicy);
+        }
+
+        let summary =
+            section_summary::agreed_summary(signed_summaries, section, config.quorum_policy())?
+                .clone();
+
+        let mut peer_list = PeerList::new(our_id);
+        peer_list.change_peer_state(PeerIndex::OUR, PeerState::RECV);
+
+        // The voters are as of the start of the meta-election we'll be caught up with. If the
+        // section has added us already, we're one of them.
+        let voters: PeerIndexSet = summary
+            .voters()
+            .iter()
+            .map(|peer_id| {
+                if peer_id == peer_list.our_pub_id() {
+                    PeerIndex::OUR
+                } else {
+                    peer_list.add_peer(peer_id.clone(), PeerState::VOTE | PeerState::SEND)
+                }
+            })
+            .collect();
+
+        let mut parsec = Self::empty(peer_list, genesis_group, voters, config, secure_rng);
+        parsec.section_summary = Some(summary);
+        Ok(parsec)
     }
 
     // Construct empty `Parsec` with no peers (except us) and no gossip events.
     fn empty(
         peer_list: PeerList<S>,
-        genesis_group: PeerIndexSet,
-        consensus_mode: ConsensusMode,
+        genesis_group: &BTreeSet<S::PublicId>,
+        voters: PeerIndexSet,
+        config: ParsecConfig,
         secure_rng: Box<dyn rand::Rng>,
     ) -> Self {
         dump_graph::init();
 
         Self {
             peer_list,
+            genesis_group: genesis_group.clone(),
+            pending_key_rotation: None,
+            restored_key_rotation: None,
             key_gen: BTreeMap::new(),
             key_gen_next_id: KeyGenId::default(),
+            failed_key_gens: BTreeMap::new(),
             graph: Graph::new(),
             consensused_blocks: VecDeque::new(),
+            block_sink: None,
+            payload_validator: None,
             observations: BTreeMap::new(),
-            meta_election: MetaElection::new(genesis_group),
-            consensus_mode,
+            pruned_observations: BTreeMap::new(),
+            meta_election: MetaElection::new(voters),
+            config,
             pending_dkg_msgs: vec![],
-            #[cfg(feature = "malice-detection")]
+            coin_keys: None,
+            coin_shares: CoinShares::default(),
+            pending_coin_shares: vec![],
+            unsigned_blocks: VecDeque::new(),
+            block_signature_shares: BTreeMap::new(),
+            next_block_index: 0,
+            block_signature_wait: 0,
+            pending_block_signature_shares: vec![],
+            chain_tip: None,
+            section_summary: None,
             pending_accusations: vec![],
+            consensused_accusations: BTreeMap::new(),
+            pending_removals: vec![],
             pending_events: vec![],
+            pending_requests: BTreeMap::new(),
+            pending_responses: BTreeMap::new(),
+            awaiting_responses: BTreeSet::new(),
+            peer_versions: BTreeMap::new(),
+            spam_stats: BTreeMap::new(),
+            spam_window_syncs: 0,
+            unanswered_requests: BTreeMap::new(),
+            invalid_payloads: BTreeMap::new(),
+            unprovably_accused: BTreeSet::new(),
 
             #[cfg(any(test, feature = "testing"))]
             ignore_process_events: false,
@@ -273,6 +480,11 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         self.peer_list.our_pub_id()
     }
 
+    /// Returns the configuration of this instance.
+    pub fn config(&self) -> &ParsecConfig {
+        &self.config
+    }
+
     /// Inserts the owning peer's vote for `observation` into the gossip graph. The subsequent
     /// gossip messages will spread the vote to other peers, eventually making it a candidate for
     /// the next consensused block.
@@ -281,10 +493,40 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
     /// already voted for this `observation`, or if adding a gossip event containing the vote to
     /// the gossip graph failed.
     pub fn vote_for(&mut self, observation: Observation<T, S::PublicId>) -> Result<()> {
-        debug!("{:?} voting for {:?}", self.our_pub_id(), observation);
+        log_at!(
+            self,
+            LogLevel::Debug,
+            "{:?} voting for {:?}",
+            self.our_pub_id(),
+            observation
+        );
 
         self.confirm_self_state(PeerState::VOTE)?;
 
+        match observation {
+            Observation::ChangeWeight { weight, .. } if !is_valid_weight(weight) => {
+                return Err(Error::InvalidWeight)
+            }
+            Observation::Reconfigure {
+                ref add,
+                ref remove,
+                ..
+            } if !self.is_valid_reconfiguration(add, remove) => {
+                return Err(Error::InvalidReconfiguration)
+            }
+            Observation::RotateKey {
+                ref old,
+                ref new,
+                ref proof,
+            } if !proof.is_valid(old, new) || self.peer_list.contains(new) => {
+                return Err(Error::InvalidKeyRotation)
+            }
+            Observation::OpaquePayload(_) if !self.is_valid_payload(&observation) => {
+                return Err(Error::InvalidPayload)
+            }
+            _ => (),
+        }
+
         if self.have_voted_for(&observation) {
             return Err(Error::DuplicateVote);
         }
@@ -298,6 +540,30 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         Ok(())
     }
 
+    /// Votes for rotating the owning peer's key to `new_id`, without leaving the section. Once the
+    /// vote is consensused, the other peers know us by the public ID of `new_id`, and our events
+    /// from then on are signed with it.
+    ///
+    /// Only the public ID of `new_id` is saved by `save`. If this instance is restored before the
+    /// rotation is consensused, call this again with the same `new_id` before handling any gossip
+    /// to carry on with the rotation.
+    pub fn rotate_key(&mut self, new_id: S) -> Result<()> {
+        if self.restored_key_rotation.as_ref() == Some(new_id.public_id()) {
+            self.restored_key_rotation = None;
+            self.pending_key_rotation = Some(new_id);
+            return Ok(());
+        }
+
+        let observation = Observation::RotateKey {
+            old: self.our_pub_id().clone(),
+            new: new_id.public_id().clone(),
+            proof: KeyRotationProof::new(self.peer_list.our_id(), &new_id),
+        };
+        self.vote_for(observation)?;
+        self.pending_key_rotation = Some(new_id);
+        Ok(())
+    }
+
     /// Returns an iterator with the IDs of peers who the owning peer can send gossip messages to.
     /// Calling `create_gossip` with a peer ID returned by this method is guaranteed to succeed
     /// (assuming no section mutation happened in between).
@@ -307,82 +573,131 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             .map(|(_, peer)| peer.id())
     }
 
+    /// Returns an iterator with the IDs of peers which haven't answered one of our gossip requests
+    /// for more of our own sync events than the threshold set by
+    /// [`ParsecConfig::with_unresponsive_threshold`](
+    /// struct.ParsecConfig.html#method.with_unresponsive_threshold). Always empty if there is no
+    /// such threshold.
+    pub fn unresponsive_peers(&self) -> impl Iterator<Item = &S::PublicId> {
+        let threshold = self.config.unresponsive_threshold();
+        self.unanswered_requests
+            .iter()
+            .filter(move |(_, count)| {
+                threshold.map_or(false, |threshold| **count > threshold.get())
+            })
+            .filter_map(move |(index, _)| self.peer_list.get(*index))
+            .filter(|peer| peer.state().can_recv())
+            .map(|peer| peer.id())
+    }
+
+    /// Returns the evidence proving `malice` to anyone knowing the offender's `PublicId` and the
+    /// genesis group, made of the relevant events from our gossip graph, to be checked with
+    /// [`verify_evidence_with_genesis`](fn.verify_evidence_with_genesis.html), or with
+    /// [`verify_evidence`](fn.verify_evidence.html) unless it's about a genesis vote by a peer
+    /// outside of the genesis group.
+    ///
+    /// Returns `None` if we don't have the events, or if the malice can't be proven by the
+    /// offender's events alone. That is the case for malice which depends on the whole gossip
+    /// graph: `MissingGenesis`, `IncorrectGenesis`, `InvalidAccusation`, `InvalidResponse`,
+    /// `Unprovable` and `Accomplice`, and `InvalidRequest` when its other-parent is a valid
+    /// `Requesting` event answered already.
+    pub fn malice_evidence(
+        &self,
+        malice: &Malice<T, S::PublicId>,
+    ) -> Option<Evidence<T, S::PublicId>> {
+        let genesis_group = self.genesis_group().into_iter().cloned().collect();
+        malice.evidence(self.event_context(), &genesis_group)
+    }
+
+    /// Limits the number of events in a single gossip message to `limit`, or removes the limit if
+    /// `limit` is `None`. There is no limit by default.
+    ///
+    /// Syncs with more events are split into several messages, each with `has_more()` returning
+    /// `true` except for the last one. Their recipient has to keep calling `create_gossip` for
+    /// the sender to get the remaining parts of the sync.
+    ///
+    /// Splitting syncs needs version 2 of the gossip protocol, so the limit doesn't apply to the
+    /// messages exchanged with a peer before agreeing on a version, nor to peers which only
+    /// support version 1.
+    pub fn set_gossip_event_limit(&mut self, limit: Option<NonZeroUsize>) {
+        self.config.set_gossip_event_limit(limit);
+    }
+
     /// Creates a new message to be gossiped to a peer, containing all gossip events this peer
     /// thinks that peer needs.  If the given peer is not an active node, an error is returned.
     ///
+    /// If a gossip event limit is set, the message might only contain part of those events. In
+    /// that case, subsequent calls for the same peer continue the sync instead of starting a new
+    /// one.
+    ///
     /// * `peer_id`: the intended recipient of the gossip message
     /// * returns a `Request` to be sent to the intended recipient
     pub fn create_gossip(&mut self, peer_id: &S::PublicId) -> Result<Request<T, S::PublicId>> {
         let peer_index = self.get_peer_index(peer_id)?;
         self.confirm_allowed_to_gossip_to(peer_index)?;
 
-        debug!(
-            "{:?} creating gossip request for {:?}",
-            self.our_pub_id(),
-            peer_id
-        );
-
-        let self_parent = self.peer_list.last_event(PeerIndex::OUR).ok_or_else(|| {
-            log_or_panic!("{:?} missing our own last event hash.", self.our_pub_id());
-            Error::Logic
-        })?;
-        let sync_event = Event::new_from_requesting(self_parent, peer_id, self.event_context())?;
-        let _ = self.add_event(sync_event)?;
-
-        let events = if self.peer_list.last_event(peer_index).is_some() {
-            self.events_to_gossip_to_peer(peer_index)?
-        } else {
-            self.graph.iter().map(|e| e.inner()).collect()
-        };
-        self.pack_events(events).map(Request::new)
+        let (version, capabilities) = self.gossip_version(peer_index);
+        let request = self.create_request(peer_index, peer_id);
+        self.graph.trim_cache();
+        request.map(|req| req.with_version(version, capabilities))
     }
 
     /// Handles a `Request` the owning peer received from the `src` peer.  Returns a `Response` to
     /// be sent back to `src`, or `Err` if the request was not valid or if `src` has been removed
     /// from the section already.
+    ///
+    /// The first request from `src` carries the range of gossip protocol versions it supports.
+    /// If we can't agree on a version with it, or if the request uses a version we don't support,
+    /// `Error::IncompatibleVersion` is returned.
     pub fn handle_request(
         &mut self,
         src: &S::PublicId,
         req: Request<T, S::PublicId>,
     ) -> Result<Response<T, S::PublicId>> {
-        debug!(
+        log_at!(
+            self,
+            LogLevel::Debug,
             "{:?} received gossip request from {:?}",
             self.our_pub_id(),
             src
         );
 
         let src_index = self.get_peer_index(src)?;
-        let other_parent = self.unpack_and_add_events(src_index, req.packed_events)?;
-        self.create_dkg_events()?;
-        #[cfg(feature = "malice-detection")]
-        self.create_accusation_events(other_parent)?;
-        self.create_sync_event(true, other_parent)?;
-        self.flush_pending_events()?;
+        self.check_version(src_index, req.version, req.capabilities)?;
+        self.record_gossip_from(src_index);
+        self.decay_unanswered_requests(src_index);
 
-        let events = self.events_to_gossip_to_peer(src_index)?;
-        self.pack_events(events).map(Response::new)
+        // Answer the sender's capabilities with ours, for it to agree on the version as well.
+        let (version, _) = self.gossip_version(src_index);
+        let capabilities = req.capabilities.map(|_| Capabilities::ours());
+        let response = self.respond_to_request(src_index, req);
+        self.graph.trim_cache();
+        response.map(|resp| resp.with_version(version, capabilities))
     }
 
     /// Handles a `Response` the owning peer received from the `src` peer. Returns `Err` if the
-    /// response was not valid or if `src` has been removed from the section already.
+    /// response was not valid or if `src` has been removed from the section already, or
+    /// `Error::IncompatibleVersion` if it uses a gossip protocol version we don't support.
     pub fn handle_response(
         &mut self,
         src: &S::PublicId,
         resp: Response<T, S::PublicId>,
     ) -> Result<()> {
-        debug!(
+        log_at!(
+            self,
+            LogLevel::Debug,
             "{:?} received gossip response from {:?}",
             self.our_pub_id(),
             src
         );
 
         let src_index = self.get_peer_index(src)?;
-        let other_parent = self.unpack_and_add_events(src_index, resp.packed_events)?;
-        self.create_dkg_events()?;
-        #[cfg(feature = "malice-detection")]
-        self.create_accusation_events(other_parent)?;
-        self.create_sync_event(false, other_parent)?;
-        self.flush_pending_events()
+        self.check_version(src_index, resp.version, resp.capabilities)?;
+        self.record_gossip_from(src_index);
+
+        let result = self.receive_response(src_index, resp);
+        self.graph.trim_cache();
+        result
     }
 
     /// Returns the next stable block, if any. The method might need to be called more than once
@@ -393,7 +708,7 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
     /// `Observation::Remove(our_id)` has been made stable), then no further blocks will be
     /// enqueued. So, once `poll()` returns such a block, it will continue to return `None` forever.
     pub fn poll(&mut self) -> Option<Block<T, S::PublicId>> {
-        let mut block_group = self.batch_poll()?;
+        let mut block_group = self.consensused_blocks.pop_front()?;
         let block = block_group.pop_front()?;
         if !block_group.is_empty() {
             self.consensused_blocks.push_front(block_group);
@@ -401,16 +716,68 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         Some(block)
     }
 
-    /// Returns the next group of stable blocks, if any. The method might need to be called more
-    /// than once for the caller to get all the blocks that have been consensused. A `None` value
-    /// means that all the blocks consensused so far have already been returned.
+    /// Registers `sink` to be handed the stable blocks as soon as they are consensused, instead of
+    /// queueing them to be returned by `poll` and `batch_poll`. The sink is handed the same
+    /// batches as `batch_poll` would return, starting with the ones already queued. Returns the
+    /// previously registered sink, if any.
+    ///
+    /// The sink is not part of the state written by [save](struct.Parsec.html#method.save), so it
+    /// has to be registered again on a restored instance.
+    pub fn set_block_sink(
+        &mut self,
+        mut sink: Box<dyn BlockSink<T, S::PublicId>>,
+    ) -> Option<Box<dyn BlockSink<T, S::PublicId>>> {
+        while let Some(batch) = self.batch_poll() {
+            sink.handle_batch(batch);
+        }
+        self.block_sink.replace(sink)
+    }
+
+    /// Unregisters the block sink, if any, and returns it. The stable blocks are queued to be
+    /// returned by `poll` and `batch_poll` again from then on.
+    pub fn take_block_sink(&mut self) -> Option<Box<dyn BlockSink<T, S::PublicId>>> {
+        self.block_sink.take()
+    }
+
+    /// Registers `validator` to check the opaque payloads. `vote_for` then fails with
+    /// `Error::InvalidPayload` for an invalid one. The received events carrying a vote for one are
+    /// still added to the gossip graph, but the vote never reaches consensus or a block. Returns
+    /// the previously registered validator, if any.
+    ///
+    /// The creators of such votes are accused once they exceed the threshold set by
+    /// [ParsecConfig::with_invalid_payload_threshold](
+    /// struct.ParsecConfig.html#method.with_invalid_payload_threshold).
+    ///
+    /// The validator is not part of the state written by [save](struct.Parsec.html#method.save),
+    /// so it has to be registered again on a restored instance.
+    pub fn set_payload_validator(
+        &mut self,
+        validator: Box<dyn PayloadValidator<T>>,
+    ) -> Option<Box<dyn PayloadValidator<T>>> {
+        self.payload_validator.replace(validator)
+    }
+
+    /// Unregisters the payload validator, if any, and returns it. Any opaque payload is accepted
+    /// again from then on.
+    pub fn take_payload_validator(&mut self) -> Option<Box<dyn PayloadValidator<T>>> {
+        self.payload_validator.take()
+    }
+
+    /// Returns the next batch of stable blocks, i.e. the blocks consensused within the same
+    /// meta-election, along with information about that meta-election. If `poll` has already
+    /// returned some blocks of the batch, only the remaining ones are included. The method might
+    /// need to be called more than once for the caller to get all the blocks that have been
+    /// consensused. A `None` value means that all the blocks consensused so far have already been
+    /// returned.
     ///
     /// Once the owning peer has been removed from the section (i.e. a block with payload
     /// `Observation::Remove(our_id)` has been made stable), then no further blocks will be
     /// enqueued. So, once `poll()` or `batch_poll()` returns such a block, it will continue to
     /// return `None` forever.
-    pub(crate) fn batch_poll(&mut self) -> Option<BlockGroup<T, S::PublicId>> {
-        self.consensused_blocks.pop_front()
+    pub fn batch_poll(&mut self) -> Option<ConsensusBatch<T, S::PublicId>> {
+        self.consensused_blocks
+            .pop_front()
+            .map(ConsensusBatch::from)
     }
 
     /// Check if the owning peer can vote (that is, it has reached a consensus on itself being a
@@ -422,10 +789,15 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
     /// Checks if the given `observation` has already been voted for by the owning peer.
     pub fn have_voted_for(&self, observation: &Observation<T, S::PublicId>) -> bool {
         let hash = ObservationHash::from(observation);
-        let key = ObservationKey::new(hash, PeerIndex::OUR, self.consensus_mode.of(observation));
+        let key = ObservationKey::new(
+            hash,
+            PeerIndex::OUR,
+            self.config.consensus_mode().of(observation),
+        );
         self.observations
             .get(&key)
             .map(|info| info.created_by_us)
+            .or_else(|| self.pruned_observations.get(&key).cloned())
             .unwrap_or(false)
     }
 
@@ -433,8 +805,11 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
     /// either they haven't been consensused yet or a block containing that observation hasn't yet
     /// been retrieved by calling `poll`.
     pub fn has_unpolled_observations(&self) -> bool {
-        self.observations.values().any(|info| !info.consensused)
+        self.observations
+            .values()
+            .any(|info| !info.consensused && !info.observation.is_signature_share())
             || !self.consensused_blocks.is_empty()
+            || !self.unsigned_blocks.is_empty()
     }
 
     /// Returns observations voted for by the owning peer which haven't been returned as a stable
@@ -448,6 +823,467 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             .chain(self.our_unconsensused_observations())
     }
 
+    /// Serialises the complete state of this instance - the gossip graph, the peer list, the
+    /// observations, the meta-election, the blocks not yet returned by `poll`, any ongoing DKG
+    /// sessions, the progress of split syncs, the counts kept to detect malice and the
+    /// configuration - so it can be persisted and brought back later via
+    /// [restore](struct.Parsec.html#method.restore).
+    ///
+    /// The secret ID of the owning peer is not included, but the returned bytes still contain
+    /// secret key material (our DKG secret shares), so they should be stored with the same care.
+    ///
+    /// Returns `Error::SerialisationFailure` if the state could not be serialised.
+    pub fn save(&self) -> Result<Vec<u8>> {
+        let state = SavedStateRef {
+            peer_list: &self.peer_list,
+            genesis_group: &self.genesis_group,
+            key_gen: &self.key_gen,
+            key_gen_next_id: self.key_gen_next_id,
+            failed_key_gens: &self.failed_key_gens,
+            graph: &self.graph,
+            observations: &self.observations,
+            pruned_observations: &self.pruned_observations,
+            consensused_blocks: &self.consensused_blocks,
+            meta_election: &self.meta_election,
+            config: &self.config,
+            pending_dkg_msgs: &self.pending_dkg_msgs,
+            coin_keys: &self.coin_keys,
+            coin_shares: &self.coin_shares,
+            pending_coin_shares: &self.pending_coin_shares,
+            unsigned_blocks: &self.unsigned_blocks,
+            block_signature_shares: &self.block_signature_shares,
+            next_block_index: self.next_block_index,
+            block_signature_wait: self.block_signature_wait,
+            pending_block_signature_shares: &self.pending_block_signature_shares,
+            chain_tip: &self.chain_tip,
+            section_summary: &self.section_summary,
+            pending_accusations: &self.pending_accusations,
+            consensused_accusations: &self.consensused_accusations,
+            pending_removals: &self.pending_removals,
+            pending_events: &self.pending_events,
+            pending_requests: &self.pending_requests,
+            pending_responses: &self.pending_responses,
+            awaiting_responses: &self.awaiting_responses,
+            peer_versions: &self.peer_versions,
+            spam_stats: &self.spam_stats,
+            spam_window_syncs: self.spam_window_syncs,
+            unanswered_requests: &self.unanswered_requests,
+            invalid_payloads: &self.invalid_payloads,
+            unprovably_accused: &self.unprovably_accused,
+            pending_key_rotation: self
+                .pending_key_rotation
+                .as_ref()
+                .map(SecretId::public_id)
+                .or(self.restored_key_rotation.as_ref()),
+        };
+        serialisation::serialise(&(SAVED_STATE_VERSION, state)).map_err(|error| {
+            log_at!(
+                self,
+                LogLevel::Error,
+                "{:?} failed to serialise its state: {:?}",
+                self.our_pub_id(),
+                error
+            );
+            Error::SerialisationFailure
+        })
+    }
+
+    /// Recreates a `Parsec` instance from the bytes produced by
+    /// [save](struct.Parsec.html#method.save). The restored instance continues exactly where the
+    /// saved one left off, except that it keeps its gossip graph in memory until
+    /// [set_graph_storage](struct.Parsec.html#method.set_graph_storage) is called. The whole
+    /// configuration is restored, including a gossip event limit set after creating the instance.
+    ///
+    /// * `our_id` must be the same secret ID the saved instance was created with.
+    /// * `secure_rng` cryptographically secure RNG to use for DKG key generation.
+    ///
+    /// Returns `Error::InvalidSavedState` if the bytes are malformed, were written by an
+    /// incompatible version of this library, or belong to a different peer.
+    pub fn restore(our_id: S, bytes: &[u8], secure_rng: Box<dyn rand::Rng>) -> Result<Self> {
+        let mut cursor = Cursor::new(bytes);
+        let version: u32 = deserialise_saved_state(&mut cursor)?;
+        if version != SAVED_STATE_VERSION {
+            debug!(
+                "{:?} cannot restore state saved with format version {} (expected {})",
+                our_id.public_id(),
+                version,
+                SAVED_STATE_VERSION
+            );
+            return Err(Error::InvalidSavedState);
+        }
+
+        let state: SavedState<T, S> = deserialise_saved_state(&mut cursor)?;
+        if cursor.position() != bytes.len() as u64 {
+            debug!("{:?} saved state has trailing bytes", our_id.public_id());
+            return Err(Error::InvalidSavedState);
+        }
+
+        let peer_list = PeerList::restore(our_id, state.peer_list).ok_or_else(|| {
+            debug!("Saved state belongs to a different peer");
+            Error::InvalidSavedState
+        })?;
+
+        dump_graph::init();
+
+        Ok(Self {
+            peer_list,
+            genesis_group: state.genesis_group,
+            pending_key_rotation: None,
+            restored_key_rotation: state.pending_key_rotation,
+            key_gen: state.key_gen,
+            key_gen_next_id: state.key_gen_next_id,
+            failed_key_gens: state.failed_key_gens,
+            graph: state.graph,
+            observations: state.observations,
+            pruned_observations: state.pruned_observations,
+            consensused_blocks: state.consensused_blocks,
+            block_sink: None,
+            payload_validator: None,
+            meta_election: state.meta_election,
+            config: state.config,
+            pending_dkg_msgs: state.pending_dkg_msgs,
+            coin_keys: state.coin_keys,
+            coin_shares: state.coin_shares,
+            pending_coin_shares: state.pending_coin_shares,
+            unsigned_blocks: state.unsigned_blocks,
+            block_signature_shares: state.block_signature_shares,
+            next_block_index: state.next_block_index,
+            block_signature_wait: state.block_signature_wait,
+            pending_block_signature_shares: state.pending_block_signature_shares,
+            chain_tip: state.chain_tip,
+            section_summary: state.section_summary,
+            pending_accusations: state.pending_accusations,
+            consensused_accusations: state.consensused_accusations,
+            pending_removals: state.pending_removals,
+            pending_events: state.pending_events,
+            pending_requests: state.pending_requests,
+            pending_responses: state.pending_responses,
+            awaiting_responses: state.awaiting_responses,
+            peer_versions: state.peer_versions,
+            spam_stats: state.spam_stats,
+            spam_window_syncs: state.spam_window_syncs,
+            unanswered_requests: state.unanswered_requests,
+            invalid_payloads: state.invalid_payloads,
+            unprovably_accused: state.unprovably_accused,
+
+            #[cfg(any(test, feature = "testing"))]
+            ignore_process_events: false,
+
+            secure_rng: ParsecRng::new(secure_rng),
+        })
+    }
+
+    /// Changes where the events of the gossip graph are kept, moving the events already in the
+    /// graph to the new storage. The graph is kept in memory by default.
+    ///
+    /// Returns `Error::StorageFailure` if the new storage can't be set up, in which case the
+    /// events stay where they were.
+    pub fn set_graph_storage(&mut self, storage: GraphStorage) -> Result<()> {
+        self.graph.set_storage(&storage).map_err(|error| {
+            log_at!(
+                self,
+                LogLevel::Warn,
+                "{:?} failed to set graph storage to {:?}: {:?}",
+                self.our_pub_id(),
+                storage,
+                error
+            );
+            Error::StorageFailure
+        })
+    }
+
+    /// Returns the summary of the section as of the current meta-election, signed by us, for a
+    /// peer joining the section to pass to [`from_section_summary`](
+    /// #method.from_section_summary). Only voters can sign the summary.
+    ///
+    /// Returns `Error::DkgInProgress` while a distributed key generation session is running, as
+    /// the joining peer couldn't be caught up with it.
+    pub fn section_summary(&self) -> Result<SignedSectionSummary<S::PublicId>> {
+        self.confirm_self_state(PeerState::VOTE)?;
+        self.confirm_no_dkg()?;
+
+        let peer_id = |peer_index| self.peer_list.get(peer_index).map(|peer| peer.id().clone());
+        let summary = SectionSummary {
+            voters: self.voters().iter().filter_map(peer_id).collect(),
+            weights: self
+                .meta_election
+                .weights
+                .iter()
+                .filter_map(|(peer_index, weight)| Some((peer_id(peer_index)?, *weight)))
+                .collect(),
+            consensus_history_hash: self.consensus_history_hash(),
+            election_index: self.meta_election.consensus_history().len(),
+            next_block_index: self.next_block_index,
+            chain_tip: self.chain_tip,
+            coin_keys: self
+                .coin_keys
+                .as_ref()
+                .map(|keys| (keys.participants().clone(), keys.public_key_set().clone())),
+            coin_election: self.coin_shares.election(),
+            next_key_gen_id: self.key_gen_next_id,
+        };
+        Ok(SignedSectionSummary::new(self.peer_list.our_id(), summary))
+    }
+
+    /// Returns whether we joined the section with [`from_section_summary`](
+    /// #method.from_section_summary) and haven't been caught up with it yet with
+    /// [`handle_catch_up`](#method.handle_catch_up).
+    pub fn is_catching_up(&self) -> bool {
+        self.section_summary.is_some()
+    }
+
+    /// Creates the [`CatchUp`](struct.CatchUp.html) for the given peer, which joined the section
+    /// with [`from_section_summary`](#method.from_section_summary), to pass to
+    /// [`handle_catch_up`](#method.handle_catch_up). It holds the gossip graph from the start of
+    /// the current meta-election on, so it has to be created at the same meta-election as the
+    /// summaries the peer was given.
+    ///
+    /// As with [`create_gossip`](#method.create_gossip), the peer must have been added to the
+    /// section already. Returns `Error::DkgInProgress` while a distributed key generation session
+    /// is running.
+    pub fn create_catch_up(&mut self, peer_id: &S::PublicId) -> Result<CatchUp<T, S::PublicId>> {
+        let peer_index = self.get_peer_index(peer_id)?;
+        self.confirm_allowed_to_gossip_to(peer_index)?;
+        self.confirm_no_dkg()?;
+
+        log_at!(
+            self,
+            LogLevel::Debug,
+            "{:?} creating catch-up for {:?}",
+            self.our_pub_id(),
+            peer_id
+        );
+
+        let (version, capabilities) = self.gossip_version(peer_index);
+        let _ = self.pending_requests.remove(&peer_index);
+        let _ = self.awaiting_responses.remove(&peer_index);
+        self.add_requesting_event(peer_index, peer_id)?;
+
+        // The last events of the peers which haven't created any since the start of the
+        // meta-election are needed as the self-parents of their next ones.
+        let start_index = self.meta_election.new_consensus_start_index();
+        let event_indices: BTreeSet<_> = self
+            .peer_list
+            .iter()
+            .filter(|(_, peer)| peer.removal_event().is_none())
+            .filter_map(|(peer_index, _)| self.peer_list.last_event(peer_index))
+            .filter(|event_index| event_index.topological_index() < start_index)
+            .chain(self.graph.indices_from(start_index))
+            .collect();
+        let events: Vec<_> = event_indices
+            .into_iter()
+            .filter_map(|event_index| self.graph.get(event_index))
+            .collect();
+
+        let first_indices = self
+            .peer_list
+            .iter()
+            .filter(|(index, _)| *index != peer_index)
+            .map(|(index, peer)| {
+                let first_index = events
+                    .iter()
+                    .find(|event| event.creator() == index)
+                    .map(|event| event.index_by_creator())
+                    .or_else(|| {
+                        self.peer_list
+                            .last_event(index)
+                            .and_then(|event_index| self.graph.get(event_index))
+                            .map(|event| event.index_by_creator() + 1)
+                    })
+                    .unwrap_or_else(|| peer.pruned_count());
+                (peer.id().clone(), first_index)
+            })
+            .collect();
+
+        let catch_up = self
+            .pack_events(events.iter().map(|event| event.inner()))
+            .map(|packed_events| CatchUp {
+                election_index: self.meta_election.consensus_history().len(),
+                consensus_history: self.consensus_history_ids(),
+                first_indices,
+                request: Request::new(packed_events).with_version(version, capabilities),
+            });
+        self.graph.trim_cache();
+        catch_up
+    }
+
+    /// Catches us up with the section we joined with [`from_section_summary`](
+    /// #method.from_section_summary), from the `CatchUp` the `src` peer created for us. Returns a
+    /// `Response` to be sent back to `src`, after which we gossip with the section as usual.
+    ///
+    /// Returns `Error::InvalidMessage` if we aren't catching up, and `Error::MismatchedSummary` if
+    /// the catch-up wasn't created at the meta-election our summary was taken at, or with the same
+    /// consensus history. In that case, another catch-up can be handled. If any other error is
+    /// returned, the events were only partly added, so this instance has to be created anew.
+    pub fn handle_catch_up(
+        &mut self,
+        src: &S::PublicId,
+        catch_up: CatchUp<T, S::PublicId>,
+    ) -> Result<Response<T, S::PublicId>> {
+        log_at!(
+            self,
+            LogLevel::Debug,
+            "{:?} received catch-up from {:?}",
+            self.our_pub_id(),
+            src
+        );
+
+        let summary = self.section_summary.clone().ok_or(Error::InvalidMessage)?;
+        if catch_up.election_index != summary.election_index
+            || section_summary::consensus_history_hash(&catch_up.consensus_history)
+                != summary.consensus_history_hash
+        {
+            log_at!(
+                self,
+                LogLevel::Debug,
+                "{:?} received catch-up from {:?} which doesn't match the section summary",
+                self.our_pub_id(),
+                src
+            );
+            return Err(Error::MismatchedSummary);
+        }
+
+        let src_index = self.get_peer_index(src)?;
+        let request = catch_up.request;
+        self.check_version(src_index, request.version, request.capabilities)?;
+        self.record_gossip_from(src_index);
+
+        self.start_catch_up(summary, catch_up.consensus_history, catch_up.first_indices);
+        let other_parent = self.add_catch_up_events(src_index, request.packed_events);
+        self.graph.finish_catch_up();
+
+        let (version, _) = self.gossip_version(src_index);
+        let capabilities = request.capabilities.map(|_| Capabilities::ours());
+        let response = other_parent.and_then(|other_parent| {
+            if self.voters().contains(PeerIndex::OUR) {
+                let our_id = self.our_pub_id().clone();
+                let _ = self.add_gossip_peer(&our_id, PeerState::VOTE | PeerState::SEND);
+            }
+            self.process_events(0)?;
+            self.respond_with_sync_event(src_index, other_parent)
+        });
+        self.graph.trim_cache();
+        let response = response?;
+
+        self.section_summary = None;
+        log_at!(
+            self,
+            LogLevel::Info,
+            "{:?} caught up with the section at meta-election {}",
+            self.our_pub_id(),
+            catch_up.election_index
+        );
+        Ok(response.with_version(version, capabilities))
+    }
+
+    /// Returns the progress of the distributed key generation sessions that are still running.
+    pub fn dkg_status(&self) -> BTreeMap<KeyGenId, DkgStatus<S::PublicId>> {
+        self.key_gen
+            .iter()
+            .map(|(key_gen_id, key_gen)| (*key_gen_id, DkgStatus::new(key_gen)))
+            .collect()
+    }
+
+    /// Checks the outcome of the given distributed key generation session.
+    ///
+    /// Returns `Ok` if the session is still running or has output its `DkgResult` block,
+    /// `Error::FailedDkg` with the reason if it failed or was cancelled, and `Error::UnknownDkg` if
+    /// it hasn't been started yet.
+    pub fn check_dkg(&self, key_gen_id: KeyGenId) -> Result<()> {
+        if let Some(failure) = self.failed_key_gens.get(&key_gen_id) {
+            Err(Error::FailedDkg(failure.clone()))
+        } else if key_gen_id < self.key_gen_next_id {
+            Ok(())
+        } else {
+            Err(Error::UnknownDkg)
+        }
+    }
+
+    /// Stops taking part in the given distributed key generation session, e.g. because it has
+    /// stalled. The other peers are not notified: the `Part`s and `Ack`s they send for the session
+    /// are ignored from now on.
+    ///
+    /// Returns `Error::UnknownDkg` if the session is not running, or `Error::FailedDkg` if it has
+    /// already failed.
+    pub fn cancel_dkg(&mut self, key_gen_id: KeyGenId) -> Result<()> {
+        self.check_dkg(key_gen_id)?;
+        if self.key_gen.remove(&key_gen_id).is_none() {
+            return Err(Error::UnknownDkg);
+        }
+
+        self.pending_dkg_msgs
+            .retain(|msg| msg.key_gen_id() != key_gen_id);
+        self.fail_key_gen(key_gen_id, DkgFailure::Cancelled);
+        Ok(())
+    }
+
+    /// Removes the gossip events that are no longer needed from memory, and returns how many were
+    /// removed.
+    ///
+    /// An event is removed once its payload (if any) has been consensused and it is a strict
+    /// ancestor of the last event of every peer that hasn't been removed from the section. This
+    /// doesn't change the consensus results. Events sent to us afterwards that have a removed
+    /// event as their parent are rejected, but as every peer has already built on top of the
+    /// removed events, honest peers only send such events in messages that are long outdated.
+    ///
+    /// With `GraphStorage::File`, the removed events still take space in the file, which is never
+    /// compacted. Calling [set_graph_storage](struct.Parsec.html#method.set_graph_storage) with a
+    /// new file moves only the remaining events there.
+    pub fn prune(&mut self) -> usize {
+        let end_index = self.prune_end_index();
+        let pruned_events = self.graph.prune(end_index);
+        if pruned_events.is_empty() {
+            return 0;
+        }
+
+        self.peer_list.prune_events(end_index);
+
+        let remaining_payload_keys: BTreeSet<_> = self
+            .graph
+            .iter()
+            .filter_map(|event| event.payload_key().cloned())
+            .collect();
+        for payload_key in pruned_events.iter().filter_map(Event::payload_key) {
+            if remaining_payload_keys.contains(payload_key) {
+                continue;
+            }
+
+            // Signature shares are never consensused, and are not needed once their events are
+            // gone.
+            let can_drop = self.observations.get(payload_key).map_or(false, |info| {
+                info.observation.is_signature_share()
+                    || info.consensused
+                        && !self
+                            .consensused_blocks
+                            .iter()
+                            .chain(&self.unsigned_blocks)
+                            .flatten()
+                            .any(|block| *block.payload() == info.observation)
+            });
+            if can_drop {
+                if let Some(info) = self
+                    .observations
+                    .remove(payload_key)
+                    .filter(|info| info.consensused)
+                {
+                    let _ = self
+                        .pruned_observations
+                        .insert(*payload_key, info.created_by_us);
+                }
+            }
+        }
+
+        log_at!(
+            self,
+            LogLevel::Debug,
+            "{:?} pruned {} events from the gossip graph",
+            self.our_pub_id(),
+            pruned_events.len()
+        );
+
+        pruned_events.len()
+    }
+
     fn our_consensused_observations(&self) -> impl Iterator<Item = &Observation<T, S::PublicId>> {
         self.observations.values().filter_map(move |info| {
             if info.created_by_us
@@ -463,7 +1299,7 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
 
     fn our_unconsensused_observations(&self) -> impl Iterator<Item = &Observation<T, S::PublicId>> {
         self.observations.values().filter_map(|info| {
-            if info.created_by_us && !info.consensused {
+            if info.created_by_us && !info.consensused && !info.observation.is_signature_share() {
                 Some(&info.observation)
             } else {
                 None
@@ -482,7 +1318,7 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         // signed by us, yet with payloads voted for by us.
         // In `Single` mode, on the other hand, check also that we signed it, to avoid false
         // positives when there are blocks with the same payloads but signed by someone else.
-        match self.consensus_mode.of(payload) {
+        match self.config.consensus_mode().of(payload) {
             ConsensusMode::Supermajority => matching_blocks.next().is_some(),
             ConsensusMode::Single => {
                 matching_blocks.any(|block| block.is_signed_by(self.our_pub_id()))
@@ -490,6 +1326,32 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         }
     }
 
+    // Returns the topological index of the first event that `prune` must keep. All the events
+    // before it precede the current meta-election and are strict ancestors of the last event of
+    // every peer that hasn't been removed.
+    fn prune_end_index(&self) -> usize {
+        let last_events: Vec<_> = self
+            .peer_list
+            .iter()
+            .filter(|(_, peer)| peer.removal_event().is_none())
+            .filter_map(|(peer_index, _)| self.peer_list.last_event(peer_index))
+            .filter_map(|event_index| self.graph.get(event_index))
+            .collect();
+        let new_consensus_start_index = self.meta_election.new_consensus_start_index();
+
+        self.graph
+            .iter()
+            .find(|event| {
+                event.topological_index() >= new_consensus_start_index
+                    || last_events.iter().any(|last_event| {
+                        last_event.event_index() == event.event_index()
+                            || !last_event.is_descendant_of(event)
+                    })
+            })
+            .map(|event| event.topological_index())
+            .unwrap_or_else(|| self.graph.len())
+    }
+
     /// Must only be used for events which have already been added to our graph.
     fn get_known_event(&self, event_index: EventIndex) -> Result<IndexedEventRef<S::PublicId>> {
         get_known_event(self.our_pub_id(), &self.graph, event_index)
@@ -509,7 +1371,9 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         if actual.contains(required) {
             Ok(())
         } else {
-            trace!(
+            log_at!(
+                self,
+                LogLevel::Trace,
                 "{:?} detected invalid state of {:?} (required: {:?}, actual: {:?})",
                 self.our_pub_id(),
                 peer_index,
@@ -525,7 +1389,9 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         if actual.contains(required) {
             Ok(())
         } else {
-            trace!(
+            log_at!(
+                self,
+                LogLevel::Trace,
                 "{:?} has invalid state (required: {:?}, actual: {:?})",
                 self.our_pub_id(),
                 required,
@@ -535,12 +1401,27 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         }
     }
 
+    // A peer joining the section can't be caught up with the DKG sessions running in it.
+    fn confirm_no_dkg(&self) -> Result<()> {
+        if self.key_gen.is_empty() && self.pending_dkg_msgs.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::DkgInProgress)
+        }
+    }
+
     fn confirm_can_add_event(&self, event: &Event<S::PublicId>) -> Result<()> {
         let peer = self
             .peer_list
             .get(event.creator())
             .ok_or(Error::UnknownPeer)?;
 
+        if peer.is_pruned(event.index_by_creator()) {
+            // Only an initial event can get here, as any other event would have a pruned
+            // self-parent. It would be a fork of an initial event we've already pruned.
+            return Err(Error::InvalidEvent);
+        }
+
         if event.creator() == PeerIndex::OUR || peer.state().can_send() {
             return Ok(());
         }
@@ -578,68 +1459,413 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         })
     }
 
-    fn pack_events<'a, I>(&self, events: I) -> Result<Vec<PackedEvent<T, S::PublicId>>>
-    where
-        I: IntoIterator<Item = &'a Event<S::PublicId>>,
-        S::PublicId: 'a,
-    {
-        events
-            .into_iter()
-            .map(|event| event.pack(self.event_context()))
-            .collect()
+    fn pack_events<'a, I>(&self, events: I) -> Result<Vec<PackedEvent<T, S::PublicId>>>
+    where
+        I: IntoIterator<Item = &'a Event<S::PublicId>>,
+        S::PublicId: 'a,
+    {
+        events
+            .into_iter()
+            .map(|event| event.pack(self.event_context()))
+            .collect()
+    }
+
+    fn create_request(
+        &mut self,
+        peer_index: PeerIndex,
+        peer_id: &S::PublicId,
+    ) -> Result<Request<T, S::PublicId>> {
+        // If the peer sent us only part of its response, ask it for the rest with an empty
+        // request. This is done once only, so a lost message doesn't stall our gossip to it.
+        if self.awaiting_responses.remove(&peer_index) {
+            log_at!(
+                self,
+                LogLevel::Debug,
+                "{:?} requesting rest of gossip response from {:?}",
+                self.our_pub_id(),
+                peer_id
+            );
+            let _ = self.unanswered_requests.entry(peer_index).or_insert(0);
+            return Ok(Request::new(vec![]));
+        }
+
+        if let Some(events) = self.pending_requests.remove(&peer_index) {
+            log_at!(
+                self,
+                LogLevel::Debug,
+                "{:?} continuing gossip request for {:?}",
+                self.our_pub_id(),
+                peer_id
+            );
+            let _ = self.unanswered_requests.entry(peer_index).or_insert(0);
+            let (packed_events, has_more) = self.pack_gossip_part(peer_index, events, true)?;
+            return Ok(Request::new_part(packed_events, has_more));
+        }
+
+        log_at!(
+            self,
+            LogLevel::Debug,
+            "{:?} creating gossip request for {:?}",
+            self.our_pub_id(),
+            peer_id
+        );
+
+        self.add_requesting_event(peer_index, peer_id)?;
+
+        let events = if self.peer_list.last_event(peer_index).is_some() {
+            self.event_indices_to_gossip_to_peer(peer_index)?
+        } else {
+            self.graph.indices_from(0).collect()
+        };
+        let (packed_events, has_more) = self.pack_gossip_part(peer_index, events, true)?;
+        Ok(Request::new_part(packed_events, has_more))
+    }
+
+    fn add_requesting_event(&mut self, peer_index: PeerIndex, peer_id: &S::PublicId) -> Result<()> {
+        let self_parent = self.peer_list.last_event(PeerIndex::OUR).ok_or_else(|| {
+            log_or_panic!("{:?} missing our own last event hash.", self.our_pub_id());
+            Error::Logic
+        })?;
+        let sync_event = Event::new_from_requesting(self_parent, peer_id, self.event_context())?;
+        let _ = self.add_event(sync_event)?;
+        self.detect_unresponsive_peers();
+        self.advance_spam_window();
+        self.advance_block_signature_wait();
+        let _ = self.unanswered_requests.entry(peer_index).or_insert(0);
+        Ok(())
+    }
+
+    fn respond_to_request(
+        &mut self,
+        src_index: PeerIndex,
+        req: Request<T, S::PublicId>,
+    ) -> Result<Response<T, S::PublicId>> {
+        // An empty request asks for the rest of the response we've sent to `src` only part of.
+        if req.packed_events.is_empty() && !req.has_more {
+            let events = self
+                .pending_responses
+                .remove(&src_index)
+                .ok_or(Error::InvalidMessage)?;
+            let (packed_events, has_more) = self.pack_gossip_part(src_index, events, false)?;
+            return Ok(Response::new_part(packed_events, has_more));
+        }
+
+        let _ = self.pending_responses.remove(&src_index);
+
+        // Only the last part of the request creates a sync event, so just acknowledge the others.
+        if req.has_more {
+            self.add_packed_events(src_index, req.packed_events)?;
+            return Ok(Response::new_part(vec![], true));
+        }
+
+        let other_parent = self.unpack_and_add_events(src_index, req.packed_events)?;
+        self.respond_with_sync_event(src_index, other_parent)
+    }
+
+    // Creates our sync event for the request from `src`, whose last event is `other_parent`, and
+    // responds with the events `src` doesn't know about yet.
+    fn respond_with_sync_event(
+        &mut self,
+        src_index: PeerIndex,
+        other_parent: EventIndex,
+    ) -> Result<Response<T, S::PublicId>> {
+        self.create_dkg_events()?;
+        self.create_accusation_events(other_parent)?;
+        self.create_sync_event(true, other_parent)?;
+        self.create_coin_share_events()?;
+        self.create_block_signature_share_events()?;
+        self.flush_pending_events()?;
+        self.vote_for_removals()?;
+
+        let events = self.event_indices_to_gossip_to_peer(src_index)?;
+        let (packed_events, has_more) = self.pack_gossip_part(src_index, events, false)?;
+        Ok(Response::new_part(packed_events, has_more))
+    }
+
+    fn receive_response(
+        &mut self,
+        src_index: PeerIndex,
+        resp: Response<T, S::PublicId>,
+    ) -> Result<()> {
+        // Any answer shows `src` is responsive, including to the parts of a split sync.
+        let _ = self.unanswered_requests.remove(&src_index);
+        if resp.has_more {
+            // Either an acknowledgement of part of our request, or part of the response. In the
+            // latter case, we ask for the rest of it when next gossiping to `src`.
+            if !resp.packed_events.is_empty() {
+                self.add_packed_events(src_index, resp.packed_events)?;
+                let _ = self.awaiting_responses.insert(src_index);
+            }
+            return Ok(());
+        }
+
+        let _ = self.awaiting_responses.remove(&src_index);
+        let other_parent = self.unpack_and_add_events(src_index, resp.packed_events)?;
+        self.create_dkg_events()?;
+        self.create_accusation_events(other_parent)?;
+        self.create_sync_event(false, other_parent)?;
+        self.create_coin_share_events()?;
+        self.create_block_signature_share_events()?;
+        self.flush_pending_events()?;
+        self.vote_for_removals()
+    }
+
+    // Returns the gossip protocol version to use for messages to the given peer, with our
+    // capabilities attached if we haven't agreed on a version with it yet. Until then, we use the
+    // oldest version we support, so that the peer can read the message whichever versions it
+    // supports.
+    fn gossip_version(&self, peer_index: PeerIndex) -> (u32, Option<Capabilities>) {
+        if let Some(version) = self.peer_versions.get(&peer_index) {
+            (*version, None)
+        } else {
+            (MIN_PROTOCOL_VERSION, Some(Capabilities::ours()))
+        }
+    }
+
+    // Checks that a message from the given peer uses a gossip protocol version we support, and
+    // agrees on the version to use with the peer if the message carries its capabilities.
+    fn check_version(
+        &mut self,
+        peer_index: PeerIndex,
+        version: u32,
+        capabilities: Option<Capabilities>,
+    ) -> Result<()> {
+        let ours = Capabilities::ours();
+        if let Some(capabilities) = capabilities {
+            let agreed_version = ours.negotiate(&capabilities).ok_or_else(|| {
+                log_at!(
+                    self,
+                    LogLevel::Debug,
+                    "{:?} can't agree on a gossip protocol version with {:?}: {:?}",
+                    self.our_pub_id(),
+                    peer_index,
+                    capabilities
+                );
+                Error::IncompatibleVersion
+            })?;
+            let _ = self.peer_versions.insert(peer_index, agreed_version);
+        }
+
+        if ours.supports(version) {
+            Ok(())
+        } else {
+            log_at!(
+                self,
+                LogLevel::Debug,
+                "{:?} received gossip protocol version {} from {:?}",
+                self.our_pub_id(),
+                version,
+                peer_index
+            );
+            Err(Error::IncompatibleVersion)
+        }
+    }
+
+    // Packs the first events of `events`, up to the gossip event limit, into a part of a message to
+    // the given peer. Returns whether there are more events left, in which case they're stored to
+    // be sent in the subsequent parts of the request (or response, depending on `is_request`).
+    // The limit is ignored if the gossip protocol version used with the peer can't split syncs.
+    fn pack_gossip_part(
+        &mut self,
+        peer_index: PeerIndex,
+        mut events: VecDeque<EventIndex>,
+        is_request: bool,
+    ) -> Result<(Vec<PackedEvent<T, S::PublicId>>, bool)> {
+        let (version, _) = self.gossip_version(peer_index);
+        let rest = match self.config.gossip_event_limit() {
+            Some(limit) if events.len() > limit.get() && version >= SPLIT_SYNC_VERSION => {
+                events.split_off(limit.get())
+            }
+            _ => VecDeque::new(),
+        };
+
+        // Events pruned since the sync started are ancestors of the last event of the peer, so it
+        // has them already.
+        let packed_events = self.pack_events(
+            events
+                .into_iter()
+                .filter_map(|index| self.graph.get(index))
+                .map(|event| event.inner()),
+        )?;
+
+        let has_more = !rest.is_empty();
+        if has_more {
+            let pending = if is_request {
+                &mut self.pending_requests
+            } else {
+                &mut self.pending_responses
+            };
+            let _ = pending.insert(peer_index, rest);
+        }
+        Ok((packed_events, has_more))
+    }
+
+    // Returns the list peers which have created forked events, and the event to use as the
+    // other-parent when creating our sync event as a result of handling this message.
+    fn unpack_and_add_events(
+        &mut self,
+        src_index: PeerIndex,
+        packed_events: Vec<PackedEvent<T, S::PublicId>>,
+    ) -> Result<EventIndex> {
+        let hash_of_last_event = packed_events
+            .last()
+            .map(PackedEvent::compute_hash)
+            .ok_or_else(|| Error::InvalidMessage)?;
+        self.add_packed_events(src_index, packed_events)?;
+
+        let last_event_index = self
+            .graph
+            .get_index(&hash_of_last_event)
+            .ok_or_else(|| Error::InvalidMessage)?;
+        Ok(last_event_index)
+    }
+
+    fn add_packed_events(
+        &mut self,
+        src_index: PeerIndex,
+        packed_events: Vec<PackedEvent<T, S::PublicId>>,
+    ) -> Result<()> {
+        self.confirm_self_state(PeerState::RECV)?;
+        self.confirm_peer_state(src_index, PeerState::SEND)?;
+
+        for packed_event in packed_events {
+            if let Some(event) = self.unpack(packed_event)? {
+                let event_creator = event.creator();
+                let event_index = self.add_event(event)?;
+
+                // We have received an event of a peer in the message. The peer can now receive
+                // gossips from us as well.
+                self.peer_list
+                    .change_peer_state(event_creator, PeerState::RECV);
+                self.peer_list
+                    .record_gossiped_event_by(src_index, event_index);
+
+                if self.config.detects_malice(MaliceKind::Accomplice) {
+                    self.detect_accomplice(event_index)?;
+                }
+            }
+        }
+
+        if self.config.malice_detection() {
+            self.detect_premature_gossip()?;
+        }
+
+        Ok(())
+    }
+
+    // Sets up the state of the section as of the start of the meta-election its summary was
+    // taken at: the peers, the consensus history, and the meta-election itself.
+    fn start_catch_up(
+        &mut self,
+        summary: SectionSummary<S::PublicId>,
+        consensus_history: Vec<(ObservationHash, Option<S::PublicId>)>,
+        first_indices: BTreeMap<S::PublicId, usize>,
+    ) {
+        // The peers we don't know yet have been removed from the section, or aren't voters.
+        for (peer_id, first_index) in first_indices {
+            let peer_index = self.peer_index_or_inactive(&peer_id);
+            self.peer_list.skip_events(peer_index, first_index);
+        }
+
+        let consensus_history: Vec<_> = consensus_history
+            .into_iter()
+            .map(|(hash, creator)| match creator {
+                Some(creator) => {
+                    ObservationKey::Single(hash, self.peer_index_or_inactive(&creator))
+                }
+                None => ObservationKey::Supermajority(hash),
+            })
+            .collect();
+        for payload_key in &consensus_history {
+            let _ = self.pruned_observations.insert(*payload_key, false);
+        }
+
+        let weights: PeerIndexMap<_> = summary
+            .weights
+            .iter()
+            .filter_map(|(peer_id, weight)| Some((self.peer_list.get_index(peer_id)?, *weight)))
+            .collect();
+        let voters = self.voters().clone();
+        self.meta_election = MetaElection::caught_up(voters, weights, consensus_history);
+
+        self.next_block_index = summary.next_block_index;
+        self.chain_tip = summary.chain_tip;
+        self.key_gen_next_id = summary.next_key_gen_id;
+        self.coin_keys = summary.coin_keys.map(|(participants, public_key_set)| {
+            CoinKeys::from_public(participants, public_key_set)
+        });
+        self.coin_shares
+            .catch_up(summary.coin_election, self.coin_keys.as_ref());
+    }
+
+    fn peer_index_or_inactive(&mut self, peer_id: &S::PublicId) -> PeerIndex {
+        self.peer_list.get_index(peer_id).unwrap_or_else(|| {
+            self.peer_list
+                .add_peer(peer_id.clone(), PeerState::inactive())
+        })
     }
 
-    // Returns the list peers which have created forked events, and the event to use as the
-    // other-parent when creating our sync event as a result of handling this message.
-    fn unpack_and_add_events(
+    // Adds the events of a catch-up from `src`. As their ancestors from before the section's
+    // meta-election are missing, they can't be vetted like gossiped events; the section has
+    // vetted them already. Returns the index of the last event, to use as the other-parent of our
+    // sync event.
+    fn add_catch_up_events(
         &mut self,
         src_index: PeerIndex,
         packed_events: Vec<PackedEvent<T, S::PublicId>>,
     ) -> Result<EventIndex> {
-        self.confirm_self_state(PeerState::RECV)?;
-        self.confirm_peer_state(src_index, PeerState::SEND)?;
-
-        let hash_of_last_event = packed_events
-            .last()
+        let hashes: BTreeSet<_> = packed_events
+            .iter()
             .map(PackedEvent::compute_hash)
-            .ok_or_else(|| Error::InvalidMessage)?;
+            .collect();
+        let pruned_parents: BTreeSet<_> = packed_events
+            .iter()
+            .flat_map(|packed_event| {
+                packed_event
+                    .self_parent()
+                    .into_iter()
+                    .chain(packed_event.other_parent())
+            })
+            .filter(|hash| !hashes.contains(hash))
+            .cloned()
+            .collect();
+        self.graph
+            .start_catch_up(pruned_parents.into_iter().collect());
+
+        let mut last_event_index = None;
         for packed_event in packed_events {
             if let Some(event) = self.unpack(packed_event)? {
                 let event_creator = event.creator();
-                let event_index = self.add_event(event)?;
-
-                // We have received an event of a peer in the message. The peer can now receive
-                // gossips from us as well.
+                let event_index = self.add_unchecked_event(event)?;
                 self.peer_list
                     .change_peer_state(event_creator, PeerState::RECV);
                 self.peer_list
                     .record_gossiped_event_by(src_index, event_index);
-
-                #[cfg(feature = "malice-detection")]
-                self.detect_accomplice(event_index)?;
+                last_event_index = Some(event_index);
             }
         }
 
-        #[cfg(feature = "malice-detection")]
-        self.detect_premature_gossip()?;
-
-        let last_event_index = self
-            .graph
-            .get_index(&hash_of_last_event)
-            .ok_or_else(|| Error::InvalidMessage)?;
-        Ok(last_event_index)
+        last_event_index.ok_or(Error::InvalidMessage)
     }
 
     fn unpack(
         &mut self,
         packed_event: PackedEvent<T, S::PublicId>,
     ) -> Result<Option<Event<S::PublicId>>> {
+        let creator_id = packed_event.creator().clone();
         if let Some(unpacked_event) = Event::unpack(packed_event, self.event_context())? {
+            // After a key rotation, the creator's events are signed with the new key from some
+            // point in its sequence on, and with the old key before. The old key is no longer
+            // valid for the events that know of the rotation's consensus.
+            if !self
+                .peer_list
+                .confirm_creator_id(&unpacked_event.event, &creator_id, &self.graph)
+            {
+                return Err(Error::SignatureFailure);
+            }
+
             if let Some((payload_key, observation_info)) = unpacked_event.observation_for_store {
-                let _ = self
-                    .observations
-                    .entry(payload_key)
-                    .or_insert_with(|| observation_info);
+                self.store_observation(payload_key, observation_info);
             }
             Ok(Some(unpacked_event.event))
         } else {
@@ -656,19 +1882,32 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             Event::new_from_observation(self_parent, observation, self.event_context())?;
 
         if let Some((payload_key, observation_info)) = observation_for_store {
-            let _ = self
-                .observations
-                .entry(payload_key)
-                .or_insert_with(|| observation_info);
+            self.store_observation(payload_key, observation_info);
         }
 
         Ok(event)
     }
 
+    fn store_observation(
+        &mut self,
+        payload_key: ObservationKey,
+        observation_info: ObservationInfo<T, S::PublicId>,
+    ) {
+        let info = self
+            .observations
+            .entry(payload_key)
+            .or_insert_with(|| observation_info);
+
+        // The observation might have been consensused already, with its information pruned since.
+        if let Some(created_by_us) = self.pruned_observations.remove(&payload_key) {
+            info.consensused = true;
+            info.created_by_us |= created_by_us;
+        }
+    }
+
     fn add_event(&mut self, event: Event<S::PublicId>) -> Result<EventIndex> {
         let our = event.creator() == PeerIndex::OUR;
-        if !our {
-            #[cfg(feature = "malice-detection")]
+        if !our && self.config.malice_detection() {
             self.detect_malice(&event)?;
         }
 
@@ -682,6 +1921,29 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             return Err(Error::InvalidEvent);
         }
 
+        let event_index = self.add_unchecked_event(event)?;
+
+        #[cfg(any(test, feature = "testing"))]
+        let ignore_process_events = self.ignore_process_events;
+        #[cfg(not(any(test, feature = "testing")))]
+        let ignore_process_events = false;
+
+        if !ignore_process_events {
+            self.process_events(event_index.topological_index())?;
+        }
+
+        Ok(event_index)
+    }
+
+    // Inserts the event into the graph and records its payload, without checking whether it can
+    // be added, nor processing it.
+    fn add_unchecked_event(&mut self, event: Event<S::PublicId>) -> Result<EventIndex> {
+        let our = event.creator() == PeerIndex::OUR;
+        // Votes for invalid payloads stay in the graph, and in the observation store so their
+        // events can still be gossiped, but never become interesting content.
+        let is_valid_payload = self
+            .event_payload(&event)
+            .map_or(true, |payload| self.is_valid_payload(payload));
         let unconsensused_payload_key = event
             .payload_key()
             .and_then(|key| self.observations.get_mut(key).map(|info| (key, info)))
@@ -689,27 +1951,52 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
                 if our {
                     info.created_by_us = true;
                 }
-                if info.consensused {
+                if info.consensused || info.observation.is_signature_share() || !is_valid_payload {
                     None
                 } else {
                     Some(*key)
                 }
             });
+        let coin_share = event
+            .payload_key()
+            .and_then(|key| self.observations.get(key))
+            .and_then(|info| match info.observation {
+                Observation::CoinShare(ref coin_share) => Some(coin_share.clone()),
+                _ => None,
+            });
+        let block_signature_share = event
+            .payload_key()
+            .and_then(|key| self.observations.get(key))
+            .and_then(|info| match info.observation {
+                Observation::BlockSignatureShare(ref share) => Some(share.clone()),
+                _ => None,
+            });
+        let creator = event.creator();
+        if self
+            .event_payload(&event)
+            .map_or(false, |payload| !payload.is_internal())
+        {
+            self.spam_stats.entry(creator).or_default().observations += 1;
+        }
 
-        let event_index = self.insert_event(event);
+        let event_index = self.insert_event(event)?;
+
+        // Counted only once the event is inserted, so each event is counted just once however many
+        // times it's gossiped to us.
+        if !is_valid_payload {
+            self.record_invalid_payload_by(creator);
+        }
 
         let _ = unconsensused_payload_key.map(|payload_key| {
             self.meta_election
                 .add_unconsensused_event(event_index, payload_key);
         });
 
-        #[cfg(any(test, feature = "testing"))]
-        let ignore_process_events = self.ignore_process_events;
-        #[cfg(not(any(test, feature = "testing")))]
-        let ignore_process_events = false;
-
-        if !ignore_process_events {
-            self.process_events(event_index.topological_index())?;
+        if let Some(coin_share) = coin_share {
+            self.add_coin_share(event_index, creator, coin_share);
+        }
+        if let Some(share) = block_signature_share {
+            self.add_block_signature_share(creator, share);
         }
 
         Ok(event_index)
@@ -717,15 +2004,16 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
 
     // Create initial event for this node and insert it into the graph. This must be called when
     // this node becomes voter.
-    fn add_initial_event(&mut self) {
+    fn add_initial_event(&mut self) -> Result<()> {
         let event = Event::new_initial(self.event_context());
-        let _ = self.insert_event(event);
+        let _ = self.insert_event(event)?;
+        Ok(())
     }
 
-    fn insert_event(&mut self, event: Event<S::PublicId>) -> EventIndex {
-        let event = self.graph.insert(event);
+    fn insert_event(&mut self, event: Event<S::PublicId>) -> Result<EventIndex> {
+        let event = self.graph.insert(event)?;
         self.peer_list.add_event(event);
-        event.event_index()
+        Ok(event.event_index())
     }
 
     fn process_events(&mut self, mut start_index: usize) -> Result<()> {
@@ -754,6 +2042,7 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         }
 
         self.create_needed_meta_event(event_index)?;
+        self.create_needed_coin_shares(event_index)?;
 
         let payload_keys = self.compute_consensus(event_index);
         if payload_keys.is_empty() {
@@ -764,18 +2053,22 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
 
         let blocks = self.create_blocks(&payload_keys)?;
         if !blocks.is_empty() {
-            self.consensused_blocks.push_back(blocks);
+            let block_group = self.new_block_group(blocks, event_index)?;
+            self.output_blocks(block_group);
         }
 
         self.mark_observations_as_consensused(&payload_keys);
 
         let peer_list_changes = payload_keys
             .iter()
-            .filter_map(|payload_key| self.handle_consensus(event_index, payload_key))
+            .flat_map(|payload_key| self.handle_consensus(event_index, payload_key))
             .collect();
+        // The voters may have changed, leaving no keys to sign the blocks held back with.
+        self.sign_blocks();
 
         self.meta_election
             .new_election(&self.graph, payload_keys, peer_list_changes);
+        self.coin_shares.next_election(self.coin_keys.as_ref());
 
         // Trigger reprocess.
         let start_index = self.meta_election.continue_consensus_start_index();
@@ -785,7 +2078,7 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
     fn output_consensus_info(&self, payload_keys: &[ObservationKey]) {
         dump_graph::to_file(dump_graph::ToFileInfo {
             owner_id: self.our_pub_id(),
-            consensus_mode: self.consensus_mode,
+            consensus_mode: self.config.consensus_mode(),
             gossip_graph: &self.graph,
             meta_election: &self.meta_election,
             peer_list: &self.peer_list,
@@ -800,7 +2093,9 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
                 .observations
                 .get(payload_key)
                 .map(|info| &info.observation);
-            info!(
+            log_at!(
+                self,
+                LogLevel::Info,
                 "{:?} got consensus on block {} with payload {:?} and payload hash {:?}",
                 self.our_pub_id(),
                 self.meta_election.consensus_history().len() + index,
@@ -824,26 +2119,199 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         }
     }
 
+    // Groups the blocks decided by the meta-election which reached consensus on the event at
+    // `event_index`.
+    fn new_block_group(
+        &self,
+        blocks: VecDeque<Block<T, S::PublicId>>,
+        event_index: EventIndex,
+    ) -> Result<BlockGroup<T, S::PublicId>> {
+        Ok(BlockGroup {
+            blocks,
+            election_index: self.meta_election.consensus_history().len(),
+            deciding_event: *self.get_known_event(event_index)?.hash(),
+        })
+    }
+
+    // Holds the blocks back until they're signed if block signatures are on, and outputs them
+    // otherwise.
+    fn output_blocks(&mut self, mut blocks: BlockGroup<T, S::PublicId>) {
+        for block in blocks.iter_mut() {
+            let index = self.next_block_index;
+            self.next_block_index += 1;
+            if self.config.chained_blocks() {
+                block.set_chain_link(ChainLink {
+                    index,
+                    previous: self.chain_tip,
+                });
+                self.chain_tip = Some(block.hash());
+            }
+
+            if !self.config.block_signatures() {
+                continue;
+            }
+            block.set_index(index);
+            if let Some(share) = self.active_coin_keys().and_then(|keys| {
+                keys.sign_block(index, block.previous_hash(), block.payload_hash())
+            }) {
+                self.pending_block_signature_shares.push(share);
+            }
+        }
+
+        if self.config.block_signatures() {
+            self.unsigned_blocks.push_back(blocks);
+            self.sign_blocks();
+        } else {
+            self.output_signed_blocks(blocks);
+        }
+    }
+
+    // Records the block signature share carried by an event created by `creator`, unless the
+    // block was output already. Shares can arrive before we reach consensus on their block.
+    fn add_block_signature_share(&mut self, creator: PeerIndex, share: BlockSignatureShare) {
+        if !self.config.block_signatures() || share.index < self.first_unsigned_block_index() {
+            return;
+        }
+        let creator_id = if let Some(peer) = self.peer_list.get(creator) {
+            peer.id().clone()
+        } else {
+            return;
+        };
+        self.block_signature_shares
+            .entry(share.index)
+            .or_default()
+            .push((creator_id, share));
+        self.sign_blocks();
+    }
+
+    // Index of the first block not output yet.
+    fn first_unsigned_block_index(&self) -> u64 {
+        self.unsigned_blocks
+            .front()
+            .and_then(|blocks| blocks.front())
+            .and_then(Block::index)
+            .unwrap_or(self.next_block_index)
+    }
+
+    // Signs the blocks held back which have enough valid shares, and outputs them in order. If
+    // there are no keys to sign them with, or if they're still not signed after the configured
+    // number of sync events of ours, outputs them unsigned unless there is no such timeout.
+    fn sign_blocks(&mut self) {
+        while let Some(mut blocks) = self.unsigned_blocks.pop_front() {
+            let has_keys = if let Some(keys) = self.active_coin_keys() {
+                for block in blocks
+                    .iter_mut()
+                    .filter(|block| block.signature().is_none())
+                {
+                    let index = block.index();
+                    let previous = block.previous_hash();
+                    let payload_hash = block.payload_hash();
+                    let shares = index
+                        .and_then(|index| self.block_signature_shares.get(&index))
+                        .into_iter()
+                        .flatten()
+                        .filter(|(_, share)| {
+                            share.previous == previous && share.payload_hash == payload_hash
+                        })
+                        .filter_map(|(creator_id, share)| {
+                            keys.verify_block_share(creator_id, share)
+                                .map(|index| (index, share.share.clone()))
+                        })
+                        .collect();
+                    if let Some(signature) = keys.combine_block_shares(&shares) {
+                        block.set_signature(signature);
+                    }
+                }
+                true
+            } else {
+                false
+            };
+
+            if blocks.iter().any(|block| block.signature().is_none()) {
+                let timed_out = self
+                    .config
+                    .block_signature_timeout()
+                    .map_or(false, |timeout| {
+                        !has_keys || self.block_signature_wait >= timeout.get()
+                    });
+                if !timed_out {
+                    self.unsigned_blocks.push_front(blocks);
+                    return;
+                }
+                if has_keys {
+                    log_at!(
+                        self,
+                        LogLevel::Warn,
+                        "{:?} outputting blocks without enough signature shares after {} sync \
+                         events. Do all the peers have block signatures on?",
+                        self.our_pub_id(),
+                        self.block_signature_wait
+                    );
+                }
+            }
+
+            self.block_signature_wait = 0;
+            self.output_signed_blocks(blocks);
+            self.block_signature_shares = self
+                .block_signature_shares
+                .split_off(&self.first_unsigned_block_index());
+        }
+    }
+
+    // Hands the blocks to the block sink if there is one, or queues them to be polled otherwise.
+    fn output_signed_blocks(&mut self, blocks: BlockGroup<T, S::PublicId>) {
+        if let Some(sink) = self.block_sink.as_mut() {
+            sink.handle_batch(blocks.into());
+        } else {
+            self.consensused_blocks.push_back(blocks);
+        }
+    }
+
     /// Handles consensus reached by us.
     fn handle_consensus(
         &mut self,
         event_index: EventIndex,
         payload_key: &ObservationKey,
-    ) -> Option<PeerListChange> {
+    ) -> Vec<PeerListChange> {
         match self
             .observations
             .get(payload_key)
             .map(|info| info.observation.clone())
         {
-            Some(Observation::Add { ref peer_id, .. }) => self.handle_add_peer(peer_id).into(),
-            Some(Observation::Remove { ref peer_id, .. }) => {
-                self.handle_remove_peer(event_index, peer_id)
+            Some(Observation::Add { ref peer_id, .. }) => vec![self.handle_add_peer(peer_id)],
+            Some(Observation::Remove { ref peer_id, .. }) => self
+                .handle_remove_peer(event_index, peer_id)
+                .into_iter()
+                .collect(),
+            Some(Observation::Reconfigure {
+                ref add,
+                ref remove,
+                ..
+            }) => self.handle_reconfigure(event_index, add, remove),
+            Some(Observation::RotateKey {
+                ref old,
+                ref new,
+                ref proof,
+            }) => {
+                self.handle_rotate_key(event_index, old, new, proof);
+                Vec::new()
             }
+            Some(Observation::ChangeWeight {
+                ref peer_id,
+                weight,
+            }) => self
+                .handle_change_weight(peer_id, weight)
+                .into_iter()
+                .collect(),
             Some(Observation::Accusation {
                 ref offender,
                 ref malice,
             }) => {
-                info!(
+                self.count_consensused_accusation(offender, malice);
+
+                log_at!(
+                    self,
+                    LogLevel::Info,
                     "{:?} removing {:?} due to consensus on accusation of malice {:?}",
                     self.our_pub_id(),
                     offender,
@@ -851,35 +2319,114 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
                 );
 
                 self.handle_remove_peer(event_index, offender)
+                    .into_iter()
+                    .collect()
             }
             Some(Observation::StartDkg(peers)) => {
                 if self.handle_dkg_start_consensus(&peers).is_none() {
-                    warn!("Not starting DKG on StartDkg consensus because of error");
+                    log_at!(
+                        self,
+                        LogLevel::Warn,
+                        "Not starting DKG on StartDkg consensus because of error"
+                    );
                 }
-                None
+                Vec::new()
             }
             Some(Observation::DkgResult { .. }) => {
                 log_or_panic!("Unexpected DkgResult consensus.");
-                None
+                Vec::new()
             }
             Some(Observation::DkgMessage(msg)) => {
-                if self.handle_dkg_message(payload_key, msg.clone()).is_none() {
-                    warn!(
+                if self
+                    .handle_dkg_message(event_index, payload_key, msg.clone())
+                    .is_none()
+                {
+                    log_at!(
+                        self,
+                        LogLevel::Warn,
                         "Ignoring DkgMessage with Error: key: {:?}, msg: {:?}",
-                        payload_key, msg
+                        payload_key,
+                        msg
                     );
                 }
-                None
+                Vec::new()
+            }
+            Some(Observation::CoinShare(_)) => {
+                log_or_panic!("Unexpected CoinShare consensus.");
+                Vec::new()
+            }
+            Some(Observation::BlockSignatureShare(_)) => {
+                log_or_panic!("Unexpected BlockSignatureShare consensus.");
+                Vec::new()
             }
-            Some(Observation::Genesis { .. }) | Some(Observation::OpaquePayload(_)) => None,
+            Some(Observation::Genesis { .. }) | Some(Observation::OpaquePayload(_)) => Vec::new(),
             None => {
                 log_or_panic!("Failed to get observation from hash.");
-                None
+                Vec::new()
+            }
+        }
+    }
+
+    // Counts the consensused accusation, and schedules a vote to remove the offender once the
+    // accusations reach the threshold for this kind of malice, if any.
+    fn count_consensused_accusation(
+        &mut self,
+        offender: &S::PublicId,
+        malice: &Malice<T, S::PublicId>,
+    ) {
+        if !malice.is_provable() {
+            return;
+        }
+
+        let kind = malice.kind();
+        let threshold = if let Some(threshold) = self.config.removal_threshold(kind) {
+            threshold.get()
+        } else {
+            return;
+        };
+        let count = {
+            let count = self
+                .consensused_accusations
+                .entry((offender.clone(), kind))
+                .or_insert(0);
+            *count += 1;
+            *count
+        };
+
+        if count == threshold && offender != self.our_pub_id() {
+            let accusation = Observation::Accusation {
+                offender: offender.clone(),
+                malice: malice.clone(),
+            };
+            self.pending_removals.push(Observation::Remove {
+                peer_id: offender.clone(),
+                related_info: serialise(&accusation),
+            });
+        }
+    }
+
+    fn vote_for_removals(&mut self) -> Result<()> {
+        let removals = mem::replace(&mut self.pending_removals, vec![]);
+        if self.confirm_self_state(PeerState::VOTE).is_err() {
+            return Ok(());
+        }
+
+        for removal in removals {
+            match self.vote_for(removal) {
+                // We might have voted for the removal already, e.g. on our user's request.
+                Ok(()) | Err(Error::DuplicateVote) => (),
+                Err(error) => return Err(error),
             }
         }
+        Ok(())
     }
 
-    fn handle_dkg_message(&mut self, payload_key: &ObservationKey, msg: DkgMessage) -> Option<()> {
+    fn handle_dkg_message(
+        &mut self,
+        event_index: EventIndex,
+        payload_key: &ObservationKey,
+        msg: DkgMessage,
+    ) -> Option<()> {
         let creator_id = self.peer_list.get(payload_key.peer_index()?)?.id().clone();
 
         match msg {
@@ -887,7 +2434,7 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
                 self.handle_dkg_message_part(&creator_id, key_gen_id, part)
             }
             DkgMessage::Ack { key_gen_id, ack } => {
-                self.handle_dkg_message_ack(&creator_id, key_gen_id, ack)
+                self.handle_dkg_message_ack(event_index, &creator_id, key_gen_id, ack)
             }
         }
     }
@@ -899,10 +2446,19 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         part: Part,
     ) -> Option<()> {
         if let Some(key_gen) = &mut self.key_gen.get_mut(&key_gen_id) {
-            let part_result = key_gen
-                .handle_part(self.peer_list.our_id(), creator_id, part.clone())
-                .map_err(|err| warn!("handle_dkg_message_part error: {:?}", err))
-                .ok()?;
+            let part_result =
+                match key_gen.handle_part(self.peer_list.our_id(), creator_id, part.clone()) {
+                    Ok(part_result) => part_result,
+                    Err(err) => {
+                        log_at!(
+                            self,
+                            LogLevel::Warn,
+                            "handle_dkg_message_part error: {:?}",
+                            err
+                        );
+                        return None;
+                    }
+                };
 
             match part_result {
                 PartOutcome::Valid(Some(ack)) => {
@@ -911,9 +2467,12 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
                 }
                 PartOutcome::Valid(None) => (),
                 PartOutcome::Invalid(fault) => {
-                    warn!(
+                    log_at!(
+                        self,
+                        LogLevel::Warn,
                         "An invalid Part was detected from {:?} with fault {:?}",
-                        creator_id, fault
+                        creator_id,
+                        fault
                     );
                 }
             }
@@ -924,36 +2483,67 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
 
     fn handle_dkg_message_ack(
         &mut self,
+        event_index: EventIndex,
         creator_id: &S::PublicId,
         key_gen_id: KeyGenId,
         ack: Ack,
     ) -> Option<()> {
         if let Some(key_gen) = &mut self.key_gen.get_mut(&key_gen_id) {
-            let ack_result = key_gen
-                .handle_ack(&self.peer_list.our_id(), creator_id, ack.clone())
-                .map_err(|err| warn!("handle_dkg_message_ack error: {:?}", err))
-                .ok()?;
+            let ack_result =
+                match key_gen.handle_ack(&self.peer_list.our_id(), creator_id, ack.clone()) {
+                    Ok(ack_result) => ack_result,
+                    Err(err) => {
+                        log_at!(
+                            self,
+                            LogLevel::Warn,
+                            "handle_dkg_message_ack error: {:?}",
+                            err
+                        );
+                        return None;
+                    }
+                };
 
             match ack_result {
                 AckOutcome::Valid => {
                     if key_gen.is_ready() {
-                        debug!(
+                        log_at!(
+                            self,
+                            LogLevel::Debug,
                             "{:?}: key_gen for block number {} is ready.",
                             self.peer_list.our_pub_id(),
                             key_gen_id
                         );
-                        let dkg_result = key_gen.generate().ok()?;
-
-                        self.consensused_blocks.push_back(BlockGroup(
-                            iter::once(Block::new_dkg_block(dkg_result)).collect(),
-                        ));
+                        let dkg_result = match key_gen.generate() {
+                            Ok(dkg_result) => dkg_result,
+                            Err(error) => {
+                                let _ = self.key_gen.remove(&key_gen_id);
+                                self.fail_key_gen(
+                                    key_gen_id,
+                                    DkgFailure::KeyGen(error.to_string()),
+                                );
+                                return None;
+                            }
+                        };
+                        let coin_keys = CoinKeys::new(dkg_result.0.clone(), &dkg_result.1);
+
+                        let block_group = self
+                            .new_block_group(
+                                iter::once(Block::new_dkg_block(dkg_result)).collect(),
+                                event_index,
+                            )
+                            .ok()?;
+                        self.output_blocks(block_group);
                         let _ = self.key_gen.remove(&key_gen_id);
+                        self.update_coin_keys(coin_keys);
                     }
                 }
                 AckOutcome::Invalid(fault) => {
-                    warn!(
+                    log_at!(
+                        self,
+                        LogLevel::Warn,
                         "An invalid Ack was detected from {:?} with fault {:?}",
-                        creator_id, fault
+                        creator_id,
+                        fault
                     );
                 }
             }
@@ -963,7 +2553,7 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
 
     // This function must be called on consensus on a `StartDkg` observation.
     fn handle_dkg_start_consensus(&mut self, peers: &BTreeSet<S::PublicId>) -> Option<()> {
-        let state = if self.new_peer_can_recv(self.our_pub_id()) {
+        let state = if self.new_peers_can_recv(&[self.our_pub_id()]) {
             PeerState::DKG | PeerState::SEND | PeerState::RECV
         } else {
             PeerState::DKG | PeerState::SEND
@@ -981,20 +2571,26 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             }
         }
 
-        let threshold = dkg_threshold(peers.len());
-        let (key_gen, part) = KeyGen::new(
+        // Take the ID even if we fail to start, so we keep numbering the sessions like our peers.
+        let key_gen_id = self.key_gen_next_id;
+        self.key_gen_next_id += 1;
+
+        let threshold = self
+            .config
+            .dkg_policy()
+            .threshold(peers.len(), self.config.quorum_policy());
+        let (key_gen, part) = match KeyGen::new(
             self.peer_list.our_id(),
             peers.clone(),
             threshold,
             &mut self.secure_rng,
-        )
-        .map_err(|error| {
-            error!("Vote for new DKG Error: {}", error);
-        })
-        .ok()?;
-
-        let key_gen_id = self.key_gen_next_id;
-        self.key_gen_next_id += 1;
+        ) {
+            Ok(result) => result,
+            Err(error) => {
+                self.fail_key_gen(key_gen_id, DkgFailure::KeyGen(error.to_string()));
+                return None;
+            }
+        };
 
         if let Some(part) = part {
             self.pending_dkg_msgs
@@ -1004,8 +2600,53 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         Some(())
     }
 
+    fn fail_key_gen(&mut self, key_gen_id: KeyGenId, failure: DkgFailure) {
+        let level = if failure == DkgFailure::Cancelled {
+            LogLevel::Info
+        } else {
+            LogLevel::Error
+        };
+        log_at!(
+            self,
+            level,
+            "{:?} DKG {} failed: {}",
+            self.our_pub_id(),
+            key_gen_id,
+            failure
+        );
+        let _ = self.failed_key_gens.insert(key_gen_id, failure);
+    }
+
     fn handle_add_peer(&mut self, peer_id: &S::PublicId) -> PeerListChange {
-        let state = if self.new_peer_can_recv(peer_id) {
+        let can_recv = self.new_peers_can_recv(&[peer_id]);
+        self.add_voter(peer_id, can_recv)
+    }
+
+    // Applies the whole membership delta at once: the removals first, then the additions, so the
+    // meta-election restarts only once, with the final set of voters.
+    fn handle_reconfigure(
+        &mut self,
+        event_index: EventIndex,
+        add: &BTreeSet<S::PublicId>,
+        remove: &BTreeSet<S::PublicId>,
+    ) -> Vec<PeerListChange> {
+        let mut changes: Vec<_> = remove
+            .iter()
+            .filter_map(|peer_id| self.handle_remove_peer(event_index, peer_id))
+            .collect();
+
+        let new_peers = add.iter().collect_vec();
+        let can_recv = self.new_peers_can_recv(&new_peers);
+        changes.extend(
+            new_peers
+                .into_iter()
+                .map(|peer_id| self.add_voter(peer_id, can_recv)),
+        );
+        changes
+    }
+
+    fn add_voter(&mut self, peer_id: &S::PublicId, can_recv: bool) -> PeerListChange {
+        let state = if can_recv {
             PeerState::VOTE | PeerState::SEND | PeerState::RECV
         } else {
             PeerState::VOTE | PeerState::SEND
@@ -1015,7 +2656,7 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         PeerListChange::Add(peer_index)
     }
 
-    fn new_peer_can_recv(&self, peer_id: &S::PublicId) -> bool {
+    fn new_peers_can_recv(&self, peer_ids: &[&S::PublicId]) -> bool {
         // - If we are already full member of the section, we can start sending gossips to
         //   the new peer from this moment.
         // - If we are the new peer, we must wait for the other members to send gossips to
@@ -1030,8 +2671,8 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
                 peer.state().can_vote() &&
                         // Excluding us.
                         *peer_index != PeerIndex::OUR &&
-                        // Excluding the peer being added.
-                        peer.id() != peer_id
+                        // Excluding the peers being added.
+                        !peer_ids.contains(&peer.id())
             })
             .all(|(_, peer)| {
                 // Peers that can receive, which implies they've already sent us at least
@@ -1049,7 +2690,15 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         };
 
         if peer_index == PeerIndex::OUR && self.peer_list.our_events().next().is_none() {
-            self.add_initial_event();
+            if let Err(error) = self.add_initial_event() {
+                log_at!(
+                    self,
+                    LogLevel::Error,
+                    "{:?} failed to add its initial event: {:?}",
+                    self.our_pub_id(),
+                    error
+                );
+            }
         }
 
         peer_index
@@ -1066,6 +2715,99 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         })
     }
 
+    // Rebinds the peer known by `old` to `new`. It stays the same voter, so the peer list changes
+    // don't affect the meta-election.
+    fn handle_rotate_key(
+        &mut self,
+        event_index: EventIndex,
+        old: &S::PublicId,
+        new: &S::PublicId,
+        proof: &KeyRotationProof<S::PublicId>,
+    ) {
+        let peer_index = match self.peer_list.get_index(old) {
+            Some(peer_index)
+                if self.peer_list.get(peer_index).map(Peer::id) == Some(old)
+                    && proof.is_valid(old, new)
+                    && !self.peer_list.contains(new) =>
+            {
+                peer_index
+            }
+            _ => {
+                log_at!(
+                    self,
+                    LogLevel::Warn,
+                    "{:?} ignoring consensused invalid rotation of {:?} to {:?}",
+                    self.our_pub_id(),
+                    old,
+                    new
+                );
+                return;
+            }
+        };
+
+        if peer_index != PeerIndex::OUR {
+            self.peer_list
+                .rotate_peer_key(peer_index, new.clone(), event_index);
+            return;
+        }
+
+        match self.pending_key_rotation.take() {
+            Some(new_id) if new_id.public_id() == new => {
+                let first_index = self
+                    .peer_list
+                    .our_events()
+                    .next_back()
+                    .and_then(|event_index| self.graph.get(event_index))
+                    .map_or(0, |event| event.index_by_creator() + 1);
+                self.peer_list
+                    .rotate_our_key(new_id, event_index, first_index);
+                log_at!(
+                    self,
+                    LogLevel::Info,
+                    "{:?} rotated its key from {:?}",
+                    self.our_pub_id(),
+                    old
+                );
+            }
+            pending => {
+                self.pending_key_rotation = pending;
+                if self.restored_key_rotation.as_ref() == Some(new) {
+                    self.restored_key_rotation = None;
+                }
+                log_at!(
+                    self,
+                    LogLevel::Error,
+                    "{:?} doesn't hold the secret key to rotate to {:?}, so keeps the current one",
+                    self.our_pub_id(),
+                    new
+                );
+            }
+        }
+    }
+
+    fn handle_change_weight(
+        &mut self,
+        peer_id: &S::PublicId,
+        weight: usize,
+    ) -> Option<PeerListChange> {
+        let peer_index = self.peer_list.get_index(peer_id)?;
+        if !is_valid_weight(weight) || !self.peer_list.peer_state(peer_index).can_vote() {
+            log_at!(
+                self,
+                LogLevel::Warn,
+                "{:?} ignoring consensused weight {} of {:?}, which is out of bounds or not held \
+                 by a voter",
+                self.our_pub_id(),
+                weight,
+                peer_id
+            );
+            return None;
+        }
+
+        // The meta-election holds the weights, as of its start.
+        Some(PeerListChange::ChangeWeight(peer_index, weight))
+    }
+
     fn create_needed_meta_event(&mut self, event_index: EventIndex) -> Result<()> {
         let event = get_known_event(self.our_pub_id(), &self.graph, event_index)?;
 
@@ -1075,7 +2817,9 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             return Ok(());
         }
 
-        trace!(
+        log_at!(
+            self,
+            LogLevel::Trace,
             "{:?} creating a meta-event for event {:?}",
             self.our_pub_id(),
             event
@@ -1143,37 +2887,45 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
     ) -> bool {
         match payload_key.consensus_mode() {
             ConsensusMode::Single => {
-                let num_ancestor_peers =
-                    self.num_creators_of_ancestors(peers_that_can_vote, &*builder.event());
-                is_more_than_two_thirds(num_ancestor_peers, peers_that_can_vote.len())
-                    && self.has_ancestor_carrying_payload(builder.event(), payload_key)
+                let ancestor_peers_weight =
+                    self.weight_of_creators_of_ancestors(peers_that_can_vote, &*builder.event());
+                self.config.quorum_policy().is_quorum(
+                    ancestor_peers_weight,
+                    self.meta_election.weight_of(peers_that_can_vote),
+                ) && self.has_ancestor_carrying_payload(builder.event(), payload_key)
             }
             ConsensusMode::Supermajority => {
-                let num_peers_that_did_vote = self.num_creators_of_ancestors_carrying_payload(
-                    peers_that_can_vote,
-                    builder.event(),
-                    payload_key,
-                );
-                is_more_than_two_thirds(num_peers_that_did_vote, peers_that_can_vote.len())
+                let peers_that_did_vote_weight = self
+                    .weight_of_creators_of_ancestors_carrying_payload(
+                        peers_that_can_vote,
+                        builder.event(),
+                        payload_key,
+                    );
+                self.config.quorum_policy().is_quorum(
+                    peers_that_did_vote_weight,
+                    self.meta_election.weight_of(peers_that_can_vote),
+                )
             }
         }
     }
 
-    // Number of unique peers that created at least one ancestor of the given event.
-    fn num_creators_of_ancestors(
+    // Total weight of the unique peers that created at least one ancestor of the given event.
+    fn weight_of_creators_of_ancestors(
         &self,
         peers_that_can_vote: &PeerIndexSet,
         event: &Event<S::PublicId>,
     ) -> usize {
-        event
-            .last_ancestors()
-            .filter(|(peer_index, _)| peers_that_can_vote.contains(*peer_index))
-            .count()
+        self.meta_election.weight_of(
+            event
+                .last_ancestors()
+                .map(|(peer_index, _)| peer_index)
+                .filter(|peer_index| peers_that_can_vote.contains(*peer_index)),
+        )
     }
 
-    // Number of unique peers that created at least one ancestor of the given event that carries the
-    // given payload.
-    fn num_creators_of_ancestors_carrying_payload(
+    // Total weight of the unique peers that created at least one ancestor of the given event that
+    // carries the given payload.
+    fn weight_of_creators_of_ancestors_carrying_payload(
         &self,
         peers_that_can_vote: &PeerIndexSet,
         event: IndexedEventRef<S::PublicId>,
@@ -1181,14 +2933,12 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
     ) -> usize {
         let unconsensused_events = self.unconsensused_events(Some(payload_key)).collect_vec();
 
-        peers_that_can_vote
-            .iter()
-            .filter(|peer_index| {
+        self.meta_election
+            .weight_of(peers_that_can_vote.iter().filter(|peer_index| {
                 unconsensused_events.iter().any(|that_event| {
                     that_event.creator() == *peer_index && event.is_descendant_of(*that_event)
                 })
-            })
-            .count()
+            }))
     }
 
     // At least one ancestor of the given event carries the given payload.
@@ -1210,7 +2960,7 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             return;
         }
 
-        let voter_count = self.voter_count();
+        let voters_weight = self.meta_election.voters_weight();
         let observees: PeerIndexSet = self
             .meta_election
             .interesting_events()
@@ -1225,7 +2975,11 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             })
             .collect();
 
-        if is_more_than_two_thirds(observees.len(), voter_count) {
+        if self
+            .config
+            .quorum_policy()
+            .is_quorum(self.meta_election.weight_of(&observees), voters_weight)
+        {
             builder.set_observer(Observer::This(observees));
         } else {
             builder.set_observer(Observer::None);
@@ -1255,7 +3009,8 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         }
 
         let voters = self.voters();
-        let voters_len = match NonZeroUsize::new(voters.len()) {
+        let quorum_policy = self.config.quorum_policy();
+        let voters_weight = match NonZeroUsize::new(self.meta_election.voters_weight()) {
             Some(num) => num,
             None => {
                 log_or_panic!("{:?} has no voters", self.our_pub_id());
@@ -1268,6 +3023,7 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             // This event wasn't created by a valid voter. It has no meta_votes.
             return Ok(());
         }
+        let weight = self.meta_election.weight(builder.event().creator());
 
         let ancestors_meta_votes =
             self.other_voting_ancestors_meta_votes(&voters, &builder.event());
@@ -1286,15 +3042,27 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
                 .into_iter()
                 .map(|(peer_index, parent_votes)| {
                     let other_votes = Self::peer_meta_votes(&ancestors_meta_votes, peer_index);
-                    let temp_votes = MetaVote::next_temp(parent_votes, &other_votes, voters_len);
+                    let temp_votes = MetaVote::next_temp(
+                        parent_votes,
+                        weight,
+                        &other_votes,
+                        voters_weight,
+                        quorum_policy,
+                    );
 
                     (peer_index, temp_votes)
                 })
                 .collect();
 
             for (peer_index, temp_votes) in &temp_votes {
-                let coin_tosses = self.toss_coins(&voters, peer_index, temp_votes)?;
-                let final_meta_votes = MetaVote::next_final(temp_votes, &coin_tosses, voters_len);
+                let coin_tosses = self.toss_coins(peer_index, temp_votes, builder.event())?;
+                let final_meta_votes = MetaVote::next_final(
+                    temp_votes,
+                    weight,
+                    &coin_tosses,
+                    voters_weight,
+                    quorum_policy,
+                );
 
                 builder.add_meta_votes(peer_index, final_meta_votes);
             }
@@ -1305,14 +3073,22 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
                     let other_votes = Self::peer_meta_votes(&ancestors_meta_votes, peer_index);
                     let initial_estimate = builder.has_observee(peer_index);
 
-                    MetaVote::new_for_observer(initial_estimate, &other_votes, voters_len)
+                    MetaVote::new_for_observer(
+                        initial_estimate,
+                        weight,
+                        &other_votes,
+                        voters_weight,
+                        quorum_policy,
+                    )
                 };
 
                 builder.add_meta_votes(peer_index, new_meta_votes);
             }
         }
 
-        trace!(
+        log_at!(
+            self,
+            LogLevel::Trace,
             "{:?} has set the meta votes for {:?}",
             self.our_pub_id(),
             *builder.event(),
@@ -1323,29 +3099,151 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
 
     fn toss_coins(
         &self,
-        voters: &PeerIndexSet,
         peer_index: PeerIndex,
         temp_votes: &[MetaVote],
+        event: IndexedEventRef<S::PublicId>,
     ) -> Result<BTreeMap<usize, bool>> {
         let mut coin_tosses = BTreeMap::new();
         for temp_vote in temp_votes {
-            if let Some(coin) = self.toss_coin(voters, peer_index, temp_vote)? {
+            if let Some(coin) = self.toss_coin(peer_index, temp_vote.round, event)? {
                 let _ = coin_tosses.insert(temp_vote.round, coin);
             }
         }
         Ok(coin_tosses)
     }
 
+    // Tosses the common coin of `round`, for the meta-votes about `peer_index` of `event`. Returns
+    // `None` if the toss can't be done yet.
     fn toss_coin(
         &self,
-        _voters: &PeerIndexSet,
-        _peer_index: PeerIndex,
-        temp_vote: &MetaVote,
+        peer_index: PeerIndex,
+        round: usize,
+        event: IndexedEventRef<S::PublicId>,
     ) -> Result<Option<bool>> {
-        // FIXME: This coin is common, but also predictable. It means that we can't guarentee
-        // Liveness in the presence of a sophisticated adversary which controls the scheduler.
-        // It is only a temporary measure until we implement an actual common coin.
-        Ok(Some(temp_vote.round % 2 != 0))
+        let keys = if let Some(keys) = self.active_coin_keys() {
+            keys
+        } else {
+            // Until the voters have run a DKG among themselves, fall back to a coin which is
+            // common, but also predictable. It means that we can't guarantee Liveness in the
+            // presence of a sophisticated adversary which controls the scheduler.
+            return Ok(Some(round % 2 != 0));
+        };
+
+        // The result is known once `event` can see enough shares of the toss, so that every peer
+        // agrees on which of its events toss the coin.
+        let coin_id = self.coin_id(peer_index, round)?;
+        Ok(self.coin_shares.toss(keys, &coin_id, |share_index| {
+            self.graph
+                .get(share_index)
+                .map_or(false, |share_event| event.is_descendant_of(share_event))
+        }))
+    }
+
+    fn coin_id(&self, peer_index: PeerIndex, round: usize) -> Result<CoinId<S::PublicId>> {
+        Ok(CoinId {
+            election: self.coin_shares.election(),
+            peer_id: self
+                .peer_list
+                .get(peer_index)
+                .ok_or(Error::UnknownPeer)?
+                .id()
+                .clone(),
+            round,
+        })
+    }
+
+    // Returns the keys of the common coin if they were generated by exactly the current voters.
+    fn active_coin_keys(&self) -> Option<&CoinKeys<S::PublicId>> {
+        self.coin_keys.as_ref().filter(|keys| {
+            keys.participants().len() == self.voter_count()
+                && keys.participants().iter().all(|peer_id| {
+                    self.peer_list
+                        .get_index(peer_id)
+                        .map_or(false, |peer_index| self.voters().contains(peer_index))
+                })
+        })
+    }
+
+    // This function must be called when a DKG completes. The common coin switches to the generated
+    // keys only if the DKG was among exactly the current voters, as otherwise their threshold
+    // wouldn't match the voters'.
+    fn update_coin_keys(&mut self, coin_keys: CoinKeys<S::PublicId>) {
+        let previous_keys = mem::replace(&mut self.coin_keys, Some(coin_keys));
+        if self.active_coin_keys().is_some() {
+            self.coin_shares.reset(self.coin_keys.as_ref());
+            self.resign_blocks();
+        } else {
+            self.coin_keys = previous_keys;
+        }
+    }
+
+    // Creates our shares of the blocks held back with the new keys, as the shares of the previous
+    // ones can't be combined into a signature valid for them.
+    fn resign_blocks(&mut self) {
+        let shares = if let Some(keys) = self.active_coin_keys() {
+            self.unsigned_blocks
+                .iter()
+                .flatten()
+                .filter_map(|block| {
+                    keys.sign_block(block.index()?, block.previous_hash(), block.payload_hash())
+                })
+                .collect_vec()
+        } else {
+            return;
+        };
+        self.pending_block_signature_shares.extend(shares);
+    }
+
+    // Records the common coin share carried by the event at `event_index`.
+    fn add_coin_share(
+        &mut self,
+        event_index: EventIndex,
+        creator: PeerIndex,
+        coin_share: CoinShare<S::PublicId>,
+    ) {
+        if let Some(creator_id) = self.peer_list.get(creator).map(|peer| peer.id().clone()) {
+            self.coin_shares.add(
+                self.coin_keys.as_ref(),
+                event_index,
+                &creator_id,
+                coin_share,
+            );
+        }
+    }
+
+    // Creates our shares of the coin tosses the meta-votes of our event at `event_index` may need.
+    fn create_needed_coin_shares(&mut self, event_index: EventIndex) -> Result<()> {
+        if self.get_known_event(event_index)?.creator() != PeerIndex::OUR
+            || self.active_coin_keys().is_none()
+        {
+            return Ok(());
+        }
+
+        let coin_ids =
+            if let Some(meta_votes) = self.meta_election.populated_meta_votes(event_index) {
+                meta_votes
+                    .iter()
+                    .filter_map(|(peer_index, meta_votes)| {
+                        meta_votes
+                            .last()
+                            .filter(|meta_vote| meta_vote.awaits_coin_toss())
+                            .map(|meta_vote| self.coin_id(peer_index, meta_vote.round))
+                    })
+                    .collect::<Result<Vec<_>>>()?
+            } else {
+                return Ok(());
+            };
+
+        for coin_id in coin_ids {
+            if !self.coin_shares.add_ours(coin_id.clone()) {
+                continue;
+            }
+            if let Some(coin_share) = self.coin_keys.as_ref().and_then(|keys| keys.sign(coin_id)) {
+                self.pending_coin_shares.push(coin_share);
+            }
+        }
+
+        Ok(())
     }
 
     // Returns all the meta votes from the event's voting ancestors except the event's creator.
@@ -1353,7 +3251,7 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         &self,
         voters: &PeerIndexSet,
         event: &Event<S::PublicId>,
-    ) -> Vec<&PeerIndexMap<Vec<MetaVote>>> {
+    ) -> Vec<(&PeerIndexMap<Vec<MetaVote>>, usize)> {
         voters
             .iter()
             .filter(|voter_index| *voter_index != event.creator())
@@ -1367,19 +3265,23 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
                             .next()?;
                         self.meta_election.populated_meta_votes(event_index)
                     })
+                    .map(|meta_votes| (meta_votes, self.meta_election.weight(creator)))
             })
             .collect()
     }
 
-    // Collect the vectors of meta votes for the peer
+    // Collect the vectors of meta votes for the peer, along with the weights of their creators
     fn peer_meta_votes<'a>(
-        meta_votes_maps: &'a [&PeerIndexMap<Vec<MetaVote>>],
+        meta_votes_maps: &'a [(&PeerIndexMap<Vec<MetaVote>>, usize)],
         peer_index: PeerIndex,
-    ) -> Vec<&'a [MetaVote]> {
+    ) -> Vec<(&'a [MetaVote], usize)> {
         meta_votes_maps
             .iter()
-            .filter_map(|meta_votes| meta_votes.get(peer_index))
-            .map(Vec::as_slice)
+            .filter_map(|(meta_votes, weight)| {
+                meta_votes
+                    .get(peer_index)
+                    .map(|votes| (votes.as_slice(), *weight))
+            })
             .collect()
     }
 
@@ -1388,6 +3290,29 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         self.meta_election.voters()
     }
 
+    // Keys of the consensused observations, in order, with the creators identified by their IDs so
+    // that they're the same for every peer.
+    fn consensus_history_ids(&self) -> Vec<(ObservationHash, Option<S::PublicId>)> {
+        self.meta_election
+            .consensus_history()
+            .iter()
+            .map(|key| {
+                let creator = match *key {
+                    ObservationKey::Single(_, peer_index) => {
+                        self.peer_list.get(peer_index).map(|peer| peer.id().clone())
+                    }
+                    ObservationKey::Supermajority(_) => None,
+                };
+                (*key.hash(), creator)
+            })
+            .collect()
+    }
+
+    // Hash of the keys of the consensused observations, in order.
+    fn consensus_history_hash(&self) -> Hash {
+        section_summary::consensus_history_hash(&self.consensus_history_ids())
+    }
+
     // Number of voters for the given meta-election.
     fn voter_count(&self) -> usize {
         self.meta_election.voters().len()
@@ -1446,17 +3371,22 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             .into_iter()
             .flat_map(|(peer_index, decision)| {
                 if decision {
+                    let weight = self.meta_election.weight(peer_index);
                     match self.meta_election.interesting_content_by(peer_index) {
-                        Some(content) => content.iter().enumerate().collect_vec(),
+                        Some(content) => content
+                            .iter()
+                            .enumerate()
+                            .map(|(idx, payload_key)| (idx, payload_key, weight))
+                            .collect_vec(),
                         None => Vec::new(),
                     }
                 } else {
                     Vec::new()
                 }
             })
-            .fold(BTreeMap::new(), |mut map, (idx, payload_key)| {
-                let (count, min_index) = map.entry(payload_key.clone()).or_insert((0, idx));
-                *count += 1;
+            .fold(BTreeMap::new(), |mut map, (idx, payload_key, weight)| {
+                let (count, min_index) = map.entry(payload_key.clone()).or_insert((0usize, idx));
+                *count = count.saturating_add(weight);
                 *min_index = std::cmp::min(*min_index, idx);
                 map
             });
@@ -1475,9 +3405,12 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             .collect()
     }
 
-    fn create_blocks(&self, payload_keys: &[ObservationKey]) -> Result<BlockGroup<T, S::PublicId>> {
+    fn create_blocks(
+        &self,
+        payload_keys: &[ObservationKey],
+    ) -> Result<VecDeque<Block<T, S::PublicId>>> {
         let voters = self.voters();
-        let blocks: Result<VecDeque<_>> = payload_keys
+        payload_keys
             .iter()
             .map(|payload_key| {
                 let votes = self
@@ -1486,7 +3419,10 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
                     .filter(|event| voters.contains(event.creator()))
                     .filter_map(|event| {
                         let (vote, key) = event.vote_and_payload_key(&self.observations)?;
-                        let creator_id = self.peer_list.get(event.creator()).map(Peer::id)?;
+                        let creator_id = self
+                            .peer_list
+                            .get(event.creator())
+                            .map(|peer| peer.id_at(event.index_by_creator()))?;
                         Some((key, vote, creator_id))
                     })
                     .map(|(_, vote, creator_id)| (creator_id.clone(), vote.clone()))
@@ -1502,23 +3438,23 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
                     !block.payload().is_internal()
                 }
             })
-            .collect();
-
-        Ok(BlockGroup(blocks?))
+            .collect()
     }
 
-    // Returns the number of peers that created events which are seen by event X (descendant) and
-    // see event Y (ancestor). This means number of peers through which there is a directed path
-    // between x and y, excluding peers contains fork.
-    fn num_peers_created_events_seen_by_x_that_can_see_y(
+    // Returns the total weight of the peers that created events which are seen by event X
+    // (descendant) and see event Y (ancestor). These are the peers through which there is a
+    // directed path between x and y, excluding peers contains fork.
+    fn weight_of_peers_created_events_seen_by_x_that_can_see_y(
         &self,
         x: &Event<S::PublicId>,
         y: &Event<S::PublicId>,
     ) -> usize {
-        x.last_ancestors()
+        let peers = x
+            .last_ancestors()
             .filter(|(peer_index, event_index)| {
+                // The event might have been pruned, in which case it can't see `y`.
                 for event_idx in self.peer_list.events_by_index(*peer_index, *event_index) {
-                    if let Ok(event) = self.get_known_event(event_idx) {
+                    if let Some(event) = self.graph.get(event_idx) {
                         if x.sees(event) && event.sees(y) {
                             return true;
                         }
@@ -1526,7 +3462,8 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
                 }
                 false
             })
-            .count()
+            .map(|(peer_index, _)| peer_index);
+        self.meta_election.weight_of(peers)
     }
 
     // Returns whether event X can strongly see the event Y during the evaluation of the given
@@ -1536,9 +3473,9 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         A: AsRef<Event<S::PublicId>>,
         B: AsRef<Event<S::PublicId>>,
     {
-        is_more_than_two_thirds(
-            self.num_peers_created_events_seen_by_x_that_can_see_y(x.as_ref(), y.as_ref()),
-            self.voter_count(),
+        self.config.quorum_policy().is_quorum(
+            self.weight_of_peers_created_events_seen_by_x_that_can_see_y(x.as_ref(), y.as_ref()),
+            self.meta_election.voters_weight(),
         )
     }
 
@@ -1560,16 +3497,18 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             Event::new_from_response(self_parent, other_parent, self.event_context())?
         };
 
-        #[cfg(feature = "malice-detection")]
+        if self.config.malice_detection()
+            && !self.graph.is_valid_sync_event(&event).unwrap_or(false)
         {
-            if !self.graph.is_valid_sync_event(&event).unwrap_or(false) {
-                // The message we're handling is invalid, since it doesn't allow us to create our
-                // sync event so that it follows the `Requesting -> Request -> Response` pattern.
-                return Err(Error::InvalidMessage);
-            }
+            // The message we're handling is invalid, since it doesn't allow us to create our sync
+            // event so that it follows the `Requesting -> Request -> Response` pattern.
+            return Err(Error::InvalidMessage);
         }
 
         let _ = self.add_event(event)?;
+        self.detect_unresponsive_peers();
+        self.advance_spam_window();
+        self.advance_block_signature_wait();
         Ok(())
     }
 
@@ -1584,9 +3523,40 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         self.process_or_queue_pending_event(PendingEvent::DkgMessage { msg })
     }
 
+    fn create_coin_share_events(&mut self) -> Result<()> {
+        for coin_share in mem::replace(&mut self.pending_coin_shares, vec![]) {
+            self.process_or_queue_pending_event(PendingEvent::CoinShare { coin_share })?;
+        }
+        Ok(())
+    }
+
+    fn add_coin_share_event(&mut self, coin_share: CoinShare<S::PublicId>) -> Result<()> {
+        let event = self.new_event_from_observation(
+            self.our_last_event_index()?,
+            Observation::CoinShare(coin_share),
+        )?;
+        let _ = self.add_event(event)?;
+        Ok(())
+    }
+
+    fn create_block_signature_share_events(&mut self) -> Result<()> {
+        for share in mem::replace(&mut self.pending_block_signature_shares, vec![]) {
+            self.process_or_queue_pending_event(PendingEvent::BlockSignatureShare { share })?;
+        }
+        Ok(())
+    }
+
+    fn add_block_signature_share_event(&mut self, share: BlockSignatureShare) -> Result<()> {
+        let event = self.new_event_from_observation(
+            self.our_last_event_index()?,
+            Observation::BlockSignatureShare(share),
+        )?;
+        let _ = self.add_event(event)?;
+        Ok(())
+    }
+
     fn add_dkg_event(&mut self, msg: DkgMessage) -> Result<()> {
         // TODO: Like for Accusation, handle DkgMessage that should not be published yet.
-        // (Only for "malice-detection" enabled)
         let event = self.new_event_from_observation(
             self.our_last_event_index()?,
             Observation::DkgMessage(msg),
@@ -1598,7 +3568,21 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
     // Returns an iterator over `self.events` which will yield all the events we think `peer_id`
     // doesn't yet know about.  We should already have checked that we know `peer_id` and that we
     // have recorded at least one event from this peer before calling this function.
+    #[cfg(any(test, feature = "testing"))]
     fn events_to_gossip_to_peer(&self, peer_index: PeerIndex) -> Result<Vec<&Event<S::PublicId>>> {
+        Ok(self
+            .event_indices_to_gossip_to_peer(peer_index)?
+            .into_iter()
+            .filter_map(|index| self.graph.get(index))
+            .map(|event| event.inner())
+            .collect())
+    }
+
+    // Like `events_to_gossip_to_peer`, but returns the indices of the events, in topological order.
+    fn event_indices_to_gossip_to_peer(
+        &self,
+        peer_index: PeerIndex,
+    ) -> Result<VecDeque<EventIndex>> {
         let last_event = if let Some(event_index) = self.peer_list.last_event(peer_index) {
             self.get_known_event(event_index)?
         } else {
@@ -1619,7 +3603,7 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             .graph
             .iter()
             .filter(|event| inclusion_list[event.topological_index()])
-            .map(|event| event.inner())
+            .map(|event| event.event_index())
             .collect())
     }
 
@@ -1659,7 +3643,10 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
                 ..
             } => self.add_sync_event(is_request, other_parent),
             PendingEvent::DkgMessage { msg } => self.add_dkg_event(msg),
-            #[cfg(feature = "malice-detection")]
+            PendingEvent::CoinShare { coin_share } => self.add_coin_share_event(coin_share),
+            PendingEvent::BlockSignatureShare { share } => {
+                self.add_block_signature_share_event(share)
+            }
             PendingEvent::Accusation {
                 offender,
                 malice,
@@ -1673,11 +3660,10 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             graph: &self.graph,
             peer_list: &self.peer_list,
             observations: &self.observations,
-            consensus_mode: self.consensus_mode,
+            consensus_mode: self.config.consensus_mode(),
         }
     }
 
-    #[cfg(any(all(test, feature = "mock"), feature = "malice-detection"))]
     fn event_payload<'a>(
         &'a self,
         event: &Event<S::PublicId>,
@@ -1688,7 +3674,6 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             .map(|info| &info.observation)
     }
 
-    #[cfg(any(all(test, feature = "mock"), feature = "malice-detection"))]
     fn event_creator_id<'a>(&'a self, event: &Event<S::PublicId>) -> Result<&'a S::PublicId> {
         self.peer_list
             .get(event.creator())
@@ -1704,7 +3689,6 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
     }
 }
 
-#[cfg(feature = "malice-detection")]
 impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
     fn create_accusation_events(&mut self, other_parent: EventIndex) -> Result<()> {
         let pending_accusations = mem::replace(&mut self.pending_accusations, vec![]);
@@ -1772,17 +3756,46 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
 
     fn detect_malice(&mut self, event: &Event<S::PublicId>) -> Result<()> {
         // NOTE: `detect_incorrect_genesis` must come first.
-        self.detect_incorrect_genesis(event)?;
+        if self.config.detects_malice(MaliceKind::IncorrectGenesis) {
+            self.detect_incorrect_genesis(event)?;
+        }
 
-        self.detect_other_parent_by_same_creator(event)?;
-        self.detect_self_parent_by_different_creator(event)?;
-        self.detect_invalid_sync_event(event)?;
+        if self
+            .config
+            .detects_malice(MaliceKind::OtherParentBySameCreator)
+        {
+            self.detect_other_parent_by_same_creator(event)?;
+        }
+        if self
+            .config
+            .detects_malice(MaliceKind::SelfParentByDifferentCreator)
+        {
+            self.detect_self_parent_by_different_creator(event)?;
+        }
+        if self.config.detects_malice(MaliceKind::InvalidRequest)
+            || self.config.detects_malice(MaliceKind::InvalidResponse)
+        {
+            self.detect_invalid_sync_event(event)?;
+        }
 
-        self.detect_unexpected_genesis(event);
-        self.detect_missing_genesis(event);
-        self.detect_duplicate_vote(event);
-        self.detect_fork(event);
-        self.detect_invalid_accusations(event);
+        if self.config.detects_malice(MaliceKind::UnexpectedGenesis) {
+            self.detect_unexpected_genesis(event);
+        }
+        if self.config.detects_malice(MaliceKind::MissingGenesis) {
+            self.detect_missing_genesis(event);
+        }
+        if self.config.detects_malice(MaliceKind::DuplicateVote) {
+            self.detect_duplicate_vote(event);
+        }
+        if self.config.detects_malice(MaliceKind::Fork) {
+            self.detect_fork(event);
+        }
+        if self.config.detects_malice(MaliceKind::InvalidAccusation) {
+            self.detect_invalid_accusations(event);
+        }
+        if self.config.detects_malice(MaliceKind::Unprovable) {
+            self.detect_observation_spam(event);
+        }
 
         Ok(())
     }
@@ -1854,6 +3867,9 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         } else {
             Malice::InvalidResponse(packed_event)
         };
+        if !self.config.detects_malice(malice.kind()) {
+            return Ok(());
+        }
         self.accuse(event.creator(), malice);
         Err(Error::InvalidEvent)
     }
@@ -1932,7 +3948,7 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
                 .peer_list
                 .peer_events(event.creator())
                 .rev()
-                .filter_map(|index| self.get_known_event(index).ok())
+                .filter_map(|index| self.graph.get(index))
                 .filter(|event| {
                     event
                         .payload_key()
@@ -2003,7 +4019,11 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
                     ref offender,
                     ref malice,
                 }) => {
-                    if malice.is_provable() && !self.we_have_accused(offender, malice) {
+                    // We can only tell an accusation is invalid if we detect its kind of malice.
+                    if malice.is_provable()
+                        && self.config.detects_malice(malice.kind())
+                        && !self.we_have_accused(offender, malice)
+                    {
                         invalid_accusations.push(*self_parent.hash());
                     }
                     self_parent_index = self_parent.self_parent();
@@ -2039,7 +4059,7 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         self.peer_list
             .our_events()
             .rev()
-            .filter_map(|event_index| self.get_known_event(event_index).ok())
+            .filter_map(|event_index| self.graph.get(event_index))
             .filter_map(|event| {
                 if let Some(&Observation::Accusation {
                     ref offender,
@@ -2059,6 +4079,230 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             .any(|our_accusation| their_accusation == our_accusation)
     }
 
+    // Detect whether the event carries an oversized observation, or is one user observation event
+    // too many by its creator in the current window.
+    fn detect_observation_spam(&mut self, event: &Event<S::PublicId>) {
+        let policy = self.config.spam_policy();
+        let (oversized, internal) = if let Some(payload) = self.event_payload(event) {
+            (
+                serialise(payload).len() > policy.max_payload_size,
+                payload.is_internal(),
+            )
+        } else {
+            return;
+        };
+
+        let creator = event.creator();
+        let too_many = !internal && {
+            let count = self
+                .spam_stats
+                .get(&creator)
+                .map_or(0, |stats| stats.observations)
+                + 1;
+            self.average_spam_stat(creator, |stats| stats.observations)
+                .map_or(false, |average| {
+                    policy.is_exceeded(count, policy.observation_allowance, average)
+                })
+        };
+        if oversized || too_many {
+            self.accuse_of_spam(creator);
+        }
+    }
+
+    // Record that `src_index` sent us a gossip message, and detect whether it was one too many.
+    fn record_gossip_from(&mut self, src_index: PeerIndex) {
+        let count = {
+            let stats = self.spam_stats.entry(src_index).or_default();
+            stats.gossips += 1;
+            stats.gossips
+        };
+
+        if !self.config.detects_malice(MaliceKind::Unprovable) {
+            return;
+        }
+
+        let policy = self.config.spam_policy();
+        let too_many = self
+            .average_spam_stat(src_index, |stats| stats.gossips)
+            .map_or(false, |average| {
+                policy.is_exceeded(count, policy.gossip_allowance, average)
+            });
+        if too_many {
+            self.accuse_of_spam(src_index);
+        }
+    }
+
+    // Count one more sync event of ours while blocks are held back for their signatures, and
+    // output them unsigned once they waited too long.
+    fn advance_block_signature_wait(&mut self) {
+        if !self.unsigned_blocks.is_empty() {
+            self.block_signature_wait += 1;
+            self.sign_blocks();
+        }
+    }
+
+    // Count one more sync event of ours, and restart the spam counts once the window is full.
+    fn advance_spam_window(&mut self) {
+        self.spam_window_syncs += 1;
+        if self.spam_window_syncs >= self.config.spam_policy().window {
+            self.spam_window_syncs = 0;
+            self.spam_stats.clear();
+        }
+    }
+
+    // Returns whether the observation isn't an opaque payload the registered validator deems
+    // invalid.
+    fn is_valid_payload(&self, observation: &Observation<T, S::PublicId>) -> bool {
+        match (observation, &self.payload_validator) {
+            (Observation::OpaquePayload(payload), Some(validator)) => validator.is_valid(payload),
+            _ => true,
+        }
+    }
+
+    // Returns whether the reconfiguration changes at least one peer, adds no current voter and
+    // removes only current voters, and so each of its peers just once.
+    fn is_valid_reconfiguration(
+        &self,
+        add: &BTreeSet<S::PublicId>,
+        remove: &BTreeSet<S::PublicId>,
+    ) -> bool {
+        let is_voter = |peer_id| {
+            self.peer_list
+                .get_index(peer_id)
+                .map_or(false, |index| self.peer_list.peer_state(index).can_vote())
+        };
+        (!add.is_empty() || !remove.is_empty())
+            && !add.iter().any(is_voter)
+            && remove.iter().all(is_voter)
+    }
+
+    // Record that `creator` voted for an invalid payload in an event gossiped to us, and accuse it
+    // if it just exceeded the configured threshold.
+    fn record_invalid_payload_by(&mut self, creator: PeerIndex) {
+        let count = {
+            let count = self.invalid_payloads.entry(creator).or_insert(0);
+            *count += 1;
+            *count
+        };
+
+        log_at!(
+            self,
+            LogLevel::Info,
+            "{:?} ignored a vote for an invalid payload by {:?}",
+            self.our_pub_id(),
+            creator
+        );
+
+        let threshold = match self.config.invalid_payload_threshold() {
+            Some(threshold) => threshold.get(),
+            None => return,
+        };
+        if count > threshold && self.config.detects_malice(MaliceKind::Unprovable) {
+            self.accuse_of_unprovable_malice(creator, UnprovableMalice::InvalidPayload);
+        }
+    }
+
+    // Returns the average of the given count over the voters other than us and `peer_index`, or
+    // `None` if there are no such voters to compare `peer_index` with.
+    fn average_spam_stat<F>(&self, peer_index: PeerIndex, count: F) -> Option<usize>
+    where
+        F: Fn(&SpamStats) -> usize,
+    {
+        let others = self
+            .voters()
+            .iter()
+            .filter(|index| *index != peer_index && *index != PeerIndex::OUR)
+            .map(|index| self.spam_stats.get(&index).map_or(0, &count))
+            .collect_vec();
+        if others.is_empty() {
+            None
+        } else {
+            Some(others.iter().sum::<usize>() / others.len())
+        }
+    }
+
+    // Accuse the peer of spamming, unless we already have.
+    fn accuse_of_spam(&mut self, offender: PeerIndex) {
+        if self.unprovably_accused.contains(&offender) {
+            return;
+        }
+
+        log_at!(
+            self,
+            LogLevel::Info,
+            "{:?} detected spam by {:?}",
+            self.our_pub_id(),
+            offender
+        );
+        self.accuse_of_unprovable_malice(offender, UnprovableMalice::Spam);
+    }
+
+    // Count our new sync event against every peer yet to answer our request, and accuse those
+    // which just exceeded the configured threshold.
+    fn detect_unresponsive_peers(&mut self) {
+        let threshold = if let Some(threshold) = self.config.unresponsive_threshold() {
+            threshold.get()
+        } else {
+            return;
+        };
+
+        let mut newly_unresponsive = vec![];
+        for (peer_index, count) in &mut self.unanswered_requests {
+            *count += 1;
+            if *count == threshold + 1 {
+                newly_unresponsive.push(*peer_index);
+            }
+        }
+
+        if !self.config.detects_malice(MaliceKind::Unprovable) {
+            return;
+        }
+
+        for offender in newly_unresponsive {
+            if self.unprovably_accused.contains(&offender)
+                || !self.peer_list.peer_state(offender).can_recv()
+            {
+                continue;
+            }
+
+            log_at!(
+                self,
+                LogLevel::Info,
+                "{:?} detected {:?} is unresponsive",
+                self.our_pub_id(),
+                offender
+            );
+            self.accuse_of_unprovable_malice(offender, UnprovableMalice::Unresponsive);
+        }
+    }
+
+    // A peer gossiping to us is alive even if it hasn't answered our request yet, e.g. because our
+    // messages were lost, so halve its count of unanswered sync events. It is still found
+    // unresponsive if it keeps ignoring us for long enough between its own requests.
+    fn decay_unanswered_requests(&mut self, src_index: PeerIndex) {
+        if let Some(count) = self.unanswered_requests.get_mut(&src_index) {
+            *count /= 2;
+        }
+    }
+
+    // Accuse the peer of unprovable malice, unless we already have accused it of any.
+    fn accuse_of_unprovable_malice(&mut self, offender: PeerIndex, malice: UnprovableMalice) {
+        if !self.unprovably_accused.insert(offender) {
+            return;
+        }
+
+        let malice = Malice::Unprovable(malice);
+        let accused = self
+            .peer_list
+            .get(offender)
+            .map_or(true, |peer| self.we_have_accused(peer.id(), &malice));
+        if !accused {
+            self.accuse(offender, malice);
+        }
+    }
+
+    // Returns the events proving the given malice, if it's of a kind provable by its offender's
+    // events alone. The offender's event comes first.
     fn detect_premature_gossip(&self) -> Result<()> {
         self.confirm_self_state(PeerState::DKG)
             .map_err(|_| Error::PrematureGossip)
@@ -2121,7 +4365,7 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
         let parent_index = parent.event_index();
         self.peer_list
             .events_by_index(parent.creator(), parent.index_by_creator() + 1)
-            .filter_map(move |descendant_index| self.get_known_event(descendant_index).ok())
+            .filter_map(move |descendant_index| self.graph.get(descendant_index))
             .filter(move |descendant| descendant.self_parent() == Some(parent_index))
     }
 
@@ -2203,6 +4447,8 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
             .map(|(offender, malice)| (*offender, malice))
             .chain(our_accusations)
             .filter(|(offender, _)| offender != &creator)
+            // Other peers can't be expected to have detected malice we can't prove to them.
+            .filter(|(_, malice)| malice.is_provable())
             .filter(|(_, malice)| self.accused_events_are_ancestors_of(&malice, event_index))
             .filter(|(_, malice)| {
                 !self.accused_event_is_fork_but_this_event_is_not_a_fork_descendant(
@@ -2233,7 +4479,7 @@ impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
                 }
             })
             .next()
-            .unwrap_or_else(|| self.peer_list.voters().map(|(_, peer)| peer.id()).collect())
+            .unwrap_or_else(|| self.genesis_group.iter().collect())
     }
 }
 
@@ -2241,7 +4487,7 @@ impl<T: NetworkEvent, S: SecretId> Drop for Parsec<T, S> {
     fn drop(&mut self) {
         dump_graph::to_file(dump_graph::ToFileInfo {
             owner_id: self.our_pub_id(),
-            consensus_mode: self.consensus_mode,
+            consensus_mode: self.config.consensus_mode(),
             gossip_graph: &self.graph,
             meta_election: &self.meta_election,
             peer_list: &self.peer_list,
@@ -2272,9 +4518,20 @@ enum PostProcessAction {
     Restart(usize),
 }
 
-#[cfg(feature = "malice-detection")]
 type Accusations<T, P> = Vec<(PeerIndex, Malice<T, P>)>;
 
+// Counts of what a peer has sent us in the current window, compared against the other voters' to
+// detect spam.
+#[derive(Default, Serialize, Deserialize)]
+struct SpamStats {
+    // Number of user observation events created by the peer.
+    observations: usize,
+    // Number of gossip messages the peer sent us.
+    gossips: usize,
+}
+
+#[serde(bound = "")]
+#[derive(Serialize, Deserialize)]
 enum PendingEvent<T: NetworkEvent, P: PublicId> {
     Sync {
         is_request: bool,
@@ -2284,7 +4541,12 @@ enum PendingEvent<T: NetworkEvent, P: PublicId> {
     DkgMessage {
         msg: DkgMessage,
     },
-    #[cfg(feature = "malice-detection")]
+    CoinShare {
+        coin_share: CoinShare<P>,
+    },
+    BlockSignatureShare {
+        share: BlockSignatureShare,
+    },
     Accusation {
         offender: PeerIndex,
         malice: Malice<T, P>,
@@ -2293,6 +4555,103 @@ enum PendingEvent<T: NetworkEvent, P: PublicId> {
     },
 }
 
+// Version of the format written by `Parsec::save`. Must be bumped whenever the layout of
+// `SavedState`, or of any of the types it contains, changes between releases.
+const SAVED_STATE_VERSION: u32 = 1;
+
+// The state written by `Parsec::save`. Everything except our secret ID, which the caller has to
+// supply again, and the RNG.
+#[serde(bound = "")]
+#[derive(Serialize)]
+struct SavedStateRef<'a, T: NetworkEvent, S: SecretId> {
+    peer_list: &'a PeerList<S>,
+    genesis_group: &'a BTreeSet<S::PublicId>,
+    key_gen: &'a BTreeMap<KeyGenId, KeyGen<S>>,
+    key_gen_next_id: KeyGenId,
+    failed_key_gens: &'a BTreeMap<KeyGenId, DkgFailure>,
+    graph: &'a Graph<S::PublicId>,
+    observations: &'a ObservationStore<T, S::PublicId>,
+    pruned_observations: &'a BTreeMap<ObservationKey, bool>,
+    consensused_blocks: &'a VecDeque<BlockGroup<T, S::PublicId>>,
+    meta_election: &'a MetaElection,
+    config: &'a ParsecConfig,
+    pending_dkg_msgs: &'a Vec<DkgMessage>,
+    coin_keys: &'a Option<CoinKeys<S::PublicId>>,
+    coin_shares: &'a CoinShares<S::PublicId>,
+    pending_coin_shares: &'a Vec<CoinShare<S::PublicId>>,
+    unsigned_blocks: &'a VecDeque<BlockGroup<T, S::PublicId>>,
+    block_signature_shares: &'a BTreeMap<u64, Vec<(S::PublicId, BlockSignatureShare)>>,
+    next_block_index: u64,
+    block_signature_wait: usize,
+    pending_block_signature_shares: &'a Vec<BlockSignatureShare>,
+    chain_tip: &'a Option<BlockHash>,
+    section_summary: &'a Option<SectionSummary<S::PublicId>>,
+    pending_accusations: &'a Accusations<T, S::PublicId>,
+    consensused_accusations: &'a BTreeMap<(S::PublicId, MaliceKind), usize>,
+    pending_removals: &'a Vec<Observation<T, S::PublicId>>,
+    pending_events: &'a Vec<PendingEvent<T, S::PublicId>>,
+    pending_requests: &'a BTreeMap<PeerIndex, VecDeque<EventIndex>>,
+    pending_responses: &'a BTreeMap<PeerIndex, VecDeque<EventIndex>>,
+    awaiting_responses: &'a BTreeSet<PeerIndex>,
+    peer_versions: &'a BTreeMap<PeerIndex, u32>,
+    spam_stats: &'a BTreeMap<PeerIndex, SpamStats>,
+    spam_window_syncs: usize,
+    unanswered_requests: &'a BTreeMap<PeerIndex, usize>,
+    invalid_payloads: &'a BTreeMap<PeerIndex, usize>,
+    unprovably_accused: &'a BTreeSet<PeerIndex>,
+    pending_key_rotation: Option<&'a S::PublicId>,
+}
+
+// Owned counterpart of `SavedStateRef`, read by `Parsec::restore`. The fields must stay in the same
+// order.
+#[serde(bound = "")]
+#[derive(Deserialize)]
+struct SavedState<T: NetworkEvent, S: SecretId> {
+    peer_list: SavedPeerList<S::PublicId>,
+    genesis_group: BTreeSet<S::PublicId>,
+    key_gen: BTreeMap<KeyGenId, KeyGen<S>>,
+    key_gen_next_id: KeyGenId,
+    failed_key_gens: BTreeMap<KeyGenId, DkgFailure>,
+    graph: Graph<S::PublicId>,
+    observations: ObservationStore<T, S::PublicId>,
+    pruned_observations: BTreeMap<ObservationKey, bool>,
+    consensused_blocks: VecDeque<BlockGroup<T, S::PublicId>>,
+    meta_election: MetaElection,
+    config: ParsecConfig,
+    pending_dkg_msgs: Vec<DkgMessage>,
+    coin_keys: Option<CoinKeys<S::PublicId>>,
+    coin_shares: CoinShares<S::PublicId>,
+    pending_coin_shares: Vec<CoinShare<S::PublicId>>,
+    unsigned_blocks: VecDeque<BlockGroup<T, S::PublicId>>,
+    block_signature_shares: BTreeMap<u64, Vec<(S::PublicId, BlockSignatureShare)>>,
+    next_block_index: u64,
+    block_signature_wait: usize,
+    pending_block_signature_shares: Vec<BlockSignatureShare>,
+    chain_tip: Option<BlockHash>,
+    section_summary: Option<SectionSummary<S::PublicId>>,
+    pending_accusations: Accusations<T, S::PublicId>,
+    consensused_accusations: BTreeMap<(S::PublicId, MaliceKind), usize>,
+    pending_removals: Vec<Observation<T, S::PublicId>>,
+    pending_events: Vec<PendingEvent<T, S::PublicId>>,
+    pending_requests: BTreeMap<PeerIndex, VecDeque<EventIndex>>,
+    pending_responses: BTreeMap<PeerIndex, VecDeque<EventIndex>>,
+    awaiting_responses: BTreeSet<PeerIndex>,
+    peer_versions: BTreeMap<PeerIndex, u32>,
+    spam_stats: BTreeMap<PeerIndex, SpamStats>,
+    spam_window_syncs: usize,
+    unanswered_requests: BTreeMap<PeerIndex, usize>,
+    invalid_payloads: BTreeMap<PeerIndex, usize>,
+    unprovably_accused: BTreeSet<PeerIndex>,
+    pending_key_rotation: Option<S::PublicId>,
+}
+
+fn deserialise_saved_state<T: DeserializeOwned>(cursor: &mut Cursor<&[u8]>) -> Result<T> {
+    serialisation::deserialise_from(cursor).map_err(|error| {
+        debug!("Failed to deserialise saved state: {:?}", error);
+        Error::InvalidSavedState
+    })
+}
+
 #[cfg(any(test, feature = "testing"))]
 impl<T: NetworkEvent, S: SecretId> Parsec<T, S> {
     // Disable processing consensus on this instance (speed up processing).
@@ -2316,8 +4675,9 @@ impl Parsec<Transaction, PeerId> {
         let peer_list = PeerList::new(parsed_contents.our_id);
         let mut parsec = Parsec::empty(
             peer_list,
+            &BTreeSet::new(),
             PeerIndexSet::default(),
-            parsed_contents.consensus_mode,
+            ParsecConfig::default().with_consensus_mode(parsed_contents.consensus_mode),
             secure_rng,
         );
 
@@ -2341,14 +4701,8 @@ impl Parsec<Transaction, PeerId> {
         if let Some(serialized_key_gens_and_next_id) =
             &parsed_contents.serialized_key_gens_and_next_id
         {
-            // `serialisation::deserialise` is over constrained and require `Serialize` which is
-            // only available with `dump-graphs` enabled (as it is not safe to dump secrets).
-            // Use Cursor to get an io::Read object so we can use the not over constrained
-            // `deserialise_from`.
-            let mut cursor = std::io::Cursor::new(serialized_key_gens_and_next_id);
-            let (key_gen, key_gen_next_id) = unwrap!(
-                maidsafe_utilities::serialisation::deserialise_from(&mut cursor)
-            );
+            let (key_gen, key_gen_next_id) =
+                unwrap!(serialisation::deserialise(serialized_key_gens_and_next_id));
 
             parsec.key_gen = key_gen;
             parsec.key_gen_next_id = key_gen_next_id;
@@ -2380,14 +4734,14 @@ impl<T: NetworkEvent, S: SecretId> TestParsec<T, S> {
     pub fn from_genesis(
         our_id: S,
         genesis_group: &BTreeSet<S::PublicId>,
-        consensus_mode: ConsensusMode,
+        config: ParsecConfig,
         secure_rng: Box<dyn rand::Rng>,
     ) -> Self {
         TestParsec(Parsec::from_genesis(
             our_id,
             genesis_group,
             vec![],
-            consensus_mode,
+            config,
             secure_rng,
         ))
     }
@@ -2396,18 +4750,38 @@ impl<T: NetworkEvent, S: SecretId> TestParsec<T, S> {
         our_id: S,
         genesis_group: &BTreeSet<S::PublicId>,
         section: &BTreeSet<S::PublicId>,
-        consensus_mode: ConsensusMode,
+        config: ParsecConfig,
         secure_rng: Box<dyn rand::Rng>,
     ) -> Self {
         TestParsec(Parsec::from_existing(
             our_id,
             genesis_group,
             section,
-            consensus_mode,
+            config,
             secure_rng,
         ))
     }
 
+    #[cfg(all(test, feature = "mock"))]
+    pub fn from_section_summary(
+        our_id: S,
+        genesis_group: &BTreeSet<S::PublicId>,
+        section: &BTreeSet<S::PublicId>,
+        signed_summaries: &[SignedSectionSummary<S::PublicId>],
+        config: ParsecConfig,
+        secure_rng: Box<dyn rand::Rng>,
+    ) -> Result<Self> {
+        Parsec::from_section_summary(
+            our_id,
+            genesis_group,
+            section,
+            signed_summaries,
+            config,
+            secure_rng,
+        )
+        .map(TestParsec)
+    }
+
     pub fn graph(&self) -> &Graph<S::PublicId> {
         &self.0.graph
     }
@@ -2457,6 +4831,10 @@ impl TestParsec<Transaction, PeerId> {
         TestParsec(Parsec::from_parsed_contents(parsed_contents, secure_rng))
     }
 
+    pub fn restore(our_id: PeerId, bytes: &[u8], secure_rng: Box<dyn rand::Rng>) -> Result<Self> {
+        Parsec::restore(our_id, bytes, secure_rng).map(TestParsec)
+    }
+
     pub fn meta_election(&self) -> &MetaElection {
         &self.meta_election
     }
@@ -2465,6 +4843,13 @@ impl TestParsec<Transaction, PeerId> {
         self.0.consensused_blocks.iter().flatten()
     }
 
+    // Tosses the common coin of `round` for the meta-votes about `peer_id` of our last event.
+    pub fn toss_coin(&self, peer_id: &PeerId, round: usize) -> Option<bool> {
+        let peer_index = unwrap!(self.0.peer_list.get_index(peer_id));
+        let event = unwrap!(self.0.get_known_event(self.our_last_event_index()));
+        unwrap!(self.0.toss_coin(peer_index, round, event))
+    }
+
     pub fn change_peer_state(&mut self, peer_id: &PeerId, state: PeerState) {
         let peer_index = unwrap!(self.0.peer_list.get_index(peer_id));
         self.0.peer_list.change_peer_state(peer_index, state)
@@ -2505,7 +4890,7 @@ impl TestParsec<Transaction, PeerId> {
     }
 }
 
-#[cfg(all(test, feature = "malice-detection", feature = "mock"))]
+#[cfg(all(test, feature = "mock"))]
 impl TestParsec<Transaction, PeerId> {
     pub fn remove_last_event(&mut self) -> Option<(EventIndex, Event<PeerId>)> {
         let (event_index, event) = self.graph.remove_last()?;
@@ -2580,6 +4965,10 @@ impl TestParsec<Transaction, PeerId> {
         &self.0.pending_accusations
     }
 
+    pub fn set_config(&mut self, config: ParsecConfig) {
+        self.0.config = config;
+    }
+
     pub fn add_peer(&mut self, peer_id: PeerId, state: PeerState) {
         let _ = self.0.peer_list.add_peer(peer_id, state);
     }
@@ -18,15 +18,14 @@ pub(crate) use self::{
     peer_index::{PeerIndex, PeerIndexMap, PeerIndexSet},
 };
 
-#[cfg(all(test, feature = "mock"))]
-use crate::gossip::Graph;
 #[cfg(any(test, feature = "testing"))]
 use crate::mock::PeerId;
 use crate::{
     error::Error,
-    gossip::{EventIndex, IndexedEventRef},
+    gossip::{Event, EventIndex, Graph, IndexedEventRef},
     id::SecretId,
 };
+use serde::{Serialize, Serializer};
 use std::{
     collections::btree_map::{BTreeMap, Entry},
     fmt::{self, Debug, Formatter},
@@ -40,6 +39,10 @@ pub(crate) struct PeerList<S: SecretId> {
     indices: BTreeMap<S::PublicId, PeerIndex>,
 }
 
+/// The serialised form of `PeerList`: our own peer followed by all the other peers. Our secret ID
+/// is not part of it.
+pub(crate) type SavedPeerList<P> = (Peer<P>, Vec<Peer<P>>);
+
 impl<S: SecretId> PeerList<S> {
     pub fn new(our_id: S) -> Self {
         let our_peer = Peer::new(our_id.public_id().clone(), PeerState::inactive());
@@ -52,6 +55,36 @@ impl<S: SecretId> PeerList<S> {
         }
     }
 
+    /// Recreates a peer list from its serialised form. Returns `None` if `our_id` doesn't match
+    /// the ID the peer list was saved with.
+    pub fn restore(our_id: S, (our_peer, peers): SavedPeerList<S::PublicId>) -> Option<Self> {
+        if our_peer.id() != our_id.public_id() {
+            return None;
+        }
+
+        let indices = peers
+            .iter()
+            .enumerate()
+            .flat_map(|(index, peer)| {
+                iter::once(peer.id())
+                    .chain(peer.previous_ids())
+                    .map(move |id| (id.clone(), PeerIndex(index + 1)))
+            })
+            .chain(
+                our_peer
+                    .previous_ids()
+                    .map(|id| (id.clone(), PeerIndex::OUR)),
+            )
+            .collect();
+
+        Some(PeerList {
+            our_id,
+            our_peer,
+            peers,
+            indices,
+        })
+    }
+
     pub fn our_id(&self) -> &S {
         &self.our_id
     }
@@ -116,11 +149,6 @@ impl<S: SecretId> PeerList<S> {
         )
     }
 
-    /// Returns an iterator of peers that can vote.
-    pub fn voters(&self) -> impl Iterator<Item = (PeerIndex, &Peer<S::PublicId>)> {
-        self.iter().filter(|(_, peer)| peer.state().can_vote())
-    }
-
     /// Returns an iterator of peers that we can send gossip to.
     pub fn gossip_recipients<'a>(
         &'a self,
@@ -199,6 +227,52 @@ impl<S: SecretId> PeerList<S> {
         }
     }
 
+    /// Rebinds the peer at `index` to `new_id`, as consensused at `rotation_event`. Its events are
+    /// accepted under the old ID until the first one signed with the new ID, as long as they don't
+    /// descend from `rotation_event`.
+    pub fn rotate_peer_key(
+        &mut self,
+        index: PeerIndex,
+        new_id: S::PublicId,
+        rotation_event: EventIndex,
+    ) {
+        if let Some(peer) = self.get_known_mut(index) {
+            peer.rotate_id(new_id.clone(), rotation_event, None);
+            let _ = self.indices.insert(new_id, index);
+        }
+    }
+
+    /// Switches to `new_id` as our secret ID, as consensused at `rotation_event`. Our events from
+    /// index-by-creator `first_index` on are signed with it.
+    pub fn rotate_our_key(&mut self, new_id: S, rotation_event: EventIndex, first_index: usize) {
+        let old_id = self.our_id.public_id().clone();
+        self.our_peer.rotate_id(
+            new_id.public_id().clone(),
+            rotation_event,
+            Some(first_index),
+        );
+        self.our_id = new_id;
+        let _ = self.indices.insert(old_id, PeerIndex::OUR);
+    }
+
+    /// Returns whether `id` is the key the creator of `event`, not yet in `graph`, signs it with.
+    pub fn confirm_creator_id(
+        &mut self,
+        event: &Event<S::PublicId>,
+        id: &S::PublicId,
+        graph: &Graph<S::PublicId>,
+    ) -> bool {
+        // A pruned event is an ancestor of every event its creator has yet to send us.
+        let descends_from = |event_index| {
+            graph.get(event_index).map_or(true, |rotation_event| {
+                event.is_descendant_of(rotation_event)
+            })
+        };
+        self.get_known_mut(event.creator()).map_or(false, |peer| {
+            peer.confirm_id_at(id, event.index_by_creator(), descends_from)
+        })
+    }
+
     /// Returns the index of the last event created by this peer. Returns `None` if cannot find.
     pub fn last_event(&self, peer_index: PeerIndex) -> Option<EventIndex> {
         self.get(peer_index)
@@ -229,13 +303,11 @@ impl<S: SecretId> PeerList<S> {
         }
     }
 
-    #[cfg(feature = "malice-detection")]
     pub fn accomplice_event_checkpoint_by(&self, peer_index: PeerIndex) -> Option<EventIndex> {
         self.get(peer_index)
             .and_then(|peer| peer.accomplice_event_checkpoint)
     }
 
-    #[cfg(feature = "malice-detection")]
     pub fn update_accomplice_event_checkpoint_by(
         &mut self,
         peer_index: PeerIndex,
@@ -259,6 +331,22 @@ impl<S: SecretId> PeerList<S> {
         }
     }
 
+    /// Forgets the events preceding `end_index` in topological order, for every peer. Must be
+    /// called after pruning the graph up to `end_index`.
+    pub fn prune_events(&mut self, end_index: usize) {
+        for peer in iter::once(&mut self.our_peer).chain(&mut self.peers) {
+            peer.prune_events(end_index);
+        }
+    }
+
+    /// Treats the first `count` events of the peer as pruned, for a peer which created them before
+    /// the section's meta-election we were caught up with.
+    pub fn skip_events(&mut self, index: PeerIndex, count: usize) {
+        if let Some(peer) = self.get_known_mut(index) {
+            peer.skip_events(count)
+        }
+    }
+
     /// Removes last event from its creator.
     #[cfg(any(all(test, feature = "mock"), feature = "testing"))]
     pub fn remove_last_event(&mut self, creator: PeerIndex) -> Option<EventIndex> {
@@ -297,6 +385,12 @@ impl<S: SecretId> Debug for PeerList<S> {
     }
 }
 
+impl<S: SecretId> Serialize for PeerList<S> {
+    fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+        (&self.our_peer, &self.peers).serialize(serializer)
+    }
+}
+
 #[cfg(any(test, feature = "testing"))]
 impl PeerList<PeerId> {
     // Creates a PeerList using the input parameters directly.
@@ -333,6 +427,7 @@ impl PeerList<PeerId> {
 pub(crate) enum PeerListChange {
     Add(PeerIndex),
     Remove(PeerIndex),
+    ChangeWeight(PeerIndex, usize),
 }
 
 #[cfg(test)]
@@ -15,17 +15,20 @@ use itertools::Itertools;
 use std::{
     fmt::{self, Debug, Formatter},
     iter::{self, FromIterator},
+    mem,
 };
 
-#[derive(Debug)]
+#[serde(bound = "")]
+#[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct Peer<P: PublicId> {
     id: P,
+    // The keys the peer signed its events with before rotating to `id`, oldest first.
+    previous_ids: Vec<PreviousId<P>>,
     presence: Presence,
     pub(super) events: Events,
     pub(super) last_gossiped_event: Option<EventIndex>,
     // As a performance optimisation we keep track of which events we've cleared for Accomplice
     // accusations.
-    #[cfg(feature = "malice-detection")]
     pub accomplice_event_checkpoint: Option<EventIndex>,
 }
 
@@ -33,10 +36,10 @@ impl<P: PublicId> Peer<P> {
     pub(super) fn new(id: P, state: PeerState) -> Self {
         Self {
             id,
+            previous_ids: Vec::new(),
             presence: Presence::Present(state),
             events: Events::new(),
             last_gossiped_event: None,
-            #[cfg(feature = "malice-detection")]
             accomplice_event_checkpoint: None,
         }
     }
@@ -45,6 +48,88 @@ impl<P: PublicId> Peer<P> {
         &self.id
     }
 
+    /// Returns the ID the peer signed its event with the given index-by-creator with.
+    pub fn id_at(&self, index_by_creator: usize) -> &P {
+        self.previous_ids
+            .iter()
+            .find(|previous| previous.end.map_or(true, |end| index_by_creator < end))
+            .map_or(&self.id, |previous| &previous.id)
+    }
+
+    /// Returns the IDs the peer used before its current one.
+    pub fn previous_ids<'a>(&'a self) -> impl Iterator<Item = &'a P> + 'a {
+        self.previous_ids.iter().map(|previous| &previous.id)
+    }
+
+    // Switches to `new_id`, as consensused at `rotation_event`. The new ID signs the events from
+    // index-by-creator `first_index` on, if known already.
+    pub(super) fn rotate_id(
+        &mut self,
+        new_id: P,
+        rotation_event: EventIndex,
+        first_index: Option<usize>,
+    ) {
+        let id = mem::replace(&mut self.id, new_id);
+        self.previous_ids.push(PreviousId {
+            id,
+            rotation_event,
+            end: first_index,
+        });
+    }
+
+    // Returns whether `id` may have signed the event with the given index-by-creator, which
+    // descends from the events for which `descends_from` returns true. A rotated key is no longer
+    // valid for the events descending from the one the rotation was consensused at, nor from the
+    // first event signed by the next key on.
+    pub(super) fn confirm_id_at<F>(
+        &mut self,
+        id: &P,
+        index_by_creator: usize,
+        descends_from: F,
+    ) -> bool
+    where
+        F: Fn(EventIndex) -> bool,
+    {
+        let position = if *id == self.id {
+            self.previous_ids.len()
+        } else if let Some(position) = self
+            .previous_ids
+            .iter()
+            .position(|previous| previous.id == *id)
+        {
+            position
+        } else {
+            return false;
+        };
+
+        if let Some(previous) = self.previous_ids.get(position) {
+            // The peer has switched to the next key once it knew of the rotation's consensus.
+            if descends_from(previous.rotation_event) {
+                return false;
+            }
+        }
+
+        let start = match position.checked_sub(1) {
+            Some(previous) => self.previous_ids[previous].end,
+            None => Some(0),
+        };
+        let start = match start {
+            Some(start) => start,
+            None => {
+                // First event signed by this key.
+                self.previous_ids[position - 1].end = Some(index_by_creator);
+                index_by_creator
+            }
+        };
+        let end = self
+            .previous_ids
+            .get(position)
+            .and_then(|previous| previous.end)
+            .unwrap_or(usize::MAX);
+
+        start <= index_by_creator && index_by_creator < end
+    }
+
     pub fn state(&self) -> PeerState {
         match self.presence {
             Presence::Present(state) => state,
@@ -77,6 +162,25 @@ impl<P: PublicId> Peer<P> {
         self.events.by_index(index)
     }
 
+    /// Returns whether the events with the given index-by-creator have been pruned.
+    pub fn is_pruned(&self, index_by_creator: usize) -> bool {
+        index_by_creator < self.events.pruned
+    }
+
+    /// Returns the number of the peer's events that have been pruned, or that precede the ones we
+    /// were caught up with.
+    pub fn pruned_count(&self) -> usize {
+        self.events.pruned
+    }
+
+    pub(super) fn prune_events(&mut self, end_index: usize) {
+        self.events.prune(end_index)
+    }
+
+    pub(super) fn skip_events(&mut self, count: usize) {
+        self.events.skip(count)
+    }
+
     pub fn removal_event(&self) -> Option<EventIndex> {
         match self.presence {
             Presence::Present(_) => None,
@@ -94,37 +198,63 @@ impl<P: PublicId> Peer<P> {
     }
 }
 
-#[derive(Debug)]
+#[serde(bound = "")]
+#[derive(Debug, Serialize, Deserialize)]
+struct PreviousId<P: PublicId> {
+    id: P,
+    // Index of the event at which we reached consensus on rotating away from this key.
+    rotation_event: EventIndex,
+    // Index-by-creator of the first event signed with the next key. `None` until we see such an
+    // event.
+    end: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 enum Presence {
     Present(PeerState),
     // Contains the index of the event at which we reached the consensus on the removal.
     Removed(EventIndex),
 }
 
-#[derive(Debug)]
-pub(super) struct Events(Vec<Slot>);
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct Events {
+    slots: Vec<Slot>,
+    // Number of leading slots removed by `prune`. The slot at position `i` in `slots` holds the
+    // events with index-by-creator `pruned + i`.
+    pruned: usize,
+}
 
 impl Events {
     fn new() -> Self {
-        Events(Vec::new())
+        Events {
+            slots: Vec::new(),
+            pruned: 0,
+        }
     }
 
     fn add(&mut self, index_by_creator: usize, event_index: EventIndex) {
-        if let Some(slot) = self.0.get_mut(index_by_creator) {
+        let position = if let Some(position) = index_by_creator.checked_sub(self.pruned) {
+            position
+        } else {
+            log_or_panic!("Peer events must not be added below the pruned ones");
+            return;
+        };
+
+        if let Some(slot) = self.slots.get_mut(position) {
             slot.add(event_index);
             return;
         }
 
-        if index_by_creator != self.0.len() {
+        if position != self.slots.len() {
             log_or_panic!("Peer events must be added sequentially");
         }
 
-        self.0.push(Slot::new(event_index))
+        self.slots.push(Slot::new(event_index))
     }
 
     #[cfg(any(all(test, feature = "mock"), feature = "testing"))]
     fn remove_last(&mut self) -> Option<EventIndex> {
-        if let Some(slot) = self.0.last_mut() {
+        if let Some(slot) = self.slots.last_mut() {
             if let Some(index) = slot.rest.pop() {
                 return Some(index);
             }
@@ -132,27 +262,52 @@ impl Events {
             return None;
         }
 
-        self.0.pop().map(|slot| slot.first)
+        self.slots.pop().map(|slot| slot.first)
+    }
+
+    // Removes the leading slots whose events all precede `end_index` in topological order.
+    fn prune(&mut self, end_index: usize) {
+        let count = self
+            .slots
+            .iter()
+            .take_while(|slot| {
+                slot.iter()
+                    .all(|event_index| event_index.topological_index() < end_index)
+            })
+            .count();
+        let _ = self.slots.drain(..count);
+        self.pruned += count;
+    }
+
+    // Treats the first `count` events as pruned, before any has been added.
+    fn skip(&mut self, count: usize) {
+        if !self.slots.is_empty() {
+            log_or_panic!("Peer events can only be skipped before any has been added");
+            return;
+        }
+        self.pruned = count;
     }
 
     fn iter<'a>(&'a self) -> impl DoubleEndedIterator<Item = EventIndex> + 'a {
-        self.0.iter().flat_map(Slot::iter)
+        self.slots.iter().flat_map(Slot::iter)
     }
 
     #[cfg(all(test, feature = "mock"))]
     fn indexed<'a>(&'a self) -> impl DoubleEndedIterator<Item = (usize, EventIndex)> + 'a {
-        self.0
+        let pruned = self.pruned;
+        self.slots
             .iter()
             .enumerate()
-            .flat_map(|(index_by_creator, slot)| {
+            .flat_map(move |(position, slot)| {
                 slot.iter()
-                    .map(move |event_index| (index_by_creator, event_index))
+                    .map(move |event_index| (pruned + position, event_index))
             })
     }
 
     fn by_index<'a>(&'a self, index_by_creator: usize) -> impl Iterator<Item = EventIndex> + 'a {
-        self.0
-            .get(index_by_creator)
+        index_by_creator
+            .checked_sub(self.pruned)
+            .and_then(|position| self.slots.get(position))
             .into_iter()
             .flat_map(Slot::iter)
     }
@@ -175,6 +330,7 @@ where
     }
 }
 
+#[derive(Serialize, Deserialize)]
 struct Slot {
     first: EventIndex,
     rest: Vec<EventIndex>,
@@ -12,7 +12,7 @@ use std::{
     iter::FromIterator,
 };
 
-#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
 pub(crate) struct PeerIndex(pub(super) usize);
 
 impl PeerIndex {
@@ -26,7 +26,7 @@ impl PeerIndex {
 }
 
 /// Map keyed by `PeerIndex`.
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub(crate) struct PeerIndexMap<T>(Vec<Option<T>>);
 
 impl<T> PeerIndexMap<T> {
@@ -70,6 +70,10 @@ impl<T> PeerIndexMap<T> {
         self.0[key.0].replace(value)
     }
 
+    pub fn remove(&mut self, key: PeerIndex) -> Option<T> {
+        self.0.get_mut(key.0).and_then(Option::take)
+    }
+
     pub fn clear(&mut self) {
         self.0.clear()
     }
@@ -199,7 +203,7 @@ impl<'a, T> VacantEntry<'a, T> {
 }
 
 /// Set of `PeerIndex`.
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub(crate) struct PeerIndexSet(Vec<bool>);
 
 impl PeerIndexSet {
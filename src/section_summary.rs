@@ -0,0 +1,153 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    block::BlockHash,
+    config::QuorumPolicy,
+    error::Error,
+    gossip::Request,
+    hash::Hash,
+    id::{Proof, PublicId, SecretId},
+    network_event::NetworkEvent,
+    observation::ObservationHash,
+    parsec::KeyGenId,
+    serialise,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use threshold_crypto::PublicKeySet;
+
+/// Summary of the state of a section at the start of one of its meta-elections. The voters sign
+/// it so that a peer joining the section can learn whom to gossip with, and can be caught up with
+/// the section from that meta-election on. See [`Parsec::section_summary`](
+/// struct.Parsec.html#method.section_summary) and [`Parsec::from_section_summary`](
+/// struct.Parsec.html#method.from_section_summary).
+#[serde(bound = "")]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Debug)]
+pub struct SectionSummary<P: PublicId> {
+    pub(crate) voters: BTreeSet<P>,
+    // Weights of the voters whose weight isn't the default of one.
+    pub(crate) weights: BTreeMap<P, usize>,
+    pub(crate) consensus_history_hash: Hash,
+    pub(crate) election_index: usize,
+    pub(crate) next_block_index: u64,
+    pub(crate) chain_tip: Option<BlockHash>,
+    // Participants and public keys of the DKG the common coin's keys were generated by, if any.
+    pub(crate) coin_keys: Option<(BTreeSet<P>, PublicKeySet)>,
+    // Number of meta-elections completed since the common coin's keys were generated.
+    pub(crate) coin_election: usize,
+    pub(crate) next_key_gen_id: KeyGenId,
+}
+
+impl<P: PublicId> SectionSummary<P> {
+    /// Returns the voters of the section.
+    pub fn voters(&self) -> &BTreeSet<P> {
+        &self.voters
+    }
+
+    /// Returns the index of the meta-election the summary was taken at, i.e. the number of
+    /// observations consensused by the section before it.
+    pub fn election_index(&self) -> usize {
+        self.election_index
+    }
+
+    /// Returns the number of blocks consensused by the section before the meta-election the
+    /// summary was taken at, i.e. the index of the next block.
+    pub fn next_block_index(&self) -> u64 {
+        self.next_block_index
+    }
+
+    /// Returns the hash of the last block consensused by the section before the meta-election the
+    /// summary was taken at, if the blocks are chained and there is one. The next block links to
+    /// it.
+    pub fn chain_tip(&self) -> Option<&BlockHash> {
+        self.chain_tip.as_ref()
+    }
+}
+
+/// A `SectionSummary` signed by one of the voters it lists.
+#[serde(bound = "")]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Debug)]
+pub struct SignedSectionSummary<P: PublicId> {
+    summary: SectionSummary<P>,
+    proof: Proof<P>,
+}
+
+impl<P: PublicId> SignedSectionSummary<P> {
+    pub(crate) fn new<S: SecretId<PublicId = P>>(
+        secret_id: &S,
+        summary: SectionSummary<P>,
+    ) -> Self {
+        let proof = secret_id.create_proof(&serialise(&summary));
+        Self { summary, proof }
+    }
+
+    /// Returns the summary.
+    pub fn summary(&self) -> &SectionSummary<P> {
+        &self.summary
+    }
+
+    /// Returns the signer's proof of the summary.
+    pub fn proof(&self) -> &Proof<P> {
+        &self.proof
+    }
+
+    /// Returns whether the summary is validly signed by one of the voters it lists.
+    pub fn is_valid(&self) -> bool {
+        self.summary.voters.contains(self.proof.public_id())
+            && self.proof.is_valid(&serialise(&self.summary))
+    }
+}
+
+/// The gossip graph of a section from the start of its current meta-election on, which a member
+/// of the section creates with [`Parsec::create_catch_up`](
+/// struct.Parsec.html#method.create_catch_up) for a joining peer to pass to
+/// [`Parsec::handle_catch_up`](struct.Parsec.html#method.handle_catch_up).
+#[serde(bound = "")]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CatchUp<T: NetworkEvent, P: PublicId> {
+    pub(crate) election_index: usize,
+    // Hashes of the payloads consensused before the meta-election, with their creators in
+    // `ConsensusMode::Single`. They hash to the summary's consensus history hash.
+    pub(crate) consensus_history: Vec<(ObservationHash, Option<P>)>,
+    // Index-by-creator of the first event of each creator in `request`.
+    pub(crate) first_indices: BTreeMap<P, usize>,
+    // The events from the start of the meta-election on, preceded by the last events of the peers
+    // which haven't created any since, as a gossip request of the member.
+    pub(crate) request: Request<T, P>,
+}
+
+pub(crate) fn consensus_history_hash<P: PublicId>(
+    consensus_history: &[(ObservationHash, Option<P>)],
+) -> Hash {
+    Hash::from(serialise(&consensus_history).as_slice())
+}
+
+// Returns the summary validly signed by a quorum of `section`, the voters the caller knows the
+// section to have.
+pub(crate) fn agreed_summary<'a, P: PublicId>(
+    signed_summaries: &'a [SignedSectionSummary<P>],
+    section: &BTreeSet<P>,
+    quorum_policy: QuorumPolicy,
+) -> Result<&'a SectionSummary<P>, Error> {
+    let mut signers: BTreeMap<_, BTreeSet<_>> = BTreeMap::new();
+    for signed_summary in signed_summaries
+        .iter()
+        .filter(|signed| signed.is_valid() && section.contains(signed.proof.public_id()))
+    {
+        let _ = signers
+            .entry(&signed_summary.summary)
+            .or_default()
+            .insert(signed_summary.proof.public_id());
+    }
+
+    signers
+        .into_iter()
+        .find(|(_, signers)| quorum_policy.is_quorum(signers.len(), section.len()))
+        .map(|(summary, _)| summary)
+        .ok_or(Error::InsufficientVotes)
+}
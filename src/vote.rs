@@ -67,7 +67,8 @@ impl<T: NetworkEvent, P: PublicId> Debug for Vote<T, P> {
 }
 
 /// Key representing a vote when stored inside the gossip graph.
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd)]
+#[serde(bound = "")]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
 pub(crate) struct VoteKey<P: PublicId> {
     payload_key: ObservationKey,
     signature: P::Signature,
@@ -58,7 +58,7 @@ extern crate proptest;
 #[macro_use]
 extern crate unwrap;
 
-use maidsafe_utilities::log;
+use maidsafe_utilities::{log, serialisation};
 use parsec::{
     dev_utils::{
         proptest::{arbitrary_delay, ScheduleOptionsStrategy, ScheduleStrategy},
@@ -66,11 +66,14 @@ use parsec::{
         Sampling, Schedule, ScheduleOptions,
     },
     mock::{PeerId, Transaction, NAMES},
-    ConsensusMode, Observation,
+    verify_chain, Block, ConsensusMode, Error, MaliceKind, Observation, ParsecConfig, QuorumPolicy,
 };
 use proptest::{prelude::ProptestConfig, test_runner::FileFailurePersistence};
 use rand::Rng;
-use std::collections::{BTreeMap, BTreeSet};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    num::NonZeroUsize,
+};
 
 // Alter the seed here to reproduce failures
 static SEED: RngChoice = RngChoice::SeededRandom;
@@ -427,6 +430,107 @@ fn run_dkgs(
     );
 }
 
+#[test]
+fn threshold_signed_blocks() {
+    let config = ParsecConfig::default()
+        .with_consensus_mode(ConsensusMode::Single)
+        .with_block_signatures(true)
+        .with_chained_blocks(true);
+    let mut env = Environment::with_config(SEED, config);
+    let genesis: BTreeSet<_> = PeerId::named_peer_ids()[0..4].iter().cloned().collect();
+
+    // Generate the keys first.
+    let obs_schedule = ObservationSchedule {
+        genesis: Genesis::new(genesis.clone()),
+        schedule: vec![(0, ObservationEvent::StartDkg(genesis.clone()))],
+    };
+    let schedule =
+        Schedule::from_observation_schedule(&mut env, &ScheduleOptions::default(), obs_schedule);
+    unwrap!(env.execute_schedule(schedule));
+
+    // Then vote for the observations to be signed with them.
+    let obs_schedule = ObservationSchedule {
+        genesis: Genesis::new(genesis),
+        schedule: vec![
+            (0, ObservationEvent::Opaque(Transaction::new("ABCD"))),
+            (1, ObservationEvent::Opaque(Transaction::new("EFGH"))),
+        ],
+    };
+    let mut schedule =
+        Schedule::from_observation_schedule(&mut env, &ScheduleOptions::default(), obs_schedule);
+    // Account for the `DkgResult` block output by the first schedule.
+    schedule.min_observations += 1;
+    schedule.max_observations += 1;
+    unwrap!(env.execute_schedule(schedule));
+
+    // Every block after the `DkgResult` is signed with the generated keys.
+    for peer in env.network.running_non_malicious_peers() {
+        let mut blocks = peer
+            .blocks()
+            .skip_while(|block| !block.payload().is_dkg_result());
+        let public_key_set = match unwrap!(blocks.next()).payload() {
+            Observation::DkgResult { dkg_result, .. } => dkg_result.0.public_key_set.clone(),
+            _ => unreachable!(),
+        };
+
+        let signed_blocks = blocks.collect::<Vec<_>>();
+        assert_eq!(signed_blocks.len(), 2);
+        for block in signed_blocks {
+            assert!(block.verify_with(&public_key_set), "{:?}", block.payload());
+            assert_eq!(block.index(), block.chain_link().map(|link| link.index));
+        }
+    }
+}
+
+#[test]
+fn blocks_output_unsigned_with_mismatched_signature_settings() {
+    let config = ParsecConfig::default()
+        .with_consensus_mode(ConsensusMode::Single)
+        .with_block_signatures(true);
+    let mut env = Environment::with_config(SEED, config.clone());
+    let genesis: BTreeSet<_> = PeerId::named_peer_ids()[0..4].iter().cloned().collect();
+
+    // Only Alice signs the blocks, and the signatures need the shares of two peers.
+    for name in &["Bob", "Carol", "Dave"] {
+        env.network.set_peer_config(
+            PeerId::new(name),
+            config.clone().with_block_signatures(false),
+        );
+    }
+
+    let obs_schedule = ObservationSchedule {
+        genesis: Genesis::new(genesis.clone()),
+        schedule: vec![(0, ObservationEvent::StartDkg(genesis.clone()))],
+    };
+    let schedule =
+        Schedule::from_observation_schedule(&mut env, &ScheduleOptions::default(), obs_schedule);
+    unwrap!(env.execute_schedule(schedule));
+
+    let obs_schedule = ObservationSchedule {
+        genesis: Genesis::new(genesis),
+        schedule: vec![
+            (0, ObservationEvent::Opaque(Transaction::new("ABCD"))),
+            (1, ObservationEvent::Opaque(Transaction::new("EFGH"))),
+        ],
+    };
+    let mut schedule =
+        Schedule::from_observation_schedule(&mut env, &ScheduleOptions::default(), obs_schedule);
+    schedule.min_observations += 1;
+    schedule.max_observations += 1;
+    unwrap!(env.execute_schedule(schedule));
+
+    // Alice still outputs the blocks after waiting for the missing shares, unsigned.
+    for peer in env.network.running_non_malicious_peers() {
+        let unsigned_blocks = peer
+            .blocks()
+            .skip_while(|block| !block.payload().is_dkg_result())
+            .skip(1)
+            .filter(|block| block.signature().is_none())
+            .count();
+        assert_eq!(unsigned_blocks, 2, "{:?}", peer.id());
+    }
+}
+
 #[test]
 fn add_many_peers_and_vote() {
     let mut env = Environment::new(SEED);
@@ -523,6 +627,98 @@ fn consensus_mode_single() {
     unwrap!(env.execute_schedule(schedule));
 }
 
+#[test]
+fn blocks_verify_against_voters() {
+    for &consensus_mode in &[ConsensusMode::Supermajority, ConsensusMode::Single] {
+        let mut env = Environment::with_consensus_mode(SEED, consensus_mode);
+        let mut options = ScheduleOptions {
+            genesis_size: 4,
+            opaque_to_add: 3,
+            ..Default::default()
+        };
+        if consensus_mode == ConsensusMode::Single {
+            options.opaque_voters = Sampling::Constant(1);
+        }
+        let schedule = Schedule::new(&mut env, &options);
+        unwrap!(env.execute_schedule(schedule));
+
+        let voters: BTreeSet<_> = env
+            .network
+            .running_non_malicious_peers()
+            .map(|peer| peer.id().clone())
+            .collect();
+        for peer in env.network.running_non_malicious_peers() {
+            for block in peer.blocks() {
+                unwrap!(block.verify(&voters, consensus_mode));
+
+                // Not valid for voters which don't include all the signers.
+                let signer = unwrap!(block.proofs().iter().next()).public_id();
+                let other_voters = voters.iter().filter(|id| *id != signer).cloned().collect();
+                assert_eq!(
+                    block.verify(&other_voters, consensus_mode),
+                    Err(Error::UnknownPeer)
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn three_quarters_quorum() {
+    let quorum_policy = unwrap!(QuorumPolicy::new(3, 4));
+    let config = ParsecConfig::default().with_quorum_policy(quorum_policy);
+    let mut env = Environment::with_config(SEED, config);
+    let options = ScheduleOptions {
+        genesis_size: 8,
+        opaque_to_add: 5,
+        ..Default::default()
+    };
+    let schedule = Schedule::new(&mut env, &options);
+    unwrap!(env.execute_schedule(schedule));
+
+    let voters: BTreeSet<_> = env
+        .network
+        .running_non_malicious_peers()
+        .map(|peer| peer.id().clone())
+        .collect();
+    for peer in env.network.running_non_malicious_peers() {
+        for block in peer.blocks() {
+            assert!(quorum_policy.is_quorum(block.proofs().len(), voters.len()));
+            unwrap!(block.verify_with_policy(&voters, ConsensusMode::Supermajority, quorum_policy));
+        }
+    }
+}
+
+#[test]
+fn chained_blocks() {
+    let config = ParsecConfig::default().with_chained_blocks(true);
+    let mut env = Environment::with_config(SEED, config);
+    let options = ScheduleOptions {
+        genesis_size: 4,
+        peers_to_add: 2,
+        opaque_to_add: 5,
+        ..Default::default()
+    };
+    let schedule = Schedule::new(&mut env, &options);
+    unwrap!(env.execute_schedule(schedule));
+
+    let first_peer = unwrap!(env.network.running_non_malicious_peers().next());
+    let hashes: Vec<_> = first_peer.blocks().map(Block::hash).collect();
+    for peer in env.network.running_non_malicious_peers() {
+        let blocks: Vec<_> = peer.blocks().collect();
+        unwrap!(verify_chain(blocks.iter().cloned()));
+        assert_eq!(unwrap!(blocks[0].chain_link()).previous, None);
+        assert_eq!(
+            blocks.iter().map(|block| block.hash()).collect::<Vec<_>>(),
+            hashes
+        );
+
+        // A segment with a block missing doesn't verify.
+        let segment = blocks.iter().cloned().skip(1).step_by(2);
+        assert_eq!(verify_chain(segment), Err(Error::BrokenChain));
+    }
+}
+
 #[test]
 fn extensive_dynamic_membership() {
     use parsec::dev_utils::ObservationEvent::*;
@@ -593,6 +789,94 @@ fn consensus_with_fork() {
     unwrap!(env.execute_schedule(schedule));
 }
 
+#[test]
+fn consensus_with_fork_and_removal_vote() {
+    let config =
+        ParsecConfig::default().with_removal_threshold(MaliceKind::Fork, NonZeroUsize::new(1));
+    let mut env = Environment::with_config(SEED, config);
+    let options = ScheduleOptions {
+        genesis_size: 5,
+        malicious_genesis_count: 1,
+        opaque_to_add: 2,
+        ..Default::default()
+    };
+    let schedule = Schedule::new(&mut env, &options);
+    unwrap!(env.execute_schedule(schedule));
+
+    // Once the accusation of the fork is consensused, the good peers vote to remove the malicious
+    // one, linking their vote to the accusation.
+    let malicious_id = unwrap!(env
+        .network
+        .peers
+        .values()
+        .find(|peer| peer.is_malicious())
+        .map(|peer| peer.id().clone()));
+    for peer in env.network.running_non_malicious_peers() {
+        let removal_info = unwrap!(peer
+            .blocks_payloads()
+            .into_iter()
+            .chain(peer.unpolled_observations())
+            .find_map(|payload| match payload {
+                Observation::Remove {
+                    peer_id,
+                    related_info,
+                } if *peer_id == malicious_id => Some(related_info.clone()),
+                _ => None,
+            }));
+        let accusation: Observation<Transaction, PeerId> =
+            unwrap!(serialisation::deserialise(&removal_info));
+        match accusation {
+            Observation::Accusation { offender, malice } => {
+                assert_eq!(offender, malicious_id);
+                assert_eq!(malice.kind(), MaliceKind::Fork);
+            }
+            _ => panic!("Unexpected related info {:?}", accusation),
+        }
+    }
+}
+
+#[test]
+fn consensus_with_fork_below_removal_threshold() {
+    let config =
+        ParsecConfig::default().with_removal_threshold(MaliceKind::Fork, NonZeroUsize::new(2));
+    let mut env = Environment::with_config(SEED, config);
+    let options = ScheduleOptions {
+        genesis_size: 5,
+        malicious_genesis_count: 1,
+        opaque_to_add: 2,
+        ..Default::default()
+    };
+    let schedule = Schedule::new(&mut env, &options);
+    unwrap!(env.execute_schedule(schedule));
+
+    // The accusation of the single fork is consensused, so the malicious peer is no longer
+    // gossiped with, but that's below the threshold, so no one votes to remove it.
+    let malicious_id = unwrap!(env
+        .network
+        .peers
+        .values()
+        .find(|peer| peer.is_malicious())
+        .map(|peer| peer.id().clone()));
+    for peer in env.network.running_non_malicious_peers() {
+        let payloads = peer
+            .blocks_payloads()
+            .into_iter()
+            .chain(peer.unpolled_observations())
+            .collect::<Vec<_>>();
+        assert!(payloads.iter().any(|payload| match payload {
+            Observation::Accusation { offender, .. } => *offender == malicious_id,
+            _ => false,
+        }));
+        assert!(!payloads.iter().any(|payload| match payload {
+            Observation::Remove { peer_id, .. } => *peer_id == malicious_id,
+            _ => false,
+        }));
+        assert!(!peer
+            .gossip_recipients()
+            .any(|peer_id| *peer_id == malicious_id));
+    }
+}
+
 #[test]
 fn grow_network_from_two_nodes() {
     let mut env = Environment::new(SEED);